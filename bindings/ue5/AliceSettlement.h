@@ -2,7 +2,7 @@
 // License: AGPL-3.0-only
 // Author: Moroya Sakamoto
 //
-// 22 extern "C" declarations + RAII wrapper classes
+// 23 extern "C" declarations + RAII wrapper classes
 
 #pragma once
 
@@ -54,7 +54,7 @@ void     alice_netting_engine_destroy(void* engine);
 
 // ClearingHouse
 void*    alice_clearing_house_new();
-void     alice_clearing_house_register_account(void* ch, uint64_t id, int64_t initial_balance);
+int32_t  alice_clearing_house_register_account(void* ch, uint64_t id, int64_t initial_balance);
 int64_t  alice_clearing_house_get_balance(const void* ch, uint64_t id);
 int32_t  alice_clearing_house_clear_obligation(void* ch, uint64_t symbol_hash,
              uint64_t deliverer_id, uint64_t receiver_id, uint64_t net_quantity,
@@ -64,6 +64,8 @@ void     alice_clearing_house_destroy(void* ch);
 // MarginEngine
 void*    alice_margin_engine_new(double initial_margin_rate, double variation_margin_rate,
              int64_t margin_floor);
+int32_t  alice_margin_engine_set_mark_price(void* engine, uint64_t symbol_hash,
+             int64_t mark_price);
 int32_t  alice_margin_engine_compute_obligation(const void* engine, uint64_t deliverer_id,
              uint64_t receiver_id, uint64_t net_quantity, int64_t net_payment,
              FfiMarginRequirement* out);
@@ -120,7 +122,7 @@ public:
         if (this != &o) { if (ptr_) alice_clearing_house_destroy(ptr_); ptr_ = std::exchange(o.ptr_, nullptr); }
         return *this;
     }
-    void RegisterAccount(uint64_t id, int64_t balance) { alice_clearing_house_register_account(ptr_, id, balance); }
+    int32_t RegisterAccount(uint64_t id, int64_t balance) { return alice_clearing_house_register_account(ptr_, id, balance); }
     int64_t GetBalance(uint64_t id) const { return alice_clearing_house_get_balance(ptr_, id); }
     int32_t ClearObligation(uint64_t sym, uint64_t del, uint64_t rec,
                             uint64_t qty, int64_t pay, uint32_t cnt) {
@@ -141,6 +143,9 @@ public:
         if (this != &o) { if (ptr_) alice_margin_engine_destroy(ptr_); ptr_ = std::exchange(o.ptr_, nullptr); }
         return *this;
     }
+    int32_t SetMarkPrice(uint64_t symbol, int64_t mark) {
+        return alice_margin_engine_set_mark_price(ptr_, symbol, mark);
+    }
     FfiMarginRequirement ComputeObligation(uint64_t del, uint64_t rec,
                                            uint64_t qty, int64_t pay) {
         FfiMarginRequirement req{};
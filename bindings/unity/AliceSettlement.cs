@@ -2,7 +2,7 @@
 // License: AGPL-3.0-only
 // Author: Moroya Sakamoto
 //
-// 22 DllImport + IDisposable RAII wrappers
+// 23 DllImport + IDisposable RAII wrappers
 
 using System;
 using System.Runtime.InteropServices;
@@ -68,7 +68,7 @@ namespace Alice.Settlement
 
         // ClearingHouse
         [DllImport(Lib)] public static extern IntPtr alice_clearing_house_new();
-        [DllImport(Lib)] public static extern void alice_clearing_house_register_account(
+        [DllImport(Lib)] public static extern int alice_clearing_house_register_account(
             IntPtr ch, ulong id, long initialBalance);
         [DllImport(Lib)] public static extern long alice_clearing_house_get_balance(
             IntPtr ch, ulong id);
@@ -80,6 +80,8 @@ namespace Alice.Settlement
         // MarginEngine
         [DllImport(Lib)] public static extern IntPtr alice_margin_engine_new(
             double initialMarginRate, double variationMarginRate, long marginFloor);
+        [DllImport(Lib)] public static extern int alice_margin_engine_set_mark_price(
+            IntPtr engine, ulong symbolHash, long markPrice);
         [DllImport(Lib)] public static extern int alice_margin_engine_compute_obligation(
             IntPtr engine, ulong delivererId, ulong receiverId,
             ulong netQuantity, long netPayment, out FfiMarginRequirement result);
@@ -138,7 +140,7 @@ namespace Alice.Settlement
     {
         private IntPtr _ptr;
         public ClearingHouse() => _ptr = Native.alice_clearing_house_new();
-        public void RegisterAccount(ulong id, long initialBalance)
+        public int RegisterAccount(ulong id, long initialBalance)
             => Native.alice_clearing_house_register_account(_ptr, id, initialBalance);
         public long GetBalance(ulong id) => Native.alice_clearing_house_get_balance(_ptr, id);
         public int ClearObligation(ulong symbolHash, ulong delivererId, ulong receiverId,
@@ -154,6 +156,8 @@ namespace Alice.Settlement
         private IntPtr _ptr;
         public MarginEngine(double initialRate, double variationRate, long floor)
             => _ptr = Native.alice_margin_engine_new(initialRate, variationRate, floor);
+        public int SetMarkPrice(ulong symbolHash, long markPrice)
+            => Native.alice_margin_engine_set_mark_price(_ptr, symbolHash, markPrice);
         public FfiMarginRequirement ComputeObligation(
             ulong delivererId, ulong receiverId, ulong netQuantity, long netPayment)
         {
@@ -0,0 +1,484 @@
+// ALICE-Settlement — Cheapest-to-deliver collateral allocation
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Moroya Sakamoto
+
+use std::collections::{HashMap, HashSet};
+
+use crate::collateral::{limited_issuer, CollateralAccount, CollateralType, ConcentrationLimits};
+use crate::margin::MarginRequirement;
+
+// ── Preferences ────────────────────────────────────────────────────────
+
+/// Member preferences steering which collateral is delivered first.
+#[derive(Debug, Clone)]
+pub struct AllocationPreferences {
+    /// Opportunity cost of delivering each collateral type, in bps of market
+    /// value, indexed by `CollateralType as usize`.
+    pub type_cost_bps: [u32; 4],
+    /// Per-asset cost overriding `type_cost_bps`.
+    pub asset_cost_bps: HashMap<u64, u32>,
+    /// Per-asset priority; higher priorities are delivered before any cost
+    /// comparison.  Assets not listed have priority 0.
+    pub priority: HashMap<u64, i32>,
+    /// Assets that must never be delivered.
+    pub excluded_assets: HashSet<u64>,
+}
+
+impl Default for AllocationPreferences {
+    fn default() -> Self {
+        Self {
+            // Members would rather keep cash and sovereigns than equities.
+            type_cost_bps: [50, 20, 10, 5],
+            asset_cost_bps: HashMap::new(),
+            priority: HashMap::new(),
+            excluded_assets: HashSet::new(),
+        }
+    }
+}
+
+impl AllocationPreferences {
+    /// Cost of delivering `asset_id` of `collateral_type`, in bps of market value.
+    #[inline]
+    #[must_use]
+    pub fn cost_bps(&self, asset_id: u64, collateral_type: CollateralType) -> u32 {
+        self.asset_cost_bps
+            .get(&asset_id)
+            .copied()
+            .unwrap_or(self.type_cost_bps[collateral_type as usize])
+    }
+}
+
+// ── Plan ───────────────────────────────────────────────────────────────
+
+/// One delivery of an inventory holding towards a margin requirement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Allocation {
+    /// Requirement (pool or account) receiving the collateral.
+    pub requirement_id: u64,
+    /// Inventory account the collateral is taken from.
+    pub source_account_id: u64,
+    /// Asset delivered.
+    pub asset_id: u64,
+    /// Collateral type of the asset.
+    pub collateral_type: CollateralType,
+    /// Quantity delivered.
+    pub quantity: i64,
+    /// Market value delivered.
+    pub market_value: i64,
+    /// Post-haircut value delivered.
+    pub adjusted_value: i64,
+    /// Opportunity cost of the delivery.
+    pub cost: i64,
+}
+
+/// Coverage achieved for one margin requirement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequirementCoverage {
+    /// Requirement (pool or account) ID.
+    pub requirement_id: u64,
+    /// Required margin.
+    pub requirement: i64,
+    /// Post-haircut value allocated.
+    pub allocated: i64,
+    /// Uncovered amount (zero when fully covered).
+    pub shortfall: i64,
+}
+
+impl RequirementCoverage {
+    /// True when the allocation covers the requirement.
+    #[inline]
+    #[must_use]
+    pub const fn is_covered(&self) -> bool {
+        self.shortfall == 0
+    }
+}
+
+/// Output of [`AllocationEngine::allocate`].
+#[derive(Debug, Clone, Default)]
+pub struct AllocationPlan {
+    /// Deliveries, grouped by requirement in input order.
+    pub allocations: Vec<Allocation>,
+    /// Coverage per requirement, in input order.
+    pub coverage: Vec<RequirementCoverage>,
+    /// Sum of allocation costs.
+    pub total_cost: i64,
+}
+
+impl AllocationPlan {
+    /// True when every requirement is covered.
+    #[must_use]
+    pub fn is_fully_covered(&self) -> bool {
+        self.coverage.iter().all(RequirementCoverage::is_covered)
+    }
+
+    /// Sum of uncovered amounts.
+    #[must_use]
+    pub fn total_shortfall(&self) -> i64 {
+        self.coverage.iter().map(|c| c.shortfall).sum()
+    }
+
+    /// Deliveries made towards `requirement_id`.
+    pub fn allocations_for(&self, requirement_id: u64) -> impl Iterator<Item = &Allocation> {
+        self.allocations
+            .iter()
+            .filter(move |a| a.requirement_id == requirement_id)
+    }
+}
+
+// ── Engine ─────────────────────────────────────────────────────────────
+
+/// An inventory holding available for delivery.
+struct Candidate {
+    source_account_id: u64,
+    asset_id: u64,
+    collateral_type: CollateralType,
+    /// Issuer subject to issuer limits, if any.
+    issuer_id: Option<u64>,
+    price: i64,
+    /// `10_000 - haircut_bps`.
+    retained_bps: i64,
+    cost_bps: u32,
+    priority: i32,
+    available: i64,
+}
+
+impl Candidate {
+    /// Post-haircut value of `quantity` units.
+    #[inline]
+    fn adjusted(&self, quantity: i64) -> i64 {
+        (quantity as i128 * self.price as i128 * self.retained_bps as i128 / 10_000) as i64
+    }
+
+    /// Cost per unit of post-haircut value, scaled by 10⁴.
+    #[inline]
+    fn cost_per_adjusted(&self) -> u64 {
+        self.cost_bps as u64 * 10_000 / self.retained_bps as u64
+    }
+
+    /// Smallest quantity whose post-haircut value reaches `value`.
+    #[inline]
+    fn units_for(&self, value: i64) -> i64 {
+        let per_unit = self.price as i128 * self.retained_bps as i128;
+        ((value as i128 * 10_000 + per_unit - 1) / per_unit) as i64
+    }
+
+    /// Largest quantity whose post-haircut value stays within `value`.
+    #[inline]
+    fn units_within(&self, value: i64) -> i64 {
+        let per_unit = self.price as i128 * self.retained_bps as i128;
+        (value as i128 * 10_000 / per_unit).min(i64::MAX as i128) as i64
+    }
+}
+
+/// Greedy cheapest-to-deliver collateral allocator.
+///
+/// Holdings are ranked by priority, then by cost per unit of post-haircut
+/// value (so a heavily haircut asset is dearer than its cost bps suggests).
+/// Requirements are filled in input order from the cheapest holdings first.
+/// Each collateral type and issuer may supply at most its concentration limit
+/// of a requirement (share and absolute caps), so a fully covered requirement
+/// never breaches [`ConcentrationLimits`] measured against that requirement.
+#[derive(Debug, Clone, Default)]
+pub struct AllocationEngine {
+    /// Per-requirement concentration limits.
+    pub limits: ConcentrationLimits,
+    /// Member preferences.
+    pub preferences: AllocationPreferences,
+}
+
+impl AllocationEngine {
+    /// Create an engine.
+    #[must_use]
+    pub const fn new(limits: ConcentrationLimits, preferences: AllocationPreferences) -> Self {
+        Self {
+            limits,
+            preferences,
+        }
+    }
+
+    /// Allocate `inventories` to cover `requirements`.
+    ///
+    /// Requirements for the same account are treated separately; requirements
+    /// with a non-positive `total_margin` receive nothing.
+    #[must_use]
+    pub fn allocate(
+        &self,
+        inventories: &[CollateralAccount],
+        requirements: &[MarginRequirement],
+    ) -> AllocationPlan {
+        let mut candidates = self.candidates(inventories);
+        let mut plan = AllocationPlan::default();
+
+        for req in requirements {
+            let target = req.total_margin.max(0);
+            let mut allocated = 0_i64;
+            let mut by_type = [0_i64; 4];
+            let mut by_issuer: HashMap<u64, i64> = HashMap::new();
+            let issuer_cap = self.limits.issuer_limit_value(target);
+
+            for c in &mut candidates {
+                let need = target - allocated;
+                if need <= 0 {
+                    break;
+                }
+                if c.available == 0 {
+                    continue;
+                }
+                let ti = c.collateral_type as usize;
+                let type_room =
+                    self.limits.type_limit_value(c.collateral_type, target) - by_type[ti];
+                let issuer_room = c.issuer_id.map_or(i64::MAX, |id| {
+                    issuer_cap - by_issuer.get(&id).copied().unwrap_or(0)
+                });
+                let quantity = c
+                    .available
+                    .min(c.units_for(need))
+                    .min(c.units_within(type_room.min(issuer_room)));
+                if quantity <= 0 {
+                    continue;
+                }
+
+                let adjusted_value = c.adjusted(quantity);
+                let market_value = quantity.saturating_mul(c.price);
+                let cost = (market_value as i128 * c.cost_bps as i128 / 10_000) as i64;
+                c.available -= quantity;
+                allocated += adjusted_value;
+                by_type[ti] += adjusted_value;
+                if let Some(id) = c.issuer_id {
+                    *by_issuer.entry(id).or_insert(0) += adjusted_value;
+                }
+                plan.total_cost += cost;
+                plan.allocations.push(Allocation {
+                    requirement_id: req.account_id,
+                    source_account_id: c.source_account_id,
+                    asset_id: c.asset_id,
+                    collateral_type: c.collateral_type,
+                    quantity,
+                    market_value,
+                    adjusted_value,
+                    cost,
+                });
+            }
+
+            plan.coverage.push(RequirementCoverage {
+                requirement_id: req.account_id,
+                requirement: target,
+                allocated,
+                shortfall: (target - allocated).max(0),
+            });
+        }
+        plan
+    }
+
+    /// Deliverable holdings in allocation order.
+    fn candidates(&self, inventories: &[CollateralAccount]) -> Vec<Candidate> {
+        let prefs = &self.preferences;
+        let mut candidates: Vec<Candidate> = inventories
+            .iter()
+            .flat_map(|inv| {
+                inv.holdings().into_iter().filter_map(move |h| {
+                    let retained_bps = 10_000 - inv.holding_haircut_bps(&h) as i64;
+                    if retained_bps <= 0
+                        || h.price <= 0
+                        || h.quantity <= 0
+                        || prefs.excluded_assets.contains(&h.asset_id)
+                    {
+                        return None;
+                    }
+                    Some(Candidate {
+                        source_account_id: inv.account_id(),
+                        asset_id: h.asset_id,
+                        collateral_type: h.collateral_type,
+                        issuer_id: limited_issuer(&h),
+                        price: h.price,
+                        retained_bps,
+                        cost_bps: prefs.cost_bps(h.asset_id, h.collateral_type),
+                        priority: prefs.priority.get(&h.asset_id).copied().unwrap_or(0),
+                        available: h.quantity,
+                    })
+                })
+            })
+            .collect();
+        candidates.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then(a.cost_per_adjusted().cmp(&b.cost_per_adjusted()))
+                .then(a.source_account_id.cmp(&b.source_account_id))
+                .then(a.asset_id.cmp(&b.asset_id))
+        });
+        candidates
+    }
+}
+
+// ── Tests ──────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collateral::{CollateralAsset, CreditRating, HaircutConfig, NO_CURRENCY};
+
+    fn requirement(account_id: u64, total_margin: i64) -> MarginRequirement {
+        MarginRequirement {
+            account_id,
+            initial_margin: total_margin,
+            variation_margin: 0,
+            stress_margin: 0,
+            total_margin,
+            content_hash: 0,
+        }
+    }
+
+    fn asset(asset_id: u64, collateral_type: CollateralType, price: i64) -> CollateralAsset {
+        CollateralAsset {
+            asset_id,
+            collateral_type,
+            issuer_id: asset_id,
+            rating: CreditRating::Aaa,
+            maturity_days: 0,
+            currency: NO_CURRENCY,
+            volatility_bps: 0,
+            price,
+        }
+    }
+
+    #[test]
+    fn cheapest_asset_delivered_first() {
+        let mut inv = CollateralAccount::new(1, HaircutConfig::default());
+        inv.deposit(CollateralType::Cash, 10_000);
+        inv.deposit_asset(&asset(0x10, CollateralType::CorporateBond, 100), 100);
+
+        let engine = AllocationEngine::new(
+            ConcentrationLimits::unlimited(),
+            AllocationPreferences::default(),
+        );
+        let plan = engine.allocate(&[inv], &[requirement(9, 4_500)]);
+
+        assert!(plan.is_fully_covered());
+        assert_eq!(plan.allocations.len(), 1);
+        let a = &plan.allocations[0];
+        assert_eq!(a.asset_id, 0x10);
+        // Corporate bonds retain 90%: 50 units × 100 × 0.9 = 4500.
+        assert_eq!(a.quantity, 50);
+        assert_eq!(a.adjusted_value, 4_500);
+        assert_eq!(a.cost, 5);
+        assert_eq!(plan.total_cost, 5);
+    }
+
+    #[test]
+    fn cost_accounts_for_haircut() {
+        let mut inv = CollateralAccount::new(1, HaircutConfig::default());
+        inv.deposit(CollateralType::Cash, 10_000);
+        inv.deposit(CollateralType::Equity, 10_000);
+        let prefs = AllocationPreferences {
+            // Equal headline cost: the 25% equity haircut makes it dearer.
+            type_cost_bps: [10, 10, 10, 10],
+            ..AllocationPreferences::default()
+        };
+        let plan = AllocationEngine::new(ConcentrationLimits::unlimited(), prefs)
+            .allocate(&[inv], &[requirement(9, 100)]);
+        assert_eq!(plan.allocations[0].collateral_type, CollateralType::Cash);
+    }
+
+    #[test]
+    fn concentration_caps_each_type() {
+        let mut inv = CollateralAccount::new(1, HaircutConfig::default());
+        inv.deposit(CollateralType::Cash, 10_000);
+        inv.deposit(CollateralType::Equity, 10_000);
+
+        let engine = AllocationEngine::default();
+        let plan = engine.allocate(&[inv], &[requirement(9, 1_000)]);
+
+        assert!(plan.is_fully_covered());
+        let equity: i64 = plan
+            .allocations_for(9)
+            .filter(|a| a.collateral_type == CollateralType::Equity)
+            .map(|a| a.adjusted_value)
+            .sum();
+        // Equity is cheapest but capped at 30% of the requirement.
+        assert_eq!(equity, 300);
+        assert_eq!(plan.coverage[0].allocated, 1_000);
+    }
+
+    #[test]
+    fn issuer_and_absolute_caps_respected() {
+        let mut inv = CollateralAccount::new(1, HaircutConfig::default());
+        inv.deposit(CollateralType::Cash, 10_000);
+        inv.deposit_asset(&asset(0x10, CollateralType::Equity, 4), 10_000);
+        inv.deposit_asset(&asset(0x11, CollateralType::Equity, 4), 10_000);
+        let mut limits = ConcentrationLimits {
+            issuer_max_bps: 1_800,
+            ..ConcentrationLimits::unlimited()
+        };
+        limits.type_max_value[CollateralType::Equity as usize] = Some(300);
+
+        let plan = AllocationEngine::new(limits, AllocationPreferences::default())
+            .allocate(&[inv], &[requirement(9, 1_000)]);
+        let delivered: Vec<(u64, i64)> = plan
+            .allocations
+            .iter()
+            .map(|a| (a.asset_id, a.adjusted_value))
+            .collect();
+        // Each unit retains 3: issuer 0x10 capped at 180, equities in total at
+        // 300, cash covers the rest.
+        assert_eq!(delivered, vec![(0x10, 180), (0x11, 120), (0, 700)]);
+        assert!(plan.is_fully_covered());
+    }
+
+    #[test]
+    fn preferences_priority_and_exclusion() {
+        let mut inv = CollateralAccount::new(1, HaircutConfig::default());
+        inv.deposit(CollateralType::Cash, 1_000);
+        inv.deposit_asset(&asset(0x10, CollateralType::CorporateBond, 10), 1_000);
+        inv.deposit_asset(&asset(0x11, CollateralType::CorporateBond, 10), 1_000);
+
+        let mut prefs = AllocationPreferences::default();
+        prefs.priority.insert(0, 1);
+        prefs.excluded_assets.insert(0x10);
+        let plan = AllocationEngine::new(ConcentrationLimits::unlimited(), prefs)
+            .allocate(&[inv], &[requirement(9, 1_500)]);
+
+        let delivered: Vec<(u64, i64)> = plan
+            .allocations
+            .iter()
+            .map(|a| (a.asset_id, a.quantity))
+            .collect();
+        // Cash first by priority, then 500 / 9 → 56 units of 0x11.
+        assert_eq!(delivered, vec![(0, 1_000), (0x11, 56)]);
+        assert!(plan.is_fully_covered());
+    }
+
+    #[test]
+    fn inventory_shared_across_requirements() {
+        let mut a = CollateralAccount::new(1, HaircutConfig::default());
+        a.deposit(CollateralType::Cash, 600);
+        let mut b = CollateralAccount::new(2, HaircutConfig::default());
+        b.deposit(CollateralType::Cash, 300);
+
+        let engine = AllocationEngine::new(
+            ConcentrationLimits::unlimited(),
+            AllocationPreferences::default(),
+        );
+        let plan = engine.allocate(&[a, b], &[requirement(7, 500), requirement(8, 500)]);
+
+        assert!(plan.coverage[0].is_covered());
+        assert_eq!(plan.coverage[1].allocated, 400);
+        assert_eq!(plan.coverage[1].shortfall, 100);
+        assert_eq!(plan.total_shortfall(), 100);
+        let sources: Vec<(u64, u64, i64)> = plan
+            .allocations
+            .iter()
+            .map(|x| (x.requirement_id, x.source_account_id, x.quantity))
+            .collect();
+        assert_eq!(sources, vec![(7, 1, 500), (8, 1, 100), (8, 2, 300)]);
+    }
+
+    #[test]
+    fn empty_inputs() {
+        let engine = AllocationEngine::default();
+        let plan = engine.allocate(&[], &[requirement(1, 0), requirement(2, -5)]);
+        assert!(plan.allocations.is_empty());
+        assert!(plan.is_fully_covered());
+        let plan = engine.allocate(&[], &[requirement(1, 10)]);
+        assert_eq!(plan.total_shortfall(), 10);
+    }
+}
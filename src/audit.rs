@@ -0,0 +1,334 @@
+// ALICE-Settlement — Balance conservation audit
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Moroya Sakamoto
+
+use crate::clearing::ClearingAccount;
+use crate::ledger::{GeneralLedger, LedgerAccount, LedgerMismatch};
+
+// ── Types ──────────────────────────────────────────────────────────────
+
+/// Running totals of cash entering and leaving the members' accounts.
+///
+/// Clearing, transfers and margin moves only shift funds between members, so
+/// member funds (balance plus margin held) must always equal
+/// [`expected_member_funds`](Self::expected_member_funds).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ExternalFlows {
+    /// Net funds set by registrations and restores.
+    pub adjustments: i128,
+    /// Cash paid in.
+    pub deposited: i128,
+    /// Cash paid out.
+    pub withdrawn: i128,
+    /// Fees charged to members.
+    pub fees: i128,
+    /// Defaulter margin consumed by the waterfall.
+    pub margin_drawn: i128,
+}
+
+impl ExternalFlows {
+    /// Member funds implied by the external flows.
+    #[inline]
+    #[must_use]
+    pub const fn expected_member_funds(&self) -> i128 {
+        self.adjustments + self.deposited - self.withdrawn - self.fees - self.margin_drawn
+    }
+}
+
+/// A broken invariant found by [`BalanceAuditor`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditViolation {
+    /// Member funds differ from those implied by external flows.
+    Conservation { expected: i128, actual: i128 },
+    /// The ledger does not net to zero.
+    LedgerUnbalanced { trial_balance: i128 },
+    /// A member's balance or margin disagrees with its ledger history.
+    LedgerMismatch(LedgerMismatch),
+    /// A member's balance is below zero.  Balances within the overdraft
+    /// limit are permitted but still reported.
+    NegativeBalance {
+        account_id: u64,
+        balance: i64,
+        overdraft_limit: i64,
+    },
+    /// A member holds negative margin.
+    NegativeMargin { account_id: u64, margin_held: i64 },
+}
+
+impl AuditViolation {
+    /// True for violations that indicate corrupted books rather than a
+    /// permitted overdraft.
+    #[must_use]
+    pub const fn is_critical(&self) -> bool {
+        match self {
+            Self::NegativeBalance {
+                balance,
+                overdraft_limit,
+                ..
+            } => (*balance as i128) < -(*overdraft_limit as i128),
+            _ => true,
+        }
+    }
+}
+
+/// Result of a balance audit.
+#[derive(Debug, Clone)]
+pub struct AuditReport {
+    /// Number of member accounts checked.
+    pub accounts_checked: usize,
+    /// Sum of balance plus margin held across members.
+    pub member_funds: i128,
+    /// Member funds implied by external flows.
+    pub expected_member_funds: i128,
+    /// Sum of all ledger balances (zero when consistent).
+    pub trial_balance: i128,
+    /// Every violation found, ordered by kind then account.
+    pub violations: Vec<AuditViolation>,
+}
+
+impl AuditReport {
+    /// True when no violation was found.
+    #[inline]
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// True when any violation is critical (see
+    /// [`AuditViolation::is_critical`]).
+    #[must_use]
+    pub fn has_critical(&self) -> bool {
+        self.violations.iter().any(AuditViolation::is_critical)
+    }
+}
+
+// ── Auditor ────────────────────────────────────────────────────────────
+
+/// Checks clearing books for conservation of cash.
+///
+/// The audit is O(n log n) in the number of accounts, which are sorted by
+/// id so violations are reported deterministically; ledger balances are
+/// maintained incrementally, so it is cheap enough to run after every
+/// clearing batch.
+pub struct BalanceAuditor;
+
+impl BalanceAuditor {
+    /// Audit `accounts` against `ledger` and `flows`.
+    #[must_use]
+    pub fn audit<'a, I>(accounts: I, ledger: &GeneralLedger, flows: &ExternalFlows) -> AuditReport
+    where
+        I: IntoIterator<Item = &'a ClearingAccount>,
+    {
+        let mut accounts: Vec<&ClearingAccount> = accounts.into_iter().collect();
+        accounts.sort_unstable_by_key(|a| a.account_id);
+
+        let member_funds: i128 = accounts
+            .iter()
+            .map(|a| a.balance as i128 + a.margin_held as i128)
+            .sum();
+        let expected_member_funds = flows.expected_member_funds();
+        let trial_balance = ledger.trial_balance();
+
+        let mut violations = Vec::new();
+        if member_funds != expected_member_funds {
+            violations.push(AuditViolation::Conservation {
+                expected: expected_member_funds,
+                actual: member_funds,
+            });
+        }
+        if trial_balance != 0 {
+            violations.push(AuditViolation::LedgerUnbalanced { trial_balance });
+        }
+        violations.extend(
+            ledger
+                .reconcile(accounts.iter().flat_map(|a| {
+                    [
+                        (LedgerAccount::MemberCash(a.account_id), a.balance),
+                        (LedgerAccount::MemberMargin(a.account_id), a.margin_held),
+                    ]
+                }))
+                .into_iter()
+                .map(AuditViolation::LedgerMismatch),
+        );
+        for a in &accounts {
+            if a.balance < 0 {
+                violations.push(AuditViolation::NegativeBalance {
+                    account_id: a.account_id,
+                    balance: a.balance,
+                    overdraft_limit: a.overdraft_limit,
+                });
+            }
+        }
+        for a in &accounts {
+            if a.margin_held < 0 {
+                violations.push(AuditViolation::NegativeMargin {
+                    account_id: a.account_id,
+                    margin_held: a.margin_held,
+                });
+            }
+        }
+
+        AuditReport {
+            accounts_checked: accounts.len(),
+            member_funds,
+            expected_member_funds,
+            trial_balance,
+            violations,
+        }
+    }
+}
+
+// ── Tests ──────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clearing::AccountStatus;
+    use crate::ledger::LedgerEntryKind;
+
+    fn account(account_id: u64, balance: i64, margin_held: i64) -> ClearingAccount {
+        ClearingAccount {
+            account_id,
+            balance,
+            margin_held,
+            status: AccountStatus::Active,
+            overdraft_limit: 0,
+        }
+    }
+
+    fn funded_ledger(accounts: &[ClearingAccount]) -> GeneralLedger {
+        let mut gl = GeneralLedger::new();
+        for a in accounts {
+            gl.post(
+                LedgerEntryKind::Adjustment,
+                LedgerAccount::MemberCash(a.account_id),
+                LedgerAccount::External,
+                a.balance,
+            );
+            gl.post(
+                LedgerEntryKind::Adjustment,
+                LedgerAccount::MemberMargin(a.account_id),
+                LedgerAccount::External,
+                a.margin_held,
+            );
+        }
+        gl
+    }
+
+    #[test]
+    fn clean_books() {
+        let accounts = [account(1, 100, 50), account(2, 200, 0)];
+        let flows = ExternalFlows {
+            adjustments: 350,
+            ..ExternalFlows::default()
+        };
+        let report = BalanceAuditor::audit(&accounts, &funded_ledger(&accounts), &flows);
+        assert!(report.is_clean());
+        assert_eq!(report.accounts_checked, 2);
+        assert_eq!(report.member_funds, 350);
+    }
+
+    #[test]
+    fn expected_funds_follow_flows() {
+        let flows = ExternalFlows {
+            adjustments: 1_000,
+            deposited: 300,
+            withdrawn: 100,
+            fees: 20,
+            margin_drawn: 80,
+        };
+        assert_eq!(flows.expected_member_funds(), 1_100);
+    }
+
+    #[test]
+    fn detects_created_cash() {
+        let accounts = [account(1, 100, 0)];
+        let ledger = funded_ledger(&accounts);
+        let inflated = [account(1, 150, 0)];
+        let flows = ExternalFlows {
+            adjustments: 100,
+            ..ExternalFlows::default()
+        };
+        let report = BalanceAuditor::audit(&inflated, &ledger, &flows);
+        assert_eq!(
+            report.violations,
+            vec![
+                AuditViolation::Conservation {
+                    expected: 100,
+                    actual: 150,
+                },
+                AuditViolation::LedgerMismatch(LedgerMismatch {
+                    account: LedgerAccount::MemberCash(1),
+                    ledger_balance: 100,
+                    book_balance: 150,
+                }),
+            ]
+        );
+        assert!(report.has_critical());
+    }
+
+    #[test]
+    fn negative_balance_within_overdraft_not_critical() {
+        let mut a = account(1, -50, 0);
+        a.overdraft_limit = 100;
+        let accounts = [a];
+        let flows = ExternalFlows {
+            adjustments: -50,
+            ..ExternalFlows::default()
+        };
+        let report = BalanceAuditor::audit(&accounts, &funded_ledger(&accounts), &flows);
+        assert_eq!(report.violations.len(), 1);
+        assert!(!report.has_critical());
+
+        let accounts = [account(1, -50, -5)];
+        let flows = ExternalFlows {
+            adjustments: -55,
+            ..ExternalFlows::default()
+        };
+        let report = BalanceAuditor::audit(&accounts, &funded_ledger(&accounts), &flows);
+        assert_eq!(report.violations.len(), 2);
+        assert!(report.violations.iter().all(AuditViolation::is_critical));
+    }
+
+    #[test]
+    fn ledger_history_mismatch_reported() {
+        let accounts = [account(1, 10, 0)];
+        let mut ledger = funded_ledger(&accounts);
+        // Reverse the funding in the ledger only.
+        ledger.post(
+            LedgerEntryKind::Adjustment,
+            LedgerAccount::External,
+            LedgerAccount::MemberCash(1),
+            10,
+        );
+        let flows = ExternalFlows {
+            adjustments: 10,
+            ..ExternalFlows::default()
+        };
+        let report = BalanceAuditor::audit(&accounts, &ledger, &flows);
+        assert_eq!(report.trial_balance, 0);
+        assert!(matches!(
+            report.violations[..],
+            [AuditViolation::LedgerMismatch(_)]
+        ));
+    }
+
+    #[test]
+    fn violations_ordered_by_account() {
+        let accounts = [account(3, -1, 0), account(1, -1, 0), account(2, -1, 0)];
+        let flows = ExternalFlows {
+            adjustments: -3,
+            ..ExternalFlows::default()
+        };
+        let report = BalanceAuditor::audit(&accounts, &funded_ledger(&accounts), &flows);
+        let ids: Vec<u64> = report
+            .violations
+            .iter()
+            .filter_map(|v| match v {
+                AuditViolation::NegativeBalance { account_id, .. } => Some(*account_id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+    }
+}
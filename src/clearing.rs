@@ -5,16 +5,219 @@
 
 use std::collections::HashMap;
 
+use crate::audit::{AuditReport, BalanceAuditor, ExternalFlows};
+use crate::fee::{FeeEngine, FeeObligation};
+use crate::journal::{JournalEntry, JournalEvent, SettlementJournal};
+use crate::ledger::{GeneralLedger, LedgerAccount, LedgerEntryKind, LedgerMismatch};
 use crate::netting::NetObligation;
+use crate::risk::{RiskChecker, RiskReport};
+use crate::snapshot::{ClearingSnapshot, SnapshotError};
+use crate::statement::Statement;
+use crate::waterfall::{WaterfallLayer, WaterfallResult};
+
+/// Lifecycle state of a clearing account.
+///
+/// Transitions are guarded by [`ClearingHouse`]:
+///
+/// ```text
+/// Active ⇄ Suspended
+///   │         │
+///   └──► InDefault ──► Closed
+///   └─────────┴────────► Closed
+/// ```
+///
+/// `Closed` is terminal. Closure requires a zero balance and no margin held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AccountStatus {
+    /// Account may clear obligations normally.
+    Active = 0,
+    /// Temporarily barred from clearing; may be reinstated.
+    Suspended = 1,
+    /// Member has been declared in default; awaiting default management.
+    InDefault = 2,
+    /// Account is closed and may not be used again.
+    Closed = 3,
+}
+
+impl AccountStatus {
+    /// `Ok` when an account `id` in this state may clear obligations.
+    pub(crate) const fn check_clearable(self, id: u64) -> Result<(), ClearingError> {
+        match self {
+            Self::Active => Ok(()),
+            Self::Suspended => Err(ClearingError::AccountSuspended(id)),
+            Self::InDefault => Err(ClearingError::AccountInDefault(id)),
+            Self::Closed => Err(ClearingError::AccountClosed(id)),
+        }
+    }
+
+    /// `Ok` when an account `id` in this state may move to `to`.
+    pub(crate) const fn check_transition(self, id: u64, to: Self) -> Result<(), ClearingError> {
+        let allowed = matches!(
+            (self, to),
+            (Self::Active, Self::Suspended)
+                | (Self::Suspended, Self::Active)
+                | (Self::Active | Self::Suspended, Self::InDefault)
+                | (
+                    Self::Active | Self::Suspended | Self::InDefault,
+                    Self::Closed
+                )
+        );
+        if allowed {
+            Ok(())
+        } else {
+            Err(ClearingError::InvalidStatusTransition {
+                account_id: id,
+                from: self,
+                to,
+            })
+        }
+    }
+}
 
 /// Account balance for clearing.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ClearingAccount {
     pub account_id: u64,
     /// Available balance in ticks (cash equivalent).
     pub balance: i64,
     /// Margin held.
     pub margin_held: i64,
+    /// Lifecycle state.
+    pub status: AccountStatus,
+    /// How far below zero withdrawals and transfers may take the balance.
+    pub overdraft_limit: i64,
+}
+
+impl ClearingAccount {
+    /// A newly registered `Active` account.
+    pub(crate) const fn open(account_id: u64, balance: i64) -> Self {
+        Self {
+            account_id,
+            balance,
+            margin_held: 0,
+            status: AccountStatus::Active,
+            overdraft_limit: 0,
+        }
+    }
+
+    /// `Ok` when the account may clear obligations.
+    const fn check_clearable(&self) -> Result<(), ClearingError> {
+        self.status.check_clearable(self.account_id)
+    }
+
+    /// `Ok` unless the account is closed.
+    const fn check_open(&self) -> Result<(), ClearingError> {
+        match self.status {
+            AccountStatus::Closed => Err(ClearingError::AccountClosed(self.account_id)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Verify that debiting `amount` keeps the account within its overdraft
+    /// limit.
+    const fn check_overdraft(&self, amount: i64) -> Result<(), ClearingError> {
+        let available = self.balance.saturating_add(self.overdraft_limit);
+        if available < amount {
+            return Err(ClearingError::InsufficientBalance {
+                account_id: self.account_id,
+                required: amount,
+                available,
+            });
+        }
+        Ok(())
+    }
+
+    /// Check a fee debit: any account that is not closed, within its
+    /// overdraft limit.
+    pub(crate) fn check_fee(&self, amount: i64) -> Result<(), ClearingError> {
+        self.check_open()?;
+        self.check_overdraft(amount)
+    }
+
+    /// Check moving `amount` from the balance into margin held.
+    pub(crate) fn check_margin_post(&self, amount: i64) -> Result<(), ClearingError> {
+        self.check_open()?;
+        if self.balance < amount {
+            return Err(ClearingError::InsufficientBalance {
+                account_id: self.account_id,
+                required: amount,
+                available: self.balance,
+            });
+        }
+        Ok(())
+    }
+
+    /// Check returning `amount` of margin held to the balance.
+    pub(crate) fn check_margin_release(&self, amount: i64) -> Result<(), ClearingError> {
+        self.check_clearable()?;
+        if self.margin_held < amount {
+            return Err(ClearingError::InsufficientMargin {
+                account_id: self.account_id,
+                required: amount,
+                available: self.margin_held,
+            });
+        }
+        Ok(())
+    }
+
+    /// Check that a defaulted account's margin covers a waterfall draw.
+    pub(crate) const fn check_waterfall_draw(&self, margin_draw: i64) -> Result<(), ClearingError> {
+        if !matches!(self.status, AccountStatus::InDefault) {
+            return Err(ClearingError::AccountNotInDefault(self.account_id));
+        }
+        if self.margin_held < margin_draw {
+            return Err(ClearingError::InsufficientMargin {
+                account_id: self.account_id,
+                required: margin_draw,
+                available: self.margin_held,
+            });
+        }
+        Ok(())
+    }
+
+    /// Check closing the account: it must hold no balance and no margin.
+    pub(crate) fn check_close(&self) -> Result<(), ClearingError> {
+        if !matches!(self.status, AccountStatus::Closed)
+            && (self.balance != 0 || self.margin_held != 0)
+        {
+            return Err(ClearingError::AccountNotEmpty {
+                account_id: self.account_id,
+                balance: self.balance,
+                margin_held: self.margin_held,
+            });
+        }
+        self.status
+            .check_transition(self.account_id, AccountStatus::Closed)
+    }
+}
+
+/// Account storage that the clearing checks and updates run against: the
+/// [`ClearingHouse`]'s account map, or the locked shards of a
+/// [`ShardedClearingHouse`](crate::sharded::ShardedClearingHouse).
+pub(crate) trait Accounts {
+    /// Account `id`, if it is held here.
+    fn account(&self, id: u64) -> Option<&ClearingAccount>;
+
+    /// Mutable account `id`, if it is held here.
+    fn account_mut(&mut self, id: u64) -> Option<&mut ClearingAccount>;
+
+    /// Account `id`, or [`ClearingError::AccountNotFound`].
+    fn lookup(&self, id: u64) -> Result<&ClearingAccount, ClearingError> {
+        self.account(id).ok_or(ClearingError::AccountNotFound(id))
+    }
+}
+
+impl Accounts for HashMap<u64, ClearingAccount> {
+    #[inline(always)]
+    fn account(&self, id: u64) -> Option<&ClearingAccount> {
+        self.get(&id)
+    }
+
+    #[inline(always)]
+    fn account_mut(&mut self, id: u64) -> Option<&mut ClearingAccount> {
+        self.get_mut(&id)
+    }
 }
 
 /// Error returned when clearing an obligation fails.
@@ -28,6 +231,69 @@ pub enum ClearingError {
         required: i64,
         available: i64,
     },
+    /// The account is already registered.
+    AccountExists(u64),
+    /// The account is suspended and may not clear obligations.
+    AccountSuspended(u64),
+    /// The account has been declared in default.
+    AccountInDefault(u64),
+    /// The account is closed.
+    AccountClosed(u64),
+    /// The requested lifecycle transition is not permitted.
+    InvalidStatusTransition {
+        account_id: u64,
+        from: AccountStatus,
+        to: AccountStatus,
+    },
+    /// The account cannot be closed while it still holds funds or margin.
+    AccountNotEmpty {
+        account_id: u64,
+        balance: i64,
+        margin_held: i64,
+    },
+    /// The member is in breach of its pre-clearing risk limits.
+    RiskLimitBreached(u64),
+    /// The amount must be strictly positive.
+    InvalidAmount(i64),
+    /// The account holds less margin than requested.
+    InsufficientMargin {
+        account_id: u64,
+        required: i64,
+        available: i64,
+    },
+    /// An idempotency key was reused for a different cash operation.
+    IdempotencyConflict(u64),
+    /// Applying the operation would overflow the account balance.
+    BalanceOverflow(u64),
+    /// The operation requires the member to have been declared in default.
+    AccountNotInDefault(u64),
+    /// A transfer names the same account as sender and receiver.
+    SelfTransfer(u64),
+}
+
+/// A cash movement requested through [`ClearingHouse`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CashOperation {
+    /// Cash paid in from outside the clearing house.
+    Deposit { account_id: u64, amount: i64 },
+    /// Cash paid out of the clearing house.
+    Withdraw { account_id: u64, amount: i64 },
+    /// Cash moved between two members.
+    Transfer {
+        from_id: u64,
+        to_id: u64,
+        amount: i64,
+    },
+}
+
+/// Outcome of an idempotent cash operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CashOutcome {
+    /// The operation was applied.
+    Applied,
+    /// The idempotency key was already used for the same operation; nothing
+    /// was changed.
+    Duplicate,
 }
 
 /// Per-obligation clearing outcome.
@@ -38,12 +304,33 @@ pub struct ClearingResult {
     pub error: Option<ClearingError>,
 }
 
+/// Per-fee charging outcome.
+#[derive(Debug, Clone)]
+pub struct FeeResult {
+    /// Fee that was charged or attempted.
+    pub fee: FeeObligation,
+    /// Whether the fee was debited.
+    pub success: bool,
+    /// Why the fee could not be debited, if it failed.
+    pub error: Option<ClearingError>,
+}
+
 /// Central clearing house.
 ///
 /// Maintains account balances and processes net obligations from the netting
 /// engine. On success, debits the deliverer and credits the receiver.
+///
+/// Every balance and margin movement is also posted to a double-entry
+/// [`GeneralLedger`], so account balances can be reconciled against it with
+/// [`reconcile_ledger`](Self::reconcile_ledger).  Every state change
+/// (registrations, clearing attempts, cash, margin and fees) is also recorded
+/// in the settlement journal at the time set by [`set_time`](Self::set_time).
 pub struct ClearingHouse {
     accounts: HashMap<u64, ClearingAccount>,
+    books: Books,
+    /// Balance and margin held of each account when the journal began
+    /// (the snapshot values after a restore).
+    journal_start: HashMap<u64, (i64, i64)>,
 }
 
 impl ClearingHouse {
@@ -53,22 +340,79 @@ impl ClearingHouse {
     pub fn new() -> Self {
         Self {
             accounts: HashMap::new(),
+            books: Books::new(),
+            journal_start: HashMap::new(),
         }
     }
 
-    /// Register an account with an initial balance.
+    /// Register a new account with an initial balance.
     ///
-    /// If the account already exists, the balance is replaced.
-    #[inline(always)]
-    pub fn register_account(&mut self, id: u64, initial_balance: i64) {
-        self.accounts.insert(
-            id,
-            ClearingAccount {
-                account_id: id,
-                balance: initial_balance,
-                margin_held: 0,
-            },
-        );
+    /// The balance is posted to the ledger against
+    /// [`LedgerAccount::External`].  An existing account is never replaced;
+    /// lifecycle changes go through the guarded transitions.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError::AccountExists`] if `id` is already
+    /// registered, whatever its status.
+    pub fn register_account(&mut self, id: u64, initial_balance: i64) -> Result<(), ClearingError> {
+        if self.accounts.contains_key(&id) {
+            return Err(ClearingError::AccountExists(id));
+        }
+        self.books.record_registration(id, initial_balance);
+        self.accounts
+            .insert(id, ClearingAccount::open(id, initial_balance));
+        Ok(())
+    }
+
+    /// Suspend an `Active` account.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the account is missing or not `Active`.
+    pub fn suspend_account(&mut self, id: u64) -> Result<(), ClearingError> {
+        self.transition(id, AccountStatus::Suspended)
+    }
+
+    /// Reinstate a `Suspended` account to `Active`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the account is missing or not `Suspended`.
+    pub fn reinstate_account(&mut self, id: u64) -> Result<(), ClearingError> {
+        self.transition(id, AccountStatus::Active)
+    }
+
+    /// Declare an `Active` or `Suspended` account in default.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the account is missing, already in
+    /// default, or closed.
+    pub fn declare_default(&mut self, id: u64) -> Result<(), ClearingError> {
+        self.transition(id, AccountStatus::InDefault)
+    }
+
+    /// Close an account.  The account must hold no balance and no margin.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the account is missing, already closed,
+    /// or not empty.
+    pub fn close_account(&mut self, id: u64) -> Result<(), ClearingError> {
+        self.accounts.lookup(id)?.check_close()?;
+        self.transition(id, AccountStatus::Closed)
+    }
+
+    /// Apply a guarded status transition.
+    fn transition(&mut self, id: u64, to: AccountStatus) -> Result<(), ClearingError> {
+        let from = self.accounts.lookup(id)?.status;
+        from.check_transition(id, to)?;
+        self.books.record_status(id, from, to);
+        if let Some(acc) = self.accounts.get_mut(&id) {
+            acc.status = to;
+        }
+        Ok(())
     }
 
     /// Look up an account by identifier.
@@ -80,67 +424,927 @@ impl ClearingHouse {
 
     /// Attempt to clear a single net obligation.
     ///
-    /// Checks that the deliverer has a balance of at least `net_payment`, then
-    /// transfers `net_payment` from deliverer to receiver.
+    /// Checks that both accounts are `Active` and that the deliverer has a
+    /// balance of at least `net_payment`, then transfers `net_payment` from
+    /// deliverer to receiver.
     ///
     /// # Errors
     ///
-    /// Returns [`ClearingError`] if the account is missing or has insufficient funds.
+    /// Returns [`ClearingError`] if an account is missing or not `Active`, or
+    /// the deliverer has insufficient funds.
     pub fn clear_obligation(&mut self, obligation: &NetObligation) -> Result<(), ClearingError> {
-        // Verify both accounts exist before mutating anything.
-        if !self.accounts.contains_key(&obligation.deliverer_id) {
-            return Err(ClearingError::AccountNotFound(obligation.deliverer_id));
+        let outcome = check_settlement(&self.accounts, obligation);
+        self.settle_checked(obligation, outcome)
+    }
+
+    /// Record a checked clearing attempt and, if it passed, apply its cash
+    /// leg.
+    fn settle_checked(
+        &mut self,
+        obligation: &NetObligation,
+        outcome: Result<(), ClearingError>,
+    ) -> Result<(), ClearingError> {
+        self.books.record_obligation(obligation, &outcome);
+        if outcome.is_ok() {
+            apply_settlement(&mut self.accounts, obligation);
+        }
+        outcome
+    }
+
+    /// Move `amount` from an account's balance into margin held.
+    ///
+    /// Margin may be posted by any account that is not closed, so suspended
+    /// or defaulted members can still top up.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the amount is not positive, the account is
+    /// missing or closed, or the balance is insufficient.
+    pub fn post_margin(&mut self, id: u64, amount: i64) -> Result<(), ClearingError> {
+        check_amount(amount)?;
+        self.accounts.lookup(id)?.check_margin_post(amount)?;
+        self.books.record_margin_posted(id, amount);
+        if let Some(acc) = self.accounts.get_mut(&id) {
+            acc.balance -= amount;
+            acc.margin_held += amount;
+        }
+        Ok(())
+    }
+
+    /// Return `amount` of margin held to an `Active` account's balance.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the amount is not positive, the account is
+    /// missing or not `Active`, or holds less margin than requested.
+    pub fn release_margin(&mut self, id: u64, amount: i64) -> Result<(), ClearingError> {
+        check_amount(amount)?;
+        self.accounts.lookup(id)?.check_margin_release(amount)?;
+        self.books.record_margin_released(id, amount);
+        if let Some(acc) = self.accounts.get_mut(&id) {
+            acc.margin_held -= amount;
+            acc.balance += amount;
+        }
+        Ok(())
+    }
+
+    /// Apply a waterfall result for a defaulted member.
+    ///
+    /// The defaulter-margin layer is taken from the defaulter's margin held;
+    /// every absorbing layer is posted to the ledger as a draw against the
+    /// member's default loss.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the account is missing, is not
+    /// [`AccountStatus::InDefault`], or holds less margin than the
+    /// defaulter-margin layer absorbed.
+    pub fn apply_waterfall(
+        &mut self,
+        defaulter_id: u64,
+        result: &WaterfallResult,
+    ) -> Result<(), ClearingError> {
+        self.apply_waterfall_draws(defaulter_id, result.absorbed_by_layer())
+    }
+
+    /// Apply per-layer waterfall draws (indexed by `WaterfallLayer as
+    /// usize`) and journal them as one event.
+    fn apply_waterfall_draws(
+        &mut self,
+        defaulter_id: u64,
+        absorbed: [i64; 5],
+    ) -> Result<(), ClearingError> {
+        let margin_draw = absorbed[WaterfallLayer::DefaulterMargin as usize];
+        self.accounts
+            .lookup(defaulter_id)?
+            .check_waterfall_draw(margin_draw)?;
+        self.books.record_waterfall(defaulter_id, absorbed);
+        if let Some(acc) = self.accounts.get_mut(&defaulter_id) {
+            acc.margin_held -= margin_draw;
+        }
+        Ok(())
+    }
+
+    /// Debit a fee from an account's balance into the clearing house's fee
+    /// income.  `symbol_hash` attributes the fee to an instrument (0 for
+    /// account-level fees).
+    ///
+    /// Fees are charged to any account that is not closed and honour the
+    /// account's overdraft limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the amount is not positive, the account is
+    /// missing or closed, or the fee exceeds the balance plus overdraft limit.
+    pub fn charge_fee(
+        &mut self,
+        id: u64,
+        symbol_hash: u64,
+        amount: i64,
+    ) -> Result<(), ClearingError> {
+        check_amount(amount)?;
+        self.accounts.lookup(id)?.check_fee(amount)?;
+        self.books.record_fee(id, symbol_hash, amount);
+        if let Some(acc) = self.accounts.get_mut(&id) {
+            acc.balance -= amount;
+        }
+        Ok(())
+    }
+
+    /// Build a statement of `id`'s activity in `[from_ns, to_ns)` from the
+    /// clearing journal, reconciled against the account's current state.
+    ///
+    /// After a [`restore`](Self::restore) the journal starts at the
+    /// snapshot, so the statement opens with the snapshot balances and
+    /// earlier activity is not itemised.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError::AccountNotFound`] if the account is unknown.
+    pub fn statement(&self, id: u64, from_ns: u64, to_ns: u64) -> Result<Statement, ClearingError> {
+        let acc = self
+            .accounts
+            .get(&id)
+            .ok_or(ClearingError::AccountNotFound(id))?;
+        Ok(Statement::from_journal(
+            &self.books.journal,
+            id,
+            from_ns,
+            to_ns,
+            self.journal_start.get(&id).copied().unwrap_or((0, 0)),
+            (acc.balance, acc.margin_held),
+        ))
+    }
+
+    /// Set the timestamp recorded on subsequent journal entries.
+    #[inline(always)]
+    pub fn set_time(&mut self, timestamp_ns: u64) {
+        self.books.now_ns = timestamp_ns;
+    }
+
+    /// Allow withdrawals and transfers to take `id`'s balance down to
+    /// `-limit`.  Clearing obligations always require a covered balance.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the account is missing or `limit` is
+    /// negative.
+    pub fn set_overdraft_limit(&mut self, id: u64, limit: i64) -> Result<(), ClearingError> {
+        if limit < 0 {
+            return Err(ClearingError::InvalidAmount(limit));
+        }
+        self.accounts.lookup(id)?;
+        self.books.record_overdraft_limit(id, limit);
+        if let Some(acc) = self.accounts.get_mut(&id) {
+            acc.overdraft_limit = limit;
+        }
+        Ok(())
+    }
+
+    /// Credit cash paid in from outside the clearing house.
+    ///
+    /// Deposits are accepted for any account that is not closed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the amount is not positive, the account is
+    /// missing or closed, the balance would overflow, or the key was used for
+    /// a different operation.
+    pub fn deposit(
+        &mut self,
+        idempotency_key: u64,
+        id: u64,
+        amount: i64,
+    ) -> Result<CashOutcome, ClearingError> {
+        self.cash_operation(
+            idempotency_key,
+            CashOperation::Deposit {
+                account_id: id,
+                amount,
+            },
+        )
+    }
+
+    /// Debit cash paid out of the clearing house from an `Active` account.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the amount is not positive, the account is
+    /// missing or not `Active`, the withdrawal exceeds the balance plus
+    /// overdraft limit, or the key was used for a different operation.
+    pub fn withdraw(
+        &mut self,
+        idempotency_key: u64,
+        id: u64,
+        amount: i64,
+    ) -> Result<CashOutcome, ClearingError> {
+        self.cash_operation(
+            idempotency_key,
+            CashOperation::Withdraw {
+                account_id: id,
+                amount,
+            },
+        )
+    }
+
+    /// Move cash between two `Active` accounts.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the amount is not positive, both ids name
+    /// the same account, either account is missing or not `Active`, the
+    /// transfer exceeds the sender's balance plus overdraft limit, the
+    /// receiver's balance would overflow, or the key was used for a different
+    /// operation.
+    pub fn transfer(
+        &mut self,
+        idempotency_key: u64,
+        from_id: u64,
+        to_id: u64,
+        amount: i64,
+    ) -> Result<CashOutcome, ClearingError> {
+        self.cash_operation(
+            idempotency_key,
+            CashOperation::Transfer {
+                from_id,
+                to_id,
+                amount,
+            },
+        )
+    }
+
+    /// Apply an idempotent cash operation: repeats of an applied key are
+    /// duplicates, and the operation is checked before anything changes.
+    fn cash_operation(
+        &mut self,
+        key: u64,
+        op: CashOperation,
+    ) -> Result<CashOutcome, ClearingError> {
+        if self.books.check_idempotency(key, op)? == CashOutcome::Duplicate {
+            return Ok(CashOutcome::Duplicate);
+        }
+        check_cash(&self.accounts, op)?;
+        self.books.record_cash(key, op);
+        apply_cash(&mut self.accounts, op);
+        Ok(CashOutcome::Applied)
+    }
+
+    /// Access the clearing journal.
+    #[inline(always)]
+    #[must_use]
+    pub const fn journal(&self) -> &SettlementJournal {
+        &self.books.journal
+    }
+
+    /// Access the general ledger.
+    #[inline(always)]
+    #[must_use]
+    pub const fn ledger(&self) -> &GeneralLedger {
+        &self.books.ledger
+    }
+
+    /// Compare every account's balance and margin held with the ledger.
+    ///
+    /// Returns one [`LedgerMismatch`] per disagreeing ledger account, sorted
+    /// by account; an empty result means the books reconcile.
+    #[must_use]
+    pub fn reconcile_ledger(&self) -> Vec<LedgerMismatch> {
+        let mut mismatches = self
+            .books
+            .ledger
+            .reconcile(self.accounts.values().flat_map(|acc| {
+                [
+                    (LedgerAccount::MemberCash(acc.account_id), acc.balance),
+                    (LedgerAccount::MemberMargin(acc.account_id), acc.margin_held),
+                ]
+            }));
+        mismatches.sort_by_key(|m| m.account);
+        mismatches
+    }
+
+    /// Cash that has entered or left the members' accounts.
+    #[inline(always)]
+    #[must_use]
+    pub const fn external_flows(&self) -> &ExternalFlows {
+        &self.books.flows
+    }
+
+    /// Check that no cash was created or destroyed: member funds against
+    /// external flows, every account against its ledger history, and
+    /// negative balances.  Runs in O(accounts).
+    #[must_use]
+    pub fn audit(&self) -> AuditReport {
+        BalanceAuditor::audit(
+            self.accounts.values(),
+            &self.books.ledger,
+            &self.books.flows,
+        )
+    }
+
+    /// Capture accounts, idempotency keys, house-account ledger balances and
+    /// the journal position.
+    #[must_use]
+    pub fn snapshot(&self) -> ClearingSnapshot {
+        self.books
+            .snapshot(self.accounts.values().cloned().collect())
+    }
+
+    /// Content hash of the current state, equal to the hash of
+    /// [`snapshot`](Self::snapshot).
+    #[must_use]
+    pub fn state_hash(&self) -> u64 {
+        self.snapshot().content_hash
+    }
+
+    /// Rebuild a clearing house from a snapshot.
+    ///
+    /// The ledger is re-opened with one adjustment per member balance,
+    /// margin held and house-account balance (fees, waterfall, default
+    /// losses).  The journal resumes after the snapshot's sequence.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SnapshotError::HashMismatch`] if the snapshot's content no
+    /// longer matches its hash.
+    pub fn restore(snapshot: &ClearingSnapshot) -> Result<Self, SnapshotError> {
+        snapshot.check()?;
+        Ok(Self {
+            accounts: snapshot
+                .accounts
+                .iter()
+                .map(|acc| (acc.account_id, acc.clone()))
+                .collect(),
+            books: Books::restore(snapshot),
+            journal_start: snapshot
+                .accounts
+                .iter()
+                .map(|acc| (acc.account_id, (acc.balance, acc.margin_held)))
+                .collect(),
+        })
+    }
+
+    /// Rebuild from a snapshot, then re-apply the journal entries recorded
+    /// after it.
+    ///
+    /// Entries at or before the snapshot's sequence are skipped; the rest
+    /// must be contiguous.  Each state-changing entry is re-executed and must
+    /// reproduce the original entry exactly.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SnapshotError`] if the snapshot fails its hash check, on a
+    /// sequence gap, or if an entry fails or diverges when re-applied.
+    pub fn restore_with_journal(
+        snapshot: &ClearingSnapshot,
+        entries: &[JournalEntry],
+    ) -> Result<Self, SnapshotError> {
+        let mut ch = Self::restore(snapshot)?;
+        for entry in entries.iter().filter(|e| e.sequence > snapshot.sequence) {
+            let expected = ch.books.journal.next_sequence();
+            if entry.sequence != expected {
+                return Err(SnapshotError::SequenceGap {
+                    expected,
+                    found: entry.sequence,
+                });
+            }
+            ch.replay_entry(entry)?;
         }
-        if !self.accounts.contains_key(&obligation.receiver_id) {
-            return Err(ClearingError::AccountNotFound(obligation.receiver_id));
+        Ok(ch)
+    }
+
+    /// Re-execute one journal entry at its original timestamp.
+    fn replay_entry(&mut self, entry: &JournalEntry) -> Result<(), SnapshotError> {
+        self.books.now_ns = entry.timestamp_ns;
+        let outcome = match entry.event {
+            JournalEvent::AccountRegistered {
+                account_id,
+                balance,
+            } => self.register_account(account_id, balance),
+            JournalEvent::CashDeposited {
+                account_id,
+                amount,
+                idempotency_key,
+            } => self
+                .deposit(idempotency_key, account_id, amount)
+                .map(|_| ()),
+            JournalEvent::CashWithdrawn {
+                account_id,
+                amount,
+                idempotency_key,
+            } => self
+                .withdraw(idempotency_key, account_id, amount)
+                .map(|_| ()),
+            JournalEvent::CashTransferred {
+                from_id,
+                to_id,
+                amount,
+                idempotency_key,
+            } => self
+                .transfer(idempotency_key, from_id, to_id, amount)
+                .map(|_| ()),
+            JournalEvent::ObligationCleared {
+                symbol_hash,
+                deliverer_id,
+                receiver_id,
+                net_quantity,
+                net_payment,
+            } => self.clear_obligation(&NetObligation {
+                symbol_hash,
+                deliverer_id,
+                receiver_id,
+                net_quantity,
+                net_payment,
+                trade_count: 0,
+            }),
+            JournalEvent::MarginPosted { account_id, amount } => {
+                self.post_margin(account_id, amount)
+            }
+            JournalEvent::MarginReleased { account_id, amount } => {
+                self.release_margin(account_id, amount)
+            }
+            JournalEvent::WaterfallApplied {
+                account_id,
+                absorbed,
+            } => self.apply_waterfall_draws(account_id, absorbed),
+            JournalEvent::FeeCharged {
+                account_id,
+                symbol_hash,
+                amount,
+            } => self.charge_fee(account_id, symbol_hash, amount),
+            JournalEvent::AccountStatusChanged { account_id, to, .. } => {
+                self.transition(account_id, to)
+            }
+            JournalEvent::OverdraftLimitSet { account_id, limit } => {
+                self.set_overdraft_limit(account_id, limit)
+            }
+            // Failed obligations and batch-level events change no state.
+            JournalEvent::ObligationFailed { .. }
+            | JournalEvent::TradeReceived { .. }
+            | JournalEvent::NettingCompleted { .. }
+            | JournalEvent::ClearingAttempted { .. }
+            | JournalEvent::SettlementCompleted { .. }
+            | JournalEvent::SettlementFailed { .. } => {
+                self.books.journal.append(entry.clone());
+                return Ok(());
+            }
+        };
+        outcome.map_err(|error| SnapshotError::ReplayFailed {
+            sequence: entry.sequence,
+            error,
+        })?;
+        if self.books.journal.last_entry() != Some(entry) {
+            return Err(SnapshotError::ReplayDiverged {
+                sequence: entry.sequence,
+            });
         }
+        Ok(())
+    }
+
+    /// Attempt to clear all obligations, returning per-obligation results.
+    ///
+    /// Obligations that fail do not roll back previously cleared obligations.
+    pub fn clear_all(&mut self, obligations: &[NetObligation]) -> Vec<ClearingResult> {
+        obligations
+            .iter()
+            .map(|ob| Self::to_result(ob, self.clear_obligation(ob)))
+            .collect()
+    }
+
+    /// Evaluate risk limits over the whole batch, then clear it.
+    ///
+    /// When the checker's limits have `block_on_breach` set, every obligation
+    /// involving a member in breach fails with
+    /// [`ClearingError::RiskLimitBreached`] and is not applied; otherwise the
+    /// report is informational and all obligations are attempted.
+    pub fn clear_all_checked(
+        &mut self,
+        obligations: &[NetObligation],
+        checker: &RiskChecker,
+    ) -> (RiskReport, Vec<ClearingResult>) {
+        let report = checker.evaluate(obligations);
+        let block = checker.limits().block_on_breach;
+        let results = obligations
+            .iter()
+            .map(|ob| {
+                let outcome = if block && report.is_breached(ob.deliverer_id) {
+                    Err(ClearingError::RiskLimitBreached(ob.deliverer_id))
+                } else if block && report.is_breached(ob.receiver_id) {
+                    Err(ClearingError::RiskLimitBreached(ob.receiver_id))
+                } else {
+                    check_settlement(&self.accounts, ob)
+                };
+                Self::to_result(ob, self.settle_checked(ob, outcome))
+            })
+            .collect();
+        (report, results)
+    }
+
+    /// Clear a batch, then charge the fees priced by `fees`.
+    ///
+    /// Trade-based fees accumulated in `fees` are charged regardless of the
+    /// clearing outcome; per-obligation fees apply only to obligations that
+    /// cleared.  Each fee is charged with [`charge_fee`](Self::charge_fee).
+    pub fn clear_all_with_fees(
+        &mut self,
+        obligations: &[NetObligation],
+        fees: &FeeEngine,
+    ) -> (Vec<ClearingResult>, Vec<FeeResult>) {
+        let results = self.clear_all(obligations);
+        let cleared: Vec<NetObligation> = results
+            .iter()
+            .filter(|r| r.success)
+            .map(|r| r.obligation.clone())
+            .collect();
+        let fee_results = self.charge_fees(&fees.compute_fees(&cleared));
+        (results, fee_results)
+    }
+
+    /// Charge a batch of fee obligations.
+    pub fn charge_fees(&mut self, fees: &[FeeObligation]) -> Vec<FeeResult> {
+        fees.iter()
+            .map(
+                |fee| match self.charge_fee(fee.account_id, fee.symbol_hash, fee.amount) {
+                    Ok(()) => FeeResult {
+                        fee: fee.clone(),
+                        success: true,
+                        error: None,
+                    },
+                    Err(e) => FeeResult {
+                        fee: fee.clone(),
+                        success: false,
+                        error: Some(e),
+                    },
+                },
+            )
+            .collect()
+    }
+
+    pub(crate) fn to_result(
+        ob: &NetObligation,
+        outcome: Result<(), ClearingError>,
+    ) -> ClearingResult {
+        match outcome {
+            Ok(()) => ClearingResult {
+                obligation: ob.clone(),
+                success: true,
+                error: None,
+            },
+            Err(e) => ClearingResult {
+                obligation: ob.clone(),
+                success: false,
+                error: Some(e),
+            },
+        }
+    }
+}
+
+// ── Shared checks and books ───────────────────────────────────────────
+//
+// Both clearing houses run every operation the same way: check it against
+// the accounts, record it in the books, then update the accounts.
+
+/// `Ok` when `amount` is strictly positive.
+pub(crate) const fn check_amount(amount: i64) -> Result<(), ClearingError> {
+    if amount <= 0 {
+        return Err(ClearingError::InvalidAmount(amount));
+    }
+    Ok(())
+}
+
+/// Check a cash operation against the accounts it touches.
+pub(crate) fn check_cash(accounts: &impl Accounts, op: CashOperation) -> Result<(), ClearingError> {
+    match op {
+        CashOperation::Deposit { account_id, amount } => {
+            check_amount(amount)?;
+            let acc = accounts.lookup(account_id)?;
+            acc.check_open()?;
+            if acc.balance.checked_add(amount).is_none() {
+                return Err(ClearingError::BalanceOverflow(account_id));
+            }
+        }
+        CashOperation::Withdraw { account_id, amount } => {
+            check_amount(amount)?;
+            let acc = accounts.lookup(account_id)?;
+            acc.check_clearable()?;
+            acc.check_overdraft(amount)?;
+        }
+        CashOperation::Transfer {
+            from_id,
+            to_id,
+            amount,
+        } => {
+            check_amount(amount)?;
+            if from_id == to_id {
+                return Err(ClearingError::SelfTransfer(from_id));
+            }
+            let from = accounts.lookup(from_id)?;
+            from.check_clearable()?;
+            let to = accounts.lookup(to_id)?;
+            to.check_clearable()?;
+            from.check_overdraft(amount)?;
+            if to.balance.checked_add(amount).is_none() {
+                return Err(ClearingError::BalanceOverflow(to_id));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Apply a cash operation that passed [`check_cash`].
+pub(crate) fn apply_cash(accounts: &mut impl Accounts, op: CashOperation) {
+    match op {
+        CashOperation::Deposit { account_id, amount } => {
+            move_balance(accounts, None, Some(account_id), amount);
+        }
+        CashOperation::Withdraw { account_id, amount } => {
+            move_balance(accounts, Some(account_id), None, amount);
+        }
+        CashOperation::Transfer {
+            from_id,
+            to_id,
+            amount,
+        } => move_balance(accounts, Some(from_id), Some(to_id), amount),
+    }
+}
+
+/// Check that both sides of an obligation may clear and the deliverer's
+/// balance covers the payment.
+pub(crate) fn check_settlement(
+    accounts: &impl Accounts,
+    ob: &NetObligation,
+) -> Result<(), ClearingError> {
+    let deliverer = accounts.lookup(ob.deliverer_id)?;
+    deliverer.check_clearable()?;
+    accounts.lookup(ob.receiver_id)?.check_clearable()?;
+    if deliverer.balance < ob.net_payment {
+        return Err(ClearingError::InsufficientBalance {
+            account_id: ob.deliverer_id,
+            required: ob.net_payment,
+            available: deliverer.balance,
+        });
+    }
+    Ok(())
+}
+
+/// Apply an obligation that passed [`check_settlement`].
+pub(crate) fn apply_settlement(accounts: &mut impl Accounts, ob: &NetObligation) {
+    move_balance(
+        accounts,
+        Some(ob.deliverer_id),
+        Some(ob.receiver_id),
+        ob.net_payment,
+    );
+}
+
+/// Debit `from` and credit `to` by `amount`; `None` is outside the house.
+fn move_balance(accounts: &mut impl Accounts, from: Option<u64>, to: Option<u64>, amount: i64) {
+    if let Some(acc) = from.and_then(|id| accounts.account_mut(id)) {
+        acc.balance -= amount;
+    }
+    if let Some(acc) = to.and_then(|id| accounts.account_mut(id)) {
+        acc.balance += amount;
+    }
+}
+
+/// Ledger, journal and cash bookkeeping kept alongside the accounts.
+///
+/// Each `record_*` posts and journals a change that has already passed its
+/// checks; the caller then applies it to the account.
+pub(crate) struct Books {
+    pub(crate) ledger: GeneralLedger,
+    pub(crate) journal: SettlementJournal,
+    /// Applied cash operations keyed by idempotency key.
+    pub(crate) cash_requests: HashMap<u64, CashOperation>,
+    /// Timestamp stamped on journal entries.
+    pub(crate) now_ns: u64,
+    /// Cash that has entered or left the members' accounts.
+    pub(crate) flows: ExternalFlows,
+}
+
+impl Books {
+    pub(crate) fn new() -> Self {
+        Self {
+            ledger: GeneralLedger::new(),
+            journal: SettlementJournal::new(),
+            cash_requests: HashMap::new(),
+            now_ns: 0,
+            flows: ExternalFlows::default(),
+        }
+    }
+
+    /// Books re-opened from a snapshot: one ledger adjustment per member
+    /// balance, margin held and house-account balance, and the journal
+    /// resuming after the snapshot's sequence.
+    pub(crate) fn restore(snapshot: &ClearingSnapshot) -> Self {
+        Self {
+            ledger: snapshot.opening_ledger(),
+            journal: SettlementJournal::resume(snapshot.sequence + 1),
+            cash_requests: snapshot.cash_requests.iter().copied().collect(),
+            now_ns: snapshot.timestamp_ns,
+            flows: ExternalFlows {
+                adjustments: snapshot.member_funds(),
+                ..ExternalFlows::default()
+            },
+        }
+    }
+
+    /// Snapshot of `accounts` at the current journal position.
+    pub(crate) fn snapshot(&self, accounts: Vec<ClearingAccount>) -> ClearingSnapshot {
+        ClearingSnapshot::new(
+            self.journal.next_sequence() - 1,
+            self.now_ns,
+            accounts,
+            self.cash_requests.iter().map(|(&k, &op)| (k, op)).collect(),
+            self.ledger.house_balances(),
+        )
+    }
+
+    /// Classify an idempotency key: unseen keys proceed, repeats of the same
+    /// operation are duplicates, and reuse for anything else is a conflict.
+    pub(crate) fn check_idempotency(
+        &self,
+        key: u64,
+        op: CashOperation,
+    ) -> Result<CashOutcome, ClearingError> {
+        match self.cash_requests.get(&key) {
+            None => Ok(CashOutcome::Applied),
+            Some(prev) if *prev == op => Ok(CashOutcome::Duplicate),
+            Some(_) => Err(ClearingError::IdempotencyConflict(key)),
+        }
+    }
+
+    #[inline(always)]
+    fn record(&mut self, event: JournalEvent) {
+        self.journal.record(self.now_ns, event);
+    }
+
+    pub(crate) fn record_registration(&mut self, id: u64, initial_balance: i64) {
+        self.ledger.post_registration(id, initial_balance);
+        self.flows.adjustments += initial_balance as i128;
+        self.record(JournalEvent::AccountRegistered {
+            account_id: id,
+            balance: initial_balance,
+        });
+    }
+
+    /// Post and journal a cash operation and remember its idempotency key.
+    pub(crate) fn record_cash(&mut self, idempotency_key: u64, op: CashOperation) {
+        let event = match op {
+            CashOperation::Deposit { account_id, amount } => {
+                self.flows.deposited += amount as i128;
+                self.ledger.post(
+                    LedgerEntryKind::CashDeposit,
+                    LedgerAccount::MemberCash(account_id),
+                    LedgerAccount::External,
+                    amount,
+                );
+                JournalEvent::CashDeposited {
+                    account_id,
+                    amount,
+                    idempotency_key,
+                }
+            }
+            CashOperation::Withdraw { account_id, amount } => {
+                self.flows.withdrawn += amount as i128;
+                self.ledger.post(
+                    LedgerEntryKind::CashWithdrawal,
+                    LedgerAccount::External,
+                    LedgerAccount::MemberCash(account_id),
+                    amount,
+                );
+                JournalEvent::CashWithdrawn {
+                    account_id,
+                    amount,
+                    idempotency_key,
+                }
+            }
+            CashOperation::Transfer {
+                from_id,
+                to_id,
+                amount,
+            } => {
+                self.ledger.post(
+                    LedgerEntryKind::CashTransfer,
+                    LedgerAccount::MemberCash(to_id),
+                    LedgerAccount::MemberCash(from_id),
+                    amount,
+                );
+                JournalEvent::CashTransferred {
+                    from_id,
+                    to_id,
+                    amount,
+                    idempotency_key,
+                }
+            }
+        };
+        self.record(event);
+        self.cash_requests.insert(idempotency_key, op);
+    }
+
+    /// Post a cleared obligation's cash leg and journal the outcome of the
+    /// clearing attempt.
+    pub(crate) fn record_obligation(
+        &mut self,
+        ob: &NetObligation,
+        outcome: &Result<(), ClearingError>,
+    ) {
+        if outcome.is_ok() {
+            self.ledger.post(
+                LedgerEntryKind::ClearingTransfer {
+                    symbol_hash: ob.symbol_hash,
+                },
+                LedgerAccount::MemberCash(ob.receiver_id),
+                LedgerAccount::MemberCash(ob.deliverer_id),
+                ob.net_payment,
+            );
+        }
+        self.record(obligation_event(ob, outcome));
+    }
+
+    pub(crate) fn record_margin_posted(&mut self, id: u64, amount: i64) {
+        self.ledger.post(
+            LedgerEntryKind::MarginPosted,
+            LedgerAccount::MemberMargin(id),
+            LedgerAccount::MemberCash(id),
+            amount,
+        );
+        self.record(JournalEvent::MarginPosted {
+            account_id: id,
+            amount,
+        });
+    }
 
-        // Balance check: deliverer existence was verified above.
-        let deliverer_balance = if let Some(acc) = self.accounts.get(&obligation.deliverer_id) {
-            acc.balance
-        } else {
-            return Err(ClearingError::AccountNotFound(obligation.deliverer_id));
-        };
+    pub(crate) fn record_margin_released(&mut self, id: u64, amount: i64) {
+        self.ledger.post(
+            LedgerEntryKind::MarginReleased,
+            LedgerAccount::MemberCash(id),
+            LedgerAccount::MemberMargin(id),
+            amount,
+        );
+        self.record(JournalEvent::MarginReleased {
+            account_id: id,
+            amount,
+        });
+    }
 
-        if deliverer_balance < obligation.net_payment {
-            return Err(ClearingError::InsufficientBalance {
-                account_id: obligation.deliverer_id,
-                required: obligation.net_payment,
-                available: deliverer_balance,
-            });
-        }
+    pub(crate) fn record_waterfall(&mut self, defaulter_id: u64, absorbed: [i64; 5]) {
+        self.flows.margin_drawn += absorbed[WaterfallLayer::DefaulterMargin as usize] as i128;
+        self.ledger.post_waterfall(defaulter_id, &absorbed);
+        self.record(JournalEvent::WaterfallApplied {
+            account_id: defaulter_id,
+            absorbed,
+        });
+    }
 
-        // Perform the transfer; both accounts were verified above.
-        if let Some(acc) = self.accounts.get_mut(&obligation.deliverer_id) {
-            acc.balance -= obligation.net_payment;
-        }
+    pub(crate) fn record_fee(&mut self, id: u64, symbol_hash: u64, amount: i64) {
+        self.flows.fees += amount as i128;
+        self.ledger.post(
+            LedgerEntryKind::Fee { symbol_hash },
+            LedgerAccount::FeeIncome,
+            LedgerAccount::MemberCash(id),
+            amount,
+        );
+        self.record(JournalEvent::FeeCharged {
+            account_id: id,
+            symbol_hash,
+            amount,
+        });
+    }
 
-        if let Some(acc) = self.accounts.get_mut(&obligation.receiver_id) {
-            acc.balance += obligation.net_payment;
-        }
+    pub(crate) fn record_status(&mut self, id: u64, from: AccountStatus, to: AccountStatus) {
+        self.record(JournalEvent::AccountStatusChanged {
+            account_id: id,
+            from,
+            to,
+        });
+    }
 
-        Ok(())
+    pub(crate) fn record_overdraft_limit(&mut self, id: u64, limit: i64) {
+        self.record(JournalEvent::OverdraftLimitSet {
+            account_id: id,
+            limit,
+        });
     }
+}
 
-    /// Attempt to clear all obligations, returning per-obligation results.
-    ///
-    /// Obligations that fail do not roll back previously cleared obligations.
-    pub fn clear_all(&mut self, obligations: &[NetObligation]) -> Vec<ClearingResult> {
-        obligations
-            .iter()
-            .map(|ob| match self.clear_obligation(ob) {
-                Ok(()) => ClearingResult {
-                    obligation: ob.clone(),
-                    success: true,
-                    error: None,
-                },
-                Err(e) => ClearingResult {
-                    obligation: ob.clone(),
-                    success: false,
-                    error: Some(e),
-                },
-            })
-            .collect()
+/// Journal event recording the outcome of a clearing attempt.
+fn obligation_event(ob: &NetObligation, outcome: &Result<(), ClearingError>) -> JournalEvent {
+    match outcome {
+        Ok(()) => JournalEvent::ObligationCleared {
+            symbol_hash: ob.symbol_hash,
+            deliverer_id: ob.deliverer_id,
+            receiver_id: ob.receiver_id,
+            net_quantity: ob.net_quantity,
+            net_payment: ob.net_payment,
+        },
+        Err(e) => JournalEvent::ObligationFailed {
+            symbol_hash: ob.symbol_hash,
+            deliverer_id: ob.deliverer_id,
+            receiver_id: ob.receiver_id,
+            net_quantity: ob.net_quantity,
+            net_payment: ob.net_payment,
+            reason: format!("{e:?}"),
+        },
     }
 }
 
@@ -154,6 +1358,7 @@ impl Default for ClearingHouse {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::audit::AuditViolation;
 
     fn make_obligation(
         symbol_hash: u64,
@@ -175,7 +1380,7 @@ mod tests {
     #[test]
     fn test_register_account() {
         let mut ch = ClearingHouse::new();
-        ch.register_account(1, 100_000);
+        ch.register_account(1, 100_000).unwrap();
 
         let acc = ch.get_account(1).unwrap();
         assert_eq!(acc.account_id, 1);
@@ -188,8 +1393,8 @@ mod tests {
     #[test]
     fn test_clear_success() {
         let mut ch = ClearingHouse::new();
-        ch.register_account(100, 50_000); // deliverer
-        ch.register_account(200, 10_000); // receiver
+        ch.register_account(100, 50_000).unwrap(); // deliverer
+        ch.register_account(200, 10_000).unwrap(); // receiver
 
         let ob = make_obligation(0xABCD, 100, 200, 10, 5_000);
         let result = ch.clear_obligation(&ob);
@@ -202,8 +1407,8 @@ mod tests {
     #[test]
     fn test_clear_insufficient_balance() {
         let mut ch = ClearingHouse::new();
-        ch.register_account(100, 1_000); // not enough
-        ch.register_account(200, 0);
+        ch.register_account(100, 1_000).unwrap(); // not enough
+        ch.register_account(200, 0).unwrap();
 
         let ob = make_obligation(0xABCD, 100, 200, 10, 5_000);
         let result = ch.clear_obligation(&ob);
@@ -219,7 +1424,7 @@ mod tests {
                 assert_eq!(required, 5_000);
                 assert_eq!(available, 1_000);
             }
-            other @ ClearingError::AccountNotFound(_) => panic!("unexpected error: {other:?}"),
+            other => panic!("unexpected error: {other:?}"),
         }
 
         // Balances must be unchanged after failure
@@ -230,7 +1435,7 @@ mod tests {
     #[test]
     fn test_clear_unknown_account() {
         let mut ch = ClearingHouse::new();
-        ch.register_account(100, 50_000);
+        ch.register_account(100, 50_000).unwrap();
         // Receiver (200) not registered
 
         let ob = make_obligation(0xABCD, 100, 200, 10, 5_000);
@@ -241,18 +1446,16 @@ mod tests {
             ClearingError::AccountNotFound(id) => {
                 assert_eq!(id, 200);
             }
-            other @ ClearingError::InsufficientBalance { .. } => {
-                panic!("unexpected error: {other:?}")
-            }
+            other => panic!("unexpected error: {other:?}"),
         }
     }
 
     #[test]
     fn test_clear_all_partial_failures() {
         let mut ch = ClearingHouse::new();
-        ch.register_account(100, 50_000);
-        ch.register_account(200, 500); // too low for second obligation
-        ch.register_account(300, 20_000);
+        ch.register_account(100, 50_000).unwrap();
+        ch.register_account(200, 500).unwrap(); // too low for second obligation
+        ch.register_account(300, 20_000).unwrap();
 
         let ob1 = make_obligation(0x0001, 100, 300, 5, 2_000); // succeeds
         let ob2 = make_obligation(0x0002, 200, 300, 3, 5_000); // fails (balance 500 < 5000)
@@ -273,21 +1476,37 @@ mod tests {
     }
 
     #[test]
-    fn test_register_account_replaces_balance() {
+    fn test_register_account_rejects_replacement_in_any_status() {
         let mut ch = ClearingHouse::new();
-        ch.register_account(1, 1_000);
-        ch.register_account(1, 9_999); // overwrite
-        let acc = ch.get_account(1).unwrap();
-        assert_eq!(acc.balance, 9_999);
-        assert_eq!(acc.margin_held, 0);
+        ch.register_account(1, 1_000).unwrap();
+        ch.register_account(2, 0).unwrap();
+        ch.register_account(3, 0).unwrap();
+        ch.suspend_account(1).unwrap();
+        ch.declare_default(2).unwrap();
+        ch.close_account(3).unwrap();
+        let journal_len = ch.journal().len();
+
+        for (id, status) in [
+            (1, AccountStatus::Suspended),
+            (2, AccountStatus::InDefault),
+            (3, AccountStatus::Closed),
+        ] {
+            assert_eq!(
+                ch.register_account(id, 5_000),
+                Err(ClearingError::AccountExists(id))
+            );
+            assert_eq!(ch.get_account(id).unwrap().status, status);
+        }
+        assert_eq!(ch.get_account(1).unwrap().balance, 1_000);
+        assert_eq!(ch.journal().len(), journal_len);
     }
 
     #[test]
     fn test_clear_zero_payment() {
         // A zero-payment obligation should succeed and leave balances unchanged.
         let mut ch = ClearingHouse::new();
-        ch.register_account(1, 500);
-        ch.register_account(2, 500);
+        ch.register_account(1, 500).unwrap();
+        ch.register_account(2, 500).unwrap();
         let ob = make_obligation(0x01, 1, 2, 0, 0);
         assert!(ch.clear_obligation(&ob).is_ok());
         assert_eq!(ch.get_account(1).unwrap().balance, 500);
@@ -298,7 +1517,7 @@ mod tests {
     fn test_clear_deliverer_unknown() {
         // Only deliverer is missing — error must reference deliverer.
         let mut ch = ClearingHouse::new();
-        ch.register_account(200, 10_000);
+        ch.register_account(200, 10_000).unwrap();
         let ob = make_obligation(0xAA, 999, 200, 1, 100);
         match ch.clear_obligation(&ob) {
             Err(ClearingError::AccountNotFound(id)) => assert_eq!(id, 999),
@@ -310,7 +1529,7 @@ mod tests {
     fn test_clear_receiver_unknown() {
         // Deliverer exists, receiver is missing.
         let mut ch = ClearingHouse::new();
-        ch.register_account(100, 10_000);
+        ch.register_account(100, 10_000).unwrap();
         let ob = make_obligation(0xBB, 100, 888, 1, 100);
         match ch.clear_obligation(&ob) {
             Err(ClearingError::AccountNotFound(id)) => assert_eq!(id, 888),
@@ -355,8 +1574,8 @@ mod tests {
     fn test_sequential_clear_same_pair() {
         // Two consecutive obligations between the same pair — balances accumulate.
         let mut ch = ClearingHouse::new();
-        ch.register_account(1, 100_000);
-        ch.register_account(2, 0);
+        ch.register_account(1, 100_000).unwrap();
+        ch.register_account(2, 0).unwrap();
         let ob1 = make_obligation(0x01, 1, 2, 1, 10_000);
         let ob2 = make_obligation(0x02, 1, 2, 1, 20_000);
         assert!(ch.clear_obligation(&ob1).is_ok());
@@ -369,14 +1588,755 @@ mod tests {
     fn test_exact_balance_obligation_succeeds() {
         // Clearing exactly the available balance should succeed.
         let mut ch = ClearingHouse::new();
-        ch.register_account(1, 5_000);
-        ch.register_account(2, 0);
+        ch.register_account(1, 5_000).unwrap();
+        ch.register_account(2, 0).unwrap();
         let ob = make_obligation(0xCC, 1, 2, 1, 5_000);
         assert!(ch.clear_obligation(&ob).is_ok());
         assert_eq!(ch.get_account(1).unwrap().balance, 0);
         assert_eq!(ch.get_account(2).unwrap().balance, 5_000);
     }
 
+    // ── Account lifecycle ─────────────────────────────────────────────
+
+    #[test]
+    fn test_new_account_is_active() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 100).unwrap();
+        assert_eq!(ch.get_account(1).unwrap().status, AccountStatus::Active);
+    }
+
+    #[test]
+    fn test_register_existing_is_non_destructive() {
+        let mut ch = ClearingHouse::new();
+        assert!(ch.register_account(1, 1_000).is_ok());
+        assert_eq!(
+            ch.register_account(1, 9_999),
+            Err(ClearingError::AccountExists(1))
+        );
+        assert_eq!(ch.get_account(1).unwrap().balance, 1_000);
+    }
+
+    #[test]
+    fn test_suspend_and_reinstate() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 10_000).unwrap();
+        ch.register_account(2, 0).unwrap();
+        assert!(ch.suspend_account(1).is_ok());
+
+        let ob = make_obligation(0x01, 1, 2, 1, 1_000);
+        assert_eq!(
+            ch.clear_obligation(&ob),
+            Err(ClearingError::AccountSuspended(1))
+        );
+        assert_eq!(ch.get_account(1).unwrap().balance, 10_000);
+
+        assert!(ch.reinstate_account(1).is_ok());
+        assert!(ch.clear_obligation(&ob).is_ok());
+        assert_eq!(ch.get_account(1).unwrap().balance, 9_000);
+    }
+
+    #[test]
+    fn test_clear_rejects_receiver_in_default() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 10_000).unwrap();
+        ch.register_account(2, 0).unwrap();
+        assert!(ch.declare_default(2).is_ok());
+
+        let ob = make_obligation(0x01, 1, 2, 1, 1_000);
+        assert_eq!(
+            ch.clear_obligation(&ob),
+            Err(ClearingError::AccountInDefault(2))
+        );
+        assert_eq!(ch.get_account(1).unwrap().balance, 10_000);
+    }
+
+    #[test]
+    fn test_default_from_suspended() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 0).unwrap();
+        assert!(ch.suspend_account(1).is_ok());
+        assert!(ch.declare_default(1).is_ok());
+        assert_eq!(ch.get_account(1).unwrap().status, AccountStatus::InDefault);
+    }
+
+    #[test]
+    fn test_invalid_transitions() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 0).unwrap();
+
+        // Active → Active via reinstate is not a transition.
+        assert_eq!(
+            ch.reinstate_account(1),
+            Err(ClearingError::InvalidStatusTransition {
+                account_id: 1,
+                from: AccountStatus::Active,
+                to: AccountStatus::Active,
+            })
+        );
+
+        assert!(ch.declare_default(1).is_ok());
+        // A defaulted member cannot be suspended or reinstated.
+        assert!(matches!(
+            ch.suspend_account(1),
+            Err(ClearingError::InvalidStatusTransition { .. })
+        ));
+        assert!(matches!(
+            ch.reinstate_account(1),
+            Err(ClearingError::InvalidStatusTransition { .. })
+        ));
+    }
+
+    #[test]
+    fn test_close_requires_empty_account() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 500).unwrap();
+        assert_eq!(
+            ch.close_account(1),
+            Err(ClearingError::AccountNotEmpty {
+                account_id: 1,
+                balance: 500,
+                margin_held: 0,
+            })
+        );
+        assert_eq!(ch.get_account(1).unwrap().status, AccountStatus::Active);
+    }
+
+    #[test]
+    fn test_closed_is_terminal() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 0).unwrap();
+        ch.register_account(2, 1_000).unwrap();
+        assert!(ch.close_account(1).is_ok());
+
+        assert!(matches!(
+            ch.close_account(1),
+            Err(ClearingError::InvalidStatusTransition { .. })
+        ));
+        assert!(matches!(
+            ch.reinstate_account(1),
+            Err(ClearingError::InvalidStatusTransition { .. })
+        ));
+        assert!(matches!(
+            ch.declare_default(1),
+            Err(ClearingError::InvalidStatusTransition { .. })
+        ));
+
+        let ob = make_obligation(0x01, 2, 1, 1, 100);
+        assert_eq!(
+            ch.clear_obligation(&ob),
+            Err(ClearingError::AccountClosed(1))
+        );
+    }
+
+    #[test]
+    fn test_transition_unknown_account() {
+        let mut ch = ClearingHouse::new();
+        assert_eq!(
+            ch.suspend_account(7),
+            Err(ClearingError::AccountNotFound(7))
+        );
+        assert_eq!(ch.close_account(7), Err(ClearingError::AccountNotFound(7)));
+    }
+
+    #[test]
+    fn test_clear_all_skips_suspended_member() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 10_000).unwrap();
+        ch.register_account(2, 10_000).unwrap();
+        ch.register_account(3, 10_000).unwrap();
+        assert!(ch.suspend_account(2).is_ok());
+
+        let results = ch.clear_all(&[
+            make_obligation(0x01, 1, 2, 1, 1_000),
+            make_obligation(0x01, 1, 3, 1, 1_000),
+        ]);
+        assert_eq!(results[0].error, Some(ClearingError::AccountSuspended(2)));
+        assert!(results[1].success);
+        assert_eq!(ch.get_account(3).unwrap().balance, 11_000);
+    }
+
+    // ── Risk-checked clearing ─────────────────────────────────────────
+
+    #[test]
+    fn test_clear_all_checked_blocks_breached_member() {
+        use crate::risk::{MemberLimits, RiskLimits};
+
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 100_000).unwrap();
+        ch.register_account(2, 100_000).unwrap();
+        ch.register_account(3, 100_000).unwrap();
+
+        let mut limits = RiskLimits {
+            block_on_breach: true,
+            ..RiskLimits::default()
+        };
+        limits.member_limits.insert(
+            2,
+            MemberLimits {
+                max_gross_exposure: 1_000,
+                ..MemberLimits::default()
+            },
+        );
+        let checker = RiskChecker::new(limits);
+
+        let obs = [
+            make_obligation(0x01, 1, 2, 1, 5_000), // member 2 over its limit
+            make_obligation(0x01, 1, 3, 1, 5_000),
+        ];
+        let (report, results) = ch.clear_all_checked(&obs, &checker);
+        assert_eq!(report.breached_members(), vec![2]);
+        assert_eq!(results[0].error, Some(ClearingError::RiskLimitBreached(2)));
+        assert!(results[1].success);
+        assert_eq!(ch.get_account(2).unwrap().balance, 100_000);
+        assert_eq!(ch.get_account(3).unwrap().balance, 105_000);
+    }
+
+    #[test]
+    fn test_clear_all_checked_report_only() {
+        use crate::risk::RiskLimits;
+
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 10_000).unwrap();
+        ch.register_account(2, 0).unwrap();
+
+        let mut limits = RiskLimits::default();
+        limits.default_limits.max_gross_exposure = 10;
+        let checker = RiskChecker::new(limits);
+
+        let (report, results) =
+            ch.clear_all_checked(&[make_obligation(0x01, 1, 2, 1, 500)], &checker);
+        assert!(!report.is_clean());
+        assert!(results[0].success);
+        assert_eq!(ch.get_account(2).unwrap().balance, 500);
+    }
+
+    // ── Margin and ledger ─────────────────────────────────────────────
+
+    #[test]
+    fn test_post_and_release_margin() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 10_000).unwrap();
+        assert!(ch.post_margin(1, 4_000).is_ok());
+        let acc = ch.get_account(1).unwrap();
+        assert_eq!(acc.balance, 6_000);
+        assert_eq!(acc.margin_held, 4_000);
+
+        assert!(ch.release_margin(1, 1_500).is_ok());
+        let acc = ch.get_account(1).unwrap();
+        assert_eq!(acc.balance, 7_500);
+        assert_eq!(acc.margin_held, 2_500);
+        assert!(ch.reconcile_ledger().is_empty());
+    }
+
+    #[test]
+    fn test_margin_errors() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 1_000).unwrap();
+        assert_eq!(ch.post_margin(1, 0), Err(ClearingError::InvalidAmount(0)));
+        assert!(matches!(
+            ch.post_margin(1, 2_000),
+            Err(ClearingError::InsufficientBalance { .. })
+        ));
+        assert_eq!(
+            ch.release_margin(1, 1),
+            Err(ClearingError::InsufficientMargin {
+                account_id: 1,
+                required: 1,
+                available: 0,
+            })
+        );
+        assert_eq!(ch.post_margin(9, 1), Err(ClearingError::AccountNotFound(9)));
+    }
+
+    #[test]
+    fn test_defaulted_member_cannot_release_margin() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 1_000).unwrap();
+        assert!(ch.post_margin(1, 500).is_ok());
+        assert!(ch.declare_default(1).is_ok());
+        assert_eq!(
+            ch.release_margin(1, 500),
+            Err(ClearingError::AccountInDefault(1))
+        );
+        // Topping up is still allowed.
+        assert!(ch.post_margin(1, 100).is_ok());
+    }
+
+    #[test]
+    fn test_clearing_posts_to_ledger() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 50_000).unwrap();
+        ch.register_account(2, 10_000).unwrap();
+        assert!(ch
+            .clear_obligation(&make_obligation(0xAB, 1, 2, 10, 5_000))
+            .is_ok());
+
+        let gl = ch.ledger();
+        assert_eq!(gl.balance(LedgerAccount::MemberCash(1)), 45_000);
+        assert_eq!(gl.balance(LedgerAccount::MemberCash(2)), 15_000);
+        assert_eq!(gl.balance(LedgerAccount::External), -60_000);
+        assert!(gl.verify());
+        assert!(ch.reconcile_ledger().is_empty());
+    }
+
+    #[test]
+    fn test_failed_clearing_not_posted() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 100).unwrap();
+        ch.register_account(2, 0).unwrap();
+        let before = ch.ledger().len();
+        assert!(ch
+            .clear_obligation(&make_obligation(0x01, 1, 2, 1, 5_000))
+            .is_err());
+        assert_eq!(ch.ledger().len(), before);
+    }
+
+    #[test]
+    fn test_rejected_registration_posts_nothing() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 1_000).unwrap();
+        assert!(ch.post_margin(1, 400).is_ok());
+        let before = ch.ledger().len();
+        assert!(ch.register_account(1, 9_999).is_err());
+        assert_eq!(ch.ledger().len(), before);
+        assert!(ch.reconcile_ledger().is_empty());
+        assert_eq!(ch.ledger().balance(LedgerAccount::External), -1_000);
+    }
+
+    #[test]
+    fn test_reconcile_detects_divergence() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 1_000).unwrap();
+        // Simulate an out-of-band mutation of the book.
+        ch.accounts.get_mut(&1).unwrap().balance = 1_200;
+        let mismatches = ch.reconcile_ledger();
+        assert_eq!(
+            mismatches,
+            vec![LedgerMismatch {
+                account: LedgerAccount::MemberCash(1),
+                ledger_balance: 1_000,
+                book_balance: 1_200,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_apply_waterfall_draws_margin() {
+        use crate::waterfall::{DefaultWaterfall, WaterfallConfig};
+
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 1_000).unwrap();
+        assert!(ch.post_margin(1, 100).is_ok());
+        assert!(ch.declare_default(1).is_ok());
+
+        let wf = DefaultWaterfall::new(WaterfallConfig {
+            defaulter_margin: 100,
+            defaulter_fund: 50,
+            ccp_first_loss: 30,
+            members_fund: 200,
+            ccp_capital: 500,
+        });
+        let result = wf.absorb_loss(150);
+        assert!(ch.apply_waterfall(1, &result).is_ok());
+
+        assert_eq!(ch.get_account(1).unwrap().margin_held, 0);
+        assert_eq!(ch.ledger().balance(LedgerAccount::DefaultLoss(1)), 150);
+        assert!(ch.reconcile_ledger().is_empty());
+        assert!(ch.ledger().verify());
+    }
+
+    #[test]
+    fn test_apply_waterfall_insufficient_margin() {
+        use crate::waterfall::{DefaultWaterfall, WaterfallConfig};
+
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 1_000).unwrap();
+        assert!(ch.declare_default(1).is_ok());
+        let result = DefaultWaterfall::new(WaterfallConfig::default()).absorb_loss(50);
+        assert_eq!(
+            ch.apply_waterfall(1, &result),
+            Err(ClearingError::InsufficientMargin {
+                account_id: 1,
+                required: 50,
+                available: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_apply_waterfall_requires_default() {
+        use crate::waterfall::{DefaultWaterfall, WaterfallConfig};
+
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 1_000).unwrap();
+        assert!(ch.post_margin(1, 100).is_ok());
+        let result = DefaultWaterfall::new(WaterfallConfig::default()).absorb_loss(50);
+        assert_eq!(
+            ch.apply_waterfall(1, &result),
+            Err(ClearingError::AccountNotInDefault(1))
+        );
+        ch.suspend_account(1).unwrap();
+        assert_eq!(
+            ch.apply_waterfall(1, &result),
+            Err(ClearingError::AccountNotInDefault(1))
+        );
+        assert_eq!(ch.get_account(1).unwrap().margin_held, 100);
+        assert!(ch
+            .ledger()
+            .entries_for(LedgerAccount::DefaultLoss(1))
+            .is_empty());
+    }
+
+    // ── Cash operations ───────────────────────────────────────────────
+
+    fn cash_event_count(ch: &ClearingHouse) -> usize {
+        ch.journal()
+            .entries()
+            .iter()
+            .filter(|e| {
+                matches!(
+                    e.event,
+                    JournalEvent::CashDeposited { .. }
+                        | JournalEvent::CashWithdrawn { .. }
+                        | JournalEvent::CashTransferred { .. }
+                )
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_deposit_withdraw_transfer() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 1_000).unwrap();
+        ch.register_account(2, 0).unwrap();
+
+        assert_eq!(ch.deposit(1, 1, 500), Ok(CashOutcome::Applied));
+        assert_eq!(ch.withdraw(2, 1, 300), Ok(CashOutcome::Applied));
+        assert_eq!(ch.transfer(3, 1, 2, 700), Ok(CashOutcome::Applied));
+
+        assert_eq!(ch.get_account(1).unwrap().balance, 500);
+        assert_eq!(ch.get_account(2).unwrap().balance, 700);
+        assert!(ch.reconcile_ledger().is_empty());
+        assert_eq!(ch.ledger().balance(LedgerAccount::External), -1_200);
+    }
+
+    #[test]
+    fn test_transfer_to_self_rejected() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 1_000).unwrap();
+        let journal_len = ch.journal().len();
+        let ledger_len = ch.ledger().entries().len();
+        assert_eq!(
+            ch.transfer(1, 1, 1, 100),
+            Err(ClearingError::SelfTransfer(1))
+        );
+        assert_eq!(ch.journal().len(), journal_len);
+        assert_eq!(ch.ledger().entries().len(), ledger_len);
+        // The key was not consumed.
+        assert_eq!(ch.deposit(1, 1, 100), Ok(CashOutcome::Applied));
+    }
+
+    #[test]
+    fn test_cash_operations_journaled() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 1_000).unwrap();
+        ch.register_account(2, 0).unwrap();
+        ch.set_time(5_000);
+        assert!(ch.deposit(10, 1, 100).is_ok());
+        ch.set_time(6_000);
+        assert!(ch.transfer(11, 1, 2, 50).is_ok());
+
+        let entries = &ch.journal().entries()[2..]; // after two registrations
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].timestamp_ns, 5_000);
+        assert!(matches!(
+            entries[0].event,
+            JournalEvent::CashDeposited {
+                account_id: 1,
+                amount: 100,
+                idempotency_key: 10,
+            }
+        ));
+        assert_eq!(entries[1].timestamp_ns, 6_000);
+        assert!(matches!(
+            entries[1].event,
+            JournalEvent::CashTransferred {
+                from_id: 1,
+                to_id: 2,
+                amount: 50,
+                idempotency_key: 11,
+            }
+        ));
+    }
+
+    #[test]
+    fn test_idempotent_replay_is_noop() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 0).unwrap();
+        assert_eq!(ch.deposit(42, 1, 100), Ok(CashOutcome::Applied));
+        assert_eq!(ch.deposit(42, 1, 100), Ok(CashOutcome::Duplicate));
+        assert_eq!(ch.get_account(1).unwrap().balance, 100);
+        assert_eq!(cash_event_count(&ch), 1);
+    }
+
+    #[test]
+    fn test_idempotency_conflict() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 1_000).unwrap();
+        assert!(ch.deposit(42, 1, 100).is_ok());
+        assert_eq!(
+            ch.deposit(42, 1, 200),
+            Err(ClearingError::IdempotencyConflict(42))
+        );
+        assert_eq!(
+            ch.withdraw(42, 1, 100),
+            Err(ClearingError::IdempotencyConflict(42))
+        );
+        assert_eq!(ch.get_account(1).unwrap().balance, 1_100);
+    }
+
+    #[test]
+    fn test_failed_request_key_can_be_retried() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 100).unwrap();
+        assert!(matches!(
+            ch.withdraw(7, 1, 500),
+            Err(ClearingError::InsufficientBalance { .. })
+        ));
+        assert!(ch.deposit(8, 1, 400).is_ok());
+        assert_eq!(ch.withdraw(7, 1, 500), Ok(CashOutcome::Applied));
+        assert_eq!(ch.get_account(1).unwrap().balance, 0);
+    }
+
+    #[test]
+    fn test_overdraft_limit() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 100).unwrap();
+        ch.register_account(2, 0).unwrap();
+        assert!(ch.set_overdraft_limit(1, 400).is_ok());
+
+        assert_eq!(ch.withdraw(1, 1, 300), Ok(CashOutcome::Applied));
+        assert_eq!(ch.get_account(1).unwrap().balance, -200);
+        assert_eq!(
+            ch.transfer(2, 1, 2, 300),
+            Err(ClearingError::InsufficientBalance {
+                account_id: 1,
+                required: 300,
+                available: 200,
+            })
+        );
+        assert_eq!(ch.transfer(3, 1, 2, 200), Ok(CashOutcome::Applied));
+        assert_eq!(ch.get_account(1).unwrap().balance, -400);
+
+        assert_eq!(
+            ch.set_overdraft_limit(1, -1),
+            Err(ClearingError::InvalidAmount(-1))
+        );
+    }
+
+    #[test]
+    fn test_overdraft_does_not_fund_obligations() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 0).unwrap();
+        ch.register_account(2, 0).unwrap();
+        assert!(ch.set_overdraft_limit(1, 1_000).is_ok());
+        assert!(matches!(
+            ch.clear_obligation(&make_obligation(0x01, 1, 2, 1, 500)),
+            Err(ClearingError::InsufficientBalance { .. })
+        ));
+    }
+
+    #[test]
+    fn test_cash_operation_errors() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 1_000).unwrap();
+        ch.register_account(2, 0).unwrap();
+        assert_eq!(ch.deposit(1, 1, 0), Err(ClearingError::InvalidAmount(0)));
+        assert_eq!(ch.withdraw(2, 1, -5), Err(ClearingError::InvalidAmount(-5)));
+        assert_eq!(ch.deposit(3, 9, 10), Err(ClearingError::AccountNotFound(9)));
+        assert_eq!(
+            ch.deposit(4, 1, i64::MAX),
+            Err(ClearingError::BalanceOverflow(1))
+        );
+
+        assert!(ch.suspend_account(2).is_ok());
+        assert_eq!(
+            ch.transfer(5, 1, 2, 10),
+            Err(ClearingError::AccountSuspended(2))
+        );
+        // Suspended members may still pay cash in.
+        assert_eq!(ch.deposit(6, 2, 10), Ok(CashOutcome::Applied));
+        assert_eq!(
+            ch.withdraw(7, 2, 10),
+            Err(ClearingError::AccountSuspended(2))
+        );
+        assert_eq!(ch.get_account(1).unwrap().balance, 1_000);
+        assert_eq!(cash_event_count(&ch), 1);
+    }
+
+    #[test]
+    fn test_charge_fee() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 100).unwrap();
+        assert!(ch.set_overdraft_limit(1, 50).is_ok());
+        assert!(ch.charge_fee(1, 0xAB, 120).is_ok());
+        assert_eq!(ch.get_account(1).unwrap().balance, -20);
+        assert_eq!(ch.ledger().balance(LedgerAccount::FeeIncome), 120);
+        assert!(ch.reconcile_ledger().is_empty());
+        assert!(matches!(
+            ch.charge_fee(1, 0xAB, 40),
+            Err(ClearingError::InsufficientBalance { .. })
+        ));
+        assert_eq!(
+            ch.charge_fee(1, 0xAB, 0),
+            Err(ClearingError::InvalidAmount(0))
+        );
+        assert_eq!(
+            ch.charge_fee(9, 0xAB, 1),
+            Err(ClearingError::AccountNotFound(9))
+        );
+
+        // Suspended members are still charged.
+        assert!(ch.suspend_account(1).is_ok());
+        assert!(ch.charge_fee(1, 0xAB, 10).is_ok());
+        assert!(matches!(
+            ch.journal().entries().last().unwrap().event,
+            JournalEvent::FeeCharged { amount: 10, .. }
+        ));
+    }
+
+    #[test]
+    fn test_clear_all_with_fees() {
+        use crate::fee::{FeeRates, FeeSchedule};
+        use crate::trade::{SettlementStatus, Trade};
+
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 10_000).unwrap();
+        ch.register_account(2, 10_000).unwrap();
+        ch.register_account(3, 0).unwrap();
+
+        let mut fees = FeeEngine::new(FeeSchedule {
+            default_rates: FeeRates {
+                per_trade: 2,
+                per_obligation: 10,
+                ..FeeRates::default()
+            },
+            ..FeeSchedule::default()
+        });
+        for (id, seller) in [(1, 1), (2, 3)] {
+            fees.add_trade(&Trade {
+                trade_id: id,
+                symbol_hash: id,
+                buyer_id: 2,
+                seller_id: seller,
+                price: 100,
+                quantity: 1,
+                timestamp_ns: 0,
+                status: SettlementStatus::Pending,
+            });
+        }
+        let obligations = [
+            make_obligation(1, 1, 2, 1, 100),
+            make_obligation(2, 3, 2, 1, 100), // member 3 cannot pay
+        ];
+        let (results, fee_results) = ch.clear_all_with_fees(&obligations, &fees);
+        assert!(results[0].success);
+        assert!(!results[1].success);
+
+        // Members 1 and 2 pay trade + obligation fee on symbol 1; symbol 2
+        // is charged the trade fee only and member 3 cannot afford it.
+        let charged: Vec<_> = fee_results
+            .iter()
+            .map(|r| (r.fee.account_id, r.fee.symbol_hash, r.fee.amount, r.success))
+            .collect();
+        assert_eq!(
+            charged,
+            vec![
+                (1, 1, 12, true),
+                (2, 1, 12, true),
+                (2, 2, 2, true),
+                (3, 2, 2, false)
+            ]
+        );
+        assert_eq!(ch.get_account(1).unwrap().balance, 10_000 - 100 - 12);
+        assert_eq!(ch.get_account(2).unwrap().balance, 10_000 + 100 - 14);
+        assert_eq!(ch.ledger().balance(LedgerAccount::FeeIncome), 26);
+        assert!(ch.reconcile_ledger().is_empty());
+    }
+
+    #[test]
+    fn test_audit_clean_after_activity() {
+        use crate::waterfall::{DefaultWaterfall, WaterfallConfig};
+
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 10_000).unwrap();
+        ch.register_account(2, 6_000).unwrap();
+        assert!(ch.deposit(1, 1, 500).is_ok());
+        assert!(ch.withdraw(2, 2, 300).is_ok());
+        assert!(ch.transfer(3, 1, 2, 200).is_ok());
+        let _ = ch.clear_all(&[make_obligation(0x01, 1, 2, 1, 1_000)]);
+        assert!(ch.post_margin(1, 2_000).is_ok());
+        assert!(ch.charge_fee(2, 0, 50).is_ok());
+        assert!(ch.declare_default(1).is_ok());
+        let wf = DefaultWaterfall::new(WaterfallConfig {
+            defaulter_margin: 2_000,
+            defaulter_fund: 0,
+            ccp_first_loss: 0,
+            members_fund: 0,
+            ccp_capital: 0,
+        });
+        assert!(ch.apply_waterfall(1, &wf.absorb_loss(1_500)).is_ok());
+
+        let report = ch.audit();
+        assert!(report.is_clean(), "{:?}", report.violations);
+        assert_eq!(report.accounts_checked, 2);
+        // 16_000 registered + 500 in - 300 out - 50 fee - 1_500 drawn.
+        assert_eq!(report.member_funds, 14_650);
+        assert_eq!(ch.external_flows().expected_member_funds(), 14_650);
+    }
+
+    #[test]
+    fn test_audit_detects_tampering() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 1_000).unwrap();
+        ch.register_account(2, 0).unwrap();
+        if let Some(acc) = ch.accounts.get_mut(&2) {
+            acc.balance = -10;
+        }
+        let report = ch.audit();
+        assert!(report.has_critical());
+        assert_eq!(
+            report.violations,
+            vec![
+                AuditViolation::Conservation {
+                    expected: 1_000,
+                    actual: 990,
+                },
+                AuditViolation::LedgerMismatch(LedgerMismatch {
+                    account: LedgerAccount::MemberCash(2),
+                    ledger_balance: 0,
+                    book_balance: -10,
+                }),
+                AuditViolation::NegativeBalance {
+                    account_id: 2,
+                    balance: -10,
+                    overdraft_limit: 0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_audit_after_restore() {
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 700).unwrap();
+        assert!(ch.post_margin(1, 200).is_ok());
+        let mut restored = ClearingHouse::restore(&ch.snapshot()).unwrap();
+        assert!(restored.audit().is_clean());
+        assert!(restored.deposit(1, 1, 100).is_ok());
+        assert!(restored.audit().is_clean());
+    }
+
     // ── Property-based tests ──────────────────────────────────────────
 
     use proptest::prelude::*;
@@ -397,8 +2357,8 @@ mod tests {
             let receiver_id  = 2u64;
 
             let mut ch = ClearingHouse::new();
-            ch.register_account(deliverer_id, deliverer_balance);
-            ch.register_account(receiver_id,  receiver_balance);
+            ch.register_account(deliverer_id, deliverer_balance).unwrap();
+            ch.register_account(receiver_id,  receiver_balance).unwrap();
 
             let ob = make_obligation(0x01, deliverer_id, receiver_id, 1, net_payment);
             let result = ch.clear_obligation(&ob);
@@ -428,8 +2388,8 @@ mod tests {
             let receiver_id  = 20u64;
 
             let mut ch = ClearingHouse::new();
-            ch.register_account(deliverer_id, deliverer_balance);
-            ch.register_account(receiver_id,  0);
+            ch.register_account(deliverer_id, deliverer_balance).unwrap();
+            ch.register_account(receiver_id,  0).unwrap();
 
             let ob = make_obligation(0x02, deliverer_id, receiver_id, 1, net_payment);
             let result = ch.clear_obligation(&ob);
@@ -441,7 +2401,7 @@ mod tests {
                     prop_assert_eq!(required,   net_payment);
                     prop_assert_eq!(available,  deliverer_balance);
                 }
-                other @ ClearingError::AccountNotFound(_) => prop_assert!(false, "expected InsufficientBalance, got {:?}", other),
+                other => prop_assert!(false, "expected InsufficientBalance, got {:?}", other),
             }
 
             // Balances must be untouched after failure
@@ -462,10 +2422,10 @@ mod tests {
             let mut ch = ClearingHouse::new();
             if missing_is_deliverer {
                 // Only register receiver; deliverer is absent
-                ch.register_account(receiver_id, registered_balance);
+                ch.register_account(receiver_id, registered_balance).unwrap();
             } else {
                 // Only register deliverer; receiver is absent
-                ch.register_account(deliverer_id, registered_balance);
+                ch.register_account(deliverer_id, registered_balance).unwrap();
             }
 
             let ob = make_obligation(0x03, deliverer_id, receiver_id, 1, net_payment);
@@ -477,7 +2437,7 @@ mod tests {
                     let expected_id = if missing_is_deliverer { deliverer_id } else { receiver_id };
                     prop_assert_eq!(id, expected_id);
                 }
-                other @ ClearingError::InsufficientBalance { .. } => prop_assert!(false, "expected AccountNotFound, got {:?}", other),
+                other => prop_assert!(false, "expected AccountNotFound, got {:?}", other),
             }
         }
     }
@@ -2,12 +2,15 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Copyright (C) 2026 Moroya Sakamoto
 
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
 use crate::fnv1a;
+use crate::margin::MarginRequirement;
 
 // ── Collateral Types ───────────────────────────────────────────────────
 
 /// 担保の品質ティア（高い方が優良）。
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(u8)]
 pub enum CollateralType {
     /// 現金（最高品質、ヘアカット 0%）。
@@ -20,7 +23,136 @@ pub enum CollateralType {
     Equity = 3,
 }
 
-/// ヘアカット設定（担保タイプ別）。
+/// 信用格付け（高い方が優良）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum CreditRating {
+    /// AAA。
+    Aaa = 0,
+    /// AA。
+    Aa = 1,
+    /// A。
+    A = 2,
+    /// BBB（投資適格の下限）。
+    Bbb = 3,
+    /// BB。
+    Bb = 4,
+    /// B。
+    B = 5,
+    /// CCC 以下。
+    Ccc = 6,
+    /// 格付けなし。
+    NotRated = 7,
+}
+
+impl CreditRating {
+    /// 投資適格（BBB 以上）かどうか。
+    #[inline]
+    #[must_use]
+    pub const fn is_investment_grade(self) -> bool {
+        (self as u8) <= Self::Bbb as u8
+    }
+}
+
+/// 通貨なしを表す ISO 4217 コード（バケット資産に使用）。
+pub const NO_CURRENCY: [u8; 3] = *b"XXX";
+
+/// 残存期間バケットの上限（日）：1年、5年、10年。これを超えると最終バケット。
+pub const MATURITY_BUCKET_DAYS: [u32; 3] = [365, 1_825, 3_650];
+
+/// 残存期間（日）に対応するバケット番号（0〜3）。満期なし（0 日）は `None`。
+#[inline]
+#[must_use]
+pub const fn maturity_bucket(maturity_days: u32) -> Option<usize> {
+    if maturity_days == 0 {
+        return None;
+    }
+    let mut i = 0;
+    while i < MATURITY_BUCKET_DAYS.len() {
+        if maturity_days <= MATURITY_BUCKET_DAYS[i] {
+            return Some(i);
+        }
+        i += 1;
+    }
+    Some(MATURITY_BUCKET_DAYS.len())
+}
+
+/// 担保資産の属性（銘柄単位）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollateralAsset {
+    /// 資産 ID（ISIN のハッシュ等）。0〜3 はバケット資産用に予約。
+    pub asset_id: u64,
+    /// 担保タイプ。
+    pub collateral_type: CollateralType,
+    /// 発行体 ID。
+    pub issuer_id: u64,
+    /// 信用格付け。
+    pub rating: CreditRating,
+    /// 残存期間（日）。0 は満期なし（現金・株式）。
+    pub maturity_days: u32,
+    /// 通貨（ISO 4217）。
+    pub currency: [u8; 3],
+    /// 価格ボラティリティ（年率、bps）。
+    pub volatility_bps: u32,
+    /// 単価（ティック単位）。
+    pub price: i64,
+}
+
+impl CollateralAsset {
+    /// 担保タイプ別のバケット資産（単価 1、AAA、満期なし）。
+    ///
+    /// [`CollateralAccount::deposit`] など担保タイプ単位の操作はこの資産を使う。
+    #[must_use]
+    pub const fn bucket(collateral_type: CollateralType) -> Self {
+        Self {
+            asset_id: collateral_type as u64,
+            collateral_type,
+            issuer_id: 0,
+            rating: CreditRating::Aaa,
+            maturity_days: 0,
+            currency: NO_CURRENCY,
+            volatility_bps: 0,
+            price: 1,
+        }
+    }
+
+    /// 保有と同じ銘柄属性（タイプ・発行体・格付け・残存期間・通貨・ボラティリティ）か。
+    /// 単価は比較しない。
+    #[must_use]
+    pub fn matches_holding(&self, holding: &CollateralHolding) -> bool {
+        self.asset_id == holding.asset_id
+            && self.collateral_type == holding.collateral_type
+            && self.issuer_id == holding.issuer_id
+            && self.rating == holding.rating
+            && self.maturity_days == holding.maturity_days
+            && self.currency == holding.currency
+            && self.volatility_bps == holding.volatility_bps
+    }
+}
+
+/// 担保操作のエラー。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollateralError {
+    /// 数量・金額が正でない。
+    InvalidQuantity(i64),
+    /// ヘアカットが 10000 bps を超える。
+    HaircutOutOfRange(u32),
+    /// 数量または評価額が `i64` の範囲を超える。
+    Overflow,
+    /// バケット資産用に予約された資産 ID（0〜3）をバケット以外の資産に使った。
+    ReservedAssetId(u64),
+    /// 既存保有と銘柄属性が異なる。
+    AssetMismatch(u64),
+}
+
+/// `i64` の列を `i128` で合計し、`i64` の範囲に飽和させる。
+#[inline]
+pub(crate) fn saturating_sum(values: impl IntoIterator<Item = i64>) -> i64 {
+    let total: i128 = values.into_iter().map(i128::from).sum();
+    total.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+/// ヘアカット設定（担保タイプ別の基本値と属性別の加算）。
 ///
 /// ヘアカットは 0〜10000 の基点（bps）で表現する。
 /// 10000 bps = 100% = 担保価値ゼロ。
@@ -34,6 +166,10 @@ pub struct HaircutConfig {
     pub corp_bond_bps: u32,
     /// 株式のヘアカット（bps）。通常 1500〜3000。
     pub equity_bps: u32,
+    /// 格付け別の加算（bps、[`CreditRating`] の順）。
+    pub rating_addon_bps: [u32; 8],
+    /// 残存期間バケット別の加算（bps、[`maturity_bucket`] の順）。
+    pub maturity_addon_bps: [u32; 4],
 }
 
 impl Default for HaircutConfig {
@@ -43,11 +179,54 @@ impl Default for HaircutConfig {
             gov_bond_bps: 300,
             corp_bond_bps: 1000,
             equity_bps: 2500,
+            rating_addon_bps: [0, 0, 100, 300, 1_000, 2_000, 4_000, 1_500],
+            maturity_addon_bps: [0, 100, 200, 400],
         }
     }
 }
 
 impl HaircutConfig {
+    /// タイプ別の基本ヘアカットを指定して作る（加算は既定値）。
+    ///
+    /// # Errors
+    ///
+    /// いずれかが 10000 bps を超える場合は [`CollateralError::HaircutOutOfRange`]。
+    pub fn new(
+        cash_bps: u32,
+        gov_bond_bps: u32,
+        corp_bond_bps: u32,
+        equity_bps: u32,
+    ) -> Result<Self, CollateralError> {
+        let config = Self {
+            cash_bps,
+            gov_bond_bps,
+            corp_bond_bps,
+            equity_bps,
+            ..Self::default()
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// 全ての基本値と加算が 10000 bps 以下か検証する。
+    ///
+    /// # Errors
+    ///
+    /// 範囲外の値があれば [`CollateralError::HaircutOutOfRange`]。
+    pub fn validate(&self) -> Result<(), CollateralError> {
+        [
+            self.cash_bps,
+            self.gov_bond_bps,
+            self.corp_bond_bps,
+            self.equity_bps,
+        ]
+        .iter()
+        .chain(&self.rating_addon_bps)
+        .chain(&self.maturity_addon_bps)
+        .find(|&&bps| bps > 10_000)
+        .map_or(Ok(()), |&bps| Err(CollateralError::HaircutOutOfRange(bps)))
+    }
+
     /// 担保タイプに対応するヘアカット（bps）を返す。
     #[must_use]
     pub const fn haircut_bps(&self, collateral_type: CollateralType) -> u32 {
@@ -59,37 +238,259 @@ impl HaircutConfig {
         }
     }
 
+    /// 保有の属性（タイプ・格付け・残存期間）から導くヘアカット（bps、上限 10000）。
+    #[must_use]
+    pub const fn holding_haircut_bps(&self, holding: &CollateralHolding) -> u32 {
+        let mut bps = self.haircut_bps(holding.collateral_type) as u64
+            + self.rating_addon_bps[holding.rating as usize] as u64;
+        if let Some(bucket) = maturity_bucket(holding.maturity_days) {
+            bps += self.maturity_addon_bps[bucket] as u64;
+        }
+        if bps > 10_000 {
+            10_000
+        } else {
+            bps as u32
+        }
+    }
+
     /// ヘアカット後の価値を計算する。
     ///
-    /// `value * (10000 - haircut_bps) / 10000`
+    /// `value * (10000 - haircut_bps) / 10000`（`i128` で計算）。10000 bps を超える
+    /// ヘアカットは 10000 bps とみなす。
     #[must_use]
     pub const fn apply_haircut(&self, collateral_type: CollateralType, value: i64) -> i64 {
-        let bps = self.haircut_bps(collateral_type) as i64;
-        value * (10_000 - bps) / 10_000
+        let mut bps = self.haircut_bps(collateral_type);
+        if bps > 10_000 {
+            bps = 10_000;
+        }
+        (value as i128 * (10_000 - bps as i128) / 10_000) as i64
+    }
+
+    /// ヘアカット後の価値を計算する。ヘアカットが範囲外ならエラー。
+    ///
+    /// # Errors
+    ///
+    /// ヘアカットが 10000 bps を超える場合は [`CollateralError::HaircutOutOfRange`]。
+    pub const fn try_apply_haircut(
+        &self,
+        collateral_type: CollateralType,
+        value: i64,
+    ) -> Result<i64, CollateralError> {
+        let bps = self.haircut_bps(collateral_type);
+        if bps > 10_000 {
+            return Err(CollateralError::HaircutOutOfRange(bps));
+        }
+        Ok((value as i128 * (10_000 - bps as i128) / 10_000) as i64)
     }
 }
 
 // ── Collateral Holding ─────────────────────────────────────────────────
 
-/// 単一タイプの担保保有。
-#[derive(Debug, Clone)]
+/// 単一銘柄の担保保有。
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CollateralHolding {
+    /// 資産 ID。
+    pub asset_id: u64,
     /// 担保タイプ。
     pub collateral_type: CollateralType,
-    /// 額面価値（ティック単位）。
+    /// 発行体 ID。
+    pub issuer_id: u64,
+    /// 信用格付け。
+    pub rating: CreditRating,
+    /// 残存期間（日）。0 は満期なし。
+    pub maturity_days: u32,
+    /// 通貨（ISO 4217）。
+    pub currency: [u8; 3],
+    /// 価格ボラティリティ（年率、bps）。
+    pub volatility_bps: u32,
+    /// 保有数量。
+    pub quantity: i64,
+    /// 単価（ティック単位）。
+    pub price: i64,
+    /// 評価額（`quantity * price`、ティック単位）。バケット資産では額面と一致。
     pub face_value: i64,
 }
 
+impl CollateralHolding {
+    /// 資産属性と数量から保有を作る。
+    #[must_use]
+    pub const fn new(asset: &CollateralAsset, quantity: i64) -> Self {
+        Self {
+            asset_id: asset.asset_id,
+            collateral_type: asset.collateral_type,
+            issuer_id: asset.issuer_id,
+            rating: asset.rating,
+            maturity_days: asset.maturity_days,
+            currency: asset.currency,
+            volatility_bps: asset.volatility_bps,
+            quantity,
+            price: asset.price,
+            face_value: quantity.saturating_mul(asset.price),
+        }
+    }
+
+    /// 評価額を数量と単価から再計算する。
+    #[inline]
+    const fn revalue(&mut self) {
+        self.face_value = self.quantity.saturating_mul(self.price);
+    }
+}
+
+// ── Haircut Schedule ───────────────────────────────────────────────────
+
+/// 保有ごとのヘアカット（bps）を決めるモデル。
+pub trait HaircutModel {
+    /// 保有に適用するヘアカット（bps）。10000 を超える値は 10000 として扱われる。
+    fn haircut_bps(&self, holding: &CollateralHolding) -> u32;
+}
+
+impl HaircutModel for HaircutConfig {
+    fn haircut_bps(&self, holding: &CollateralHolding) -> u32 {
+        self.holding_haircut_bps(holding)
+    }
+}
+
+/// ヘアカットグリッドのセル（担保タイプ × 格付け × 残存期間 × ボラティリティ帯）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HaircutGridKey {
+    /// 担保タイプ。
+    pub collateral_type: CollateralType,
+    /// 信用格付け。
+    pub rating: CreditRating,
+    /// 残存期間バケット（[`maturity_bucket`]、満期なしは `None`）。
+    pub maturity_bucket: Option<usize>,
+    /// ボラティリティ帯（[`HaircutSchedule::volatility_band`]）。
+    pub volatility_band: usize,
+}
+
+/// 追加ヘアカットの適用条件。
+#[derive(Debug, Clone)]
+pub enum AddOnCondition {
+    /// 特定の発行体（誤方向リスク：会員自身や関連会社の発行体など）。
+    Issuer(u64),
+    /// 特定の銘柄。
+    Asset(u64),
+    /// 特定の通貨（通貨ミスマッチ）。
+    Currency([u8; 3]),
+    /// 特定の担保タイプ。
+    CollateralType(CollateralType),
+    /// 指定格付けより低い格付け。
+    RatingBelow(CreditRating),
+    /// 任意の条件。
+    Custom(fn(&CollateralHolding) -> bool),
+}
+
+impl AddOnCondition {
+    /// 保有が条件に当てはまるか。
+    #[must_use]
+    pub fn matches(&self, holding: &CollateralHolding) -> bool {
+        match self {
+            Self::Issuer(id) => holding.issuer_id == *id,
+            Self::Asset(id) => holding.asset_id == *id,
+            Self::Currency(ccy) => holding.currency == *ccy,
+            Self::CollateralType(ct) => holding.collateral_type == *ct,
+            Self::RatingBelow(r) => holding.rating > *r,
+            Self::Custom(f) => f(holding),
+        }
+    }
+}
+
+/// 条件付きの追加ヘアカット。
+#[derive(Debug, Clone)]
+pub struct HaircutAddOn {
+    /// 適用条件。
+    pub condition: AddOnCondition,
+    /// 加算（bps）。
+    pub bps: u32,
+}
+
+/// 格付け・残存期間・ボラティリティのグリッドによるヘアカットスケジュール。
+///
+/// グリッドにセルがあればその値を、なければ `fallback` の属性別ヘアカットを基本値とし、
+/// 条件に当てはまる全ての追加ヘアカットを加算する（上限 10000 bps）。
+#[derive(Debug, Clone, Default)]
+pub struct HaircutSchedule {
+    /// ボラティリティ帯の上限（bps、昇順）。最後の上限を超えると最終帯。
+    pub volatility_bands_bps: Vec<u32>,
+    /// セル別の基本ヘアカット（bps）。
+    pub grid: HashMap<HaircutGridKey, u32>,
+    /// セルがない場合の基本ヘアカット。
+    pub fallback: HaircutConfig,
+    /// 追加ヘアカット。
+    pub addons: Vec<HaircutAddOn>,
+}
+
+impl HaircutSchedule {
+    /// ボラティリティ（bps）に対応する帯番号。
+    #[must_use]
+    pub fn volatility_band(&self, volatility_bps: u32) -> usize {
+        self.volatility_bands_bps
+            .iter()
+            .position(|&upper| volatility_bps <= upper)
+            .unwrap_or(self.volatility_bands_bps.len())
+    }
+
+    /// 保有が属するグリッドセル。
+    #[must_use]
+    pub fn grid_key(&self, holding: &CollateralHolding) -> HaircutGridKey {
+        HaircutGridKey {
+            collateral_type: holding.collateral_type,
+            rating: holding.rating,
+            maturity_bucket: maturity_bucket(holding.maturity_days),
+            volatility_band: self.volatility_band(holding.volatility_bps),
+        }
+    }
+
+    /// セルの基本ヘアカットを設定する。
+    pub fn insert(&mut self, key: HaircutGridKey, bps: u32) {
+        self.grid.insert(key, bps);
+    }
+
+    /// 追加ヘアカットを除いた基本ヘアカット（bps）。
+    #[must_use]
+    pub fn base_bps(&self, holding: &CollateralHolding) -> u32 {
+        self.grid
+            .get(&self.grid_key(holding))
+            .copied()
+            .unwrap_or_else(|| self.fallback.holding_haircut_bps(holding))
+    }
+
+    /// 保有に当てはまる追加ヘアカットの合計（bps）。
+    #[must_use]
+    pub fn addon_bps(&self, holding: &CollateralHolding) -> u32 {
+        self.addons
+            .iter()
+            .filter(|a| a.condition.matches(holding))
+            .fold(0u32, |acc, a| acc.saturating_add(a.bps))
+    }
+}
+
+impl HaircutModel for HaircutSchedule {
+    fn haircut_bps(&self, holding: &CollateralHolding) -> u32 {
+        self.base_bps(holding)
+            .saturating_add(self.addon_bps(holding))
+            .min(10_000)
+    }
+}
+
 // ── Collateral Account ─────────────────────────────────────────────────
 
-/// 担保アカウント（複数タイプの担保を保有）。
+/// 担保アカウント（銘柄別に担保を保有）。
 pub struct CollateralAccount {
     /// アカウント ID。
     account_id: u64,
-    /// 各タイプの保有額面（`Cash`, `GovBond`, `CorpBond`, `Equity`）。
-    holdings: [i64; 4],
+    /// 資産 ID 別の保有（数量ゼロの保有は持たない）。
+    holdings: BTreeMap<u64, CollateralHolding>,
     /// ヘアカット設定。
     haircut: HaircutConfig,
+    /// 設定されていれば `haircut` の代わりに使うヘアカットモデル。
+    model: Option<Box<dyn HaircutModel + Send + Sync>>,
+    /// 現在時刻（ナノ秒）。履歴のタイムスタンプに使う。
+    now_ns: u64,
+    /// 取引履歴（時刻順）。
+    history: Vec<CollateralEvent>,
+    /// 利息を計上済みの時刻（ナノ秒）。
+    accrued_to_ns: u64,
 }
 
 impl CollateralAccount {
@@ -98,102 +499,477 @@ impl CollateralAccount {
     pub const fn new(account_id: u64, haircut: HaircutConfig) -> Self {
         Self {
             account_id,
-            holdings: [0; 4],
+            holdings: BTreeMap::new(),
             haircut,
+            model: None,
+            now_ns: 0,
+            history: Vec::new(),
+            accrued_to_ns: 0,
         }
     }
 
+    /// ヘアカットモデル（[`HaircutSchedule`] 等）を設定する。以後のヘアカット後価値は
+    /// [`HaircutConfig`] ではなくこのモデルで計算される。
+    pub fn set_haircut_model(&mut self, model: Box<dyn HaircutModel + Send + Sync>) {
+        self.model = Some(model);
+    }
+
+    /// ヘアカットモデルを外し、[`HaircutConfig`] に戻す。
+    pub fn clear_haircut_model(&mut self) {
+        self.model = None;
+    }
+
+    /// 保有 1 件に適用されるヘアカット（bps）。
+    #[must_use]
+    pub fn holding_haircut_bps(&self, holding: &CollateralHolding) -> u32 {
+        self.model.as_ref().map_or_else(
+            || self.haircut.holding_haircut_bps(holding),
+            |m| m.haircut_bps(holding).min(10_000),
+        )
+    }
+
     /// アカウント ID。
     #[must_use]
     pub const fn account_id(&self) -> u64 {
         self.account_id
     }
 
-    /// 担保を預け入れる。
-    pub const fn deposit(&mut self, collateral_type: CollateralType, amount: i64) {
-        if amount > 0 {
-            self.holdings[collateral_type as usize] += amount;
+    /// ヘアカット設定。
+    #[must_use]
+    pub const fn haircut(&self) -> &HaircutConfig {
+        &self.haircut
+    }
+
+    /// 担保タイプのバケット資産として預け入れる。
+    pub fn deposit(&mut self, collateral_type: CollateralType, amount: i64) {
+        self.deposit_asset(&CollateralAsset::bucket(collateral_type), amount);
+    }
+
+    /// 担保タイプのバケット資産を引き出す。残高不足の場合は `false` を返す。
+    pub fn withdraw(&mut self, collateral_type: CollateralType, amount: i64) -> bool {
+        self.withdraw_asset(collateral_type as u64, amount)
+    }
+
+    /// 担保タイプのバケット資産として預け入れる。残高が `i64` を超える場合はエラー。
+    ///
+    /// # Errors
+    ///
+    /// [`try_deposit_asset`](Self::try_deposit_asset) を参照。
+    pub fn try_deposit(
+        &mut self,
+        collateral_type: CollateralType,
+        amount: i64,
+    ) -> Result<(), CollateralError> {
+        self.try_deposit_asset(&CollateralAsset::bucket(collateral_type), amount)
+    }
+
+    /// 銘柄を預け入れる。数量・評価額があふれる場合は保有を変更せずエラーを返す。
+    ///
+    /// 既存保有がある場合は属性が一致しなければならず、単価は保有の値（直近の時価）を
+    /// 維持する。
+    ///
+    /// # Errors
+    ///
+    /// 数量が正でなければ [`CollateralError::InvalidQuantity`]、予約 ID をバケット以外に
+    /// 使えば [`CollateralError::ReservedAssetId`]、既存保有と属性が異なれば
+    /// [`CollateralError::AssetMismatch`]、預け入れ後の数量または評価額が `i64` を
+    /// 超えれば [`CollateralError::Overflow`]。
+    pub fn try_deposit_asset(
+        &mut self,
+        asset: &CollateralAsset,
+        quantity: i64,
+    ) -> Result<(), CollateralError> {
+        if quantity <= 0 {
+            return Err(CollateralError::InvalidQuantity(quantity));
+        }
+        self.check_asset(asset)?;
+        self.try_credit(asset, quantity)?;
+        self.record(CollateralEventKind::Deposit {
+            asset_id: asset.asset_id,
+            quantity,
+        });
+        Ok(())
+    }
+
+    /// 銘柄を預け入れる。予約 ID をバケット以外に使った場合や既存保有と属性が異なる
+    /// 場合は預け入れず `false` を返す。既存保有の単価は維持する。
+    ///
+    /// 数量・評価額は飽和演算で計算する。あふれを検出するには
+    /// [`try_deposit_asset`](Self::try_deposit_asset) を使う。
+    pub fn deposit_asset(&mut self, asset: &CollateralAsset, quantity: i64) -> bool {
+        if quantity <= 0 {
+            return true;
+        }
+        if self.check_asset(asset).is_err() {
+            return false;
+        }
+        self.credit(asset, quantity);
+        self.record(CollateralEventKind::Deposit {
+            asset_id: asset.asset_id,
+            quantity,
+        });
+        true
+    }
+
+    /// 預け入れる銘柄が予約 ID・既存保有の属性と矛盾しないか検査する。
+    fn check_asset(&self, asset: &CollateralAsset) -> Result<(), CollateralError> {
+        if asset.asset_id <= CollateralType::Equity as u64
+            && *asset != CollateralAsset::bucket(asset.collateral_type)
+        {
+            return Err(CollateralError::ReservedAssetId(asset.asset_id));
+        }
+        match self.holding(asset.asset_id) {
+            Some(h) if !asset.matches_holding(h) => {
+                Err(CollateralError::AssetMismatch(asset.asset_id))
+            }
+            _ => Ok(()),
         }
     }
 
-    /// 担保を引き出す。残高不足の場合は `false` を返す。
-    pub const fn withdraw(&mut self, collateral_type: CollateralType, amount: i64) -> bool {
-        if amount <= 0 {
+    /// 銘柄を引き出す。保有不足の場合は `false` を返す。
+    pub fn withdraw_asset(&mut self, asset_id: u64, quantity: i64) -> bool {
+        if quantity <= 0 {
             return true;
         }
-        let idx = collateral_type as usize;
-        if self.holdings[idx] < amount {
+        if !self.debit(asset_id, quantity) {
+            return false;
+        }
+        self.record(CollateralEventKind::Withdrawal { asset_id, quantity });
+        true
+    }
+
+    /// 保有を増やす（履歴は記録しない）。数量・評価額があふれる場合は保有を変更せず
+    /// [`CollateralError::Overflow`] を返す。
+    fn try_credit(
+        &mut self,
+        asset: &CollateralAsset,
+        quantity: i64,
+    ) -> Result<(), CollateralError> {
+        let (held, price) = self
+            .holding(asset.asset_id)
+            .map_or((0, asset.price), |h| (h.quantity, h.price));
+        let total = held
+            .checked_add(quantity)
+            .ok_or(CollateralError::Overflow)?;
+        total.checked_mul(price).ok_or(CollateralError::Overflow)?;
+        self.credit(asset, quantity);
+        Ok(())
+    }
+
+    /// 保有を増やす（履歴は記録しない）。既存保有の属性と単価は変更しない。
+    fn credit(&mut self, asset: &CollateralAsset, quantity: i64) {
+        let holding = self
+            .holdings
+            .entry(asset.asset_id)
+            .or_insert_with(|| CollateralHolding::new(asset, 0));
+        holding.quantity = holding.quantity.saturating_add(quantity);
+        holding.revalue();
+    }
+
+    /// 保有を減らす（履歴は記録しない）。保有不足の場合は `false` を返す。
+    fn debit(&mut self, asset_id: u64, quantity: i64) -> bool {
+        let Some(holding) = self.holdings.get_mut(&asset_id) else {
+            return false;
+        };
+        if holding.quantity < quantity {
             return false;
         }
-        self.holdings[idx] -= amount;
+        holding.quantity -= quantity;
+        holding.revalue();
+        if holding.quantity == 0 {
+            self.holdings.remove(&asset_id);
+        }
         true
     }
 
-    /// 指定タイプの額面残高。
+    /// 銘柄の保有。
+    #[must_use]
+    pub fn holding(&self, asset_id: u64) -> Option<&CollateralHolding> {
+        self.holdings.get(&asset_id)
+    }
+
+    /// 指定タイプの評価額合計。
     #[must_use]
-    pub const fn face_value(&self, collateral_type: CollateralType) -> i64 {
-        self.holdings[collateral_type as usize]
+    pub fn face_value(&self, collateral_type: CollateralType) -> i64 {
+        let values = self
+            .holdings
+            .values()
+            .filter(|h| h.collateral_type == collateral_type)
+            .map(|h| h.face_value);
+        saturating_sum(values)
+    }
+
+    /// 保有 1 件のヘアカット後価値（属性から導いたヘアカットを適用）。
+    #[must_use]
+    pub fn holding_adjusted_value(&self, holding: &CollateralHolding) -> i64 {
+        let bps = self.holding_haircut_bps(holding) as i128;
+        (holding.face_value as i128 * (10_000 - bps) / 10_000) as i64
     }
 
     /// 指定タイプのヘアカット後価値。
     #[must_use]
-    pub const fn adjusted_value(&self, collateral_type: CollateralType) -> i64 {
-        self.haircut
-            .apply_haircut(collateral_type, self.holdings[collateral_type as usize])
+    pub fn adjusted_value(&self, collateral_type: CollateralType) -> i64 {
+        saturating_sum(
+            self.holdings
+                .values()
+                .filter(|h| h.collateral_type == collateral_type)
+                .map(|h| self.holding_adjusted_value(h)),
+        )
     }
 
-    /// 全タイプ合計の額面残高。
+    /// 全タイプ合計の評価額。
     #[must_use]
-    pub const fn total_face_value(&self) -> i64 {
-        self.holdings[0] + self.holdings[1] + self.holdings[2] + self.holdings[3]
+    pub fn total_face_value(&self) -> i64 {
+        saturating_sum(self.holdings.values().map(|h| h.face_value))
     }
 
     /// 全タイプ合計のヘアカット後価値。
     #[must_use]
-    pub const fn total_adjusted_value(&self) -> i64 {
-        self.adjusted_value(CollateralType::Cash)
-            + self.adjusted_value(CollateralType::GovernmentBond)
-            + self.adjusted_value(CollateralType::CorporateBond)
-            + self.adjusted_value(CollateralType::Equity)
+    pub fn total_adjusted_value(&self) -> i64 {
+        saturating_sum(
+            self.holdings
+                .values()
+                .map(|h| self.holding_adjusted_value(h)),
+        )
+    }
+
+    /// 銘柄の単価を更新する。保有がない場合や単価が正でない場合は `false` を返す。
+    pub fn set_price(&mut self, asset_id: u64, price: i64) -> bool {
+        if price <= 0 {
+            return false;
+        }
+        let Some(holding) = self.holdings.get_mut(&asset_id) else {
+            return false;
+        };
+        holding.price = price;
+        holding.revalue();
+        true
     }
 
-    /// 全保有の詳細を返す。
+    /// 全保有の詳細を資産 ID 順に返す。
     #[must_use]
     pub fn holdings(&self) -> Vec<CollateralHolding> {
-        let types = [
-            CollateralType::Cash,
-            CollateralType::GovernmentBond,
-            CollateralType::CorporateBond,
-            CollateralType::Equity,
-        ];
-        types
-            .iter()
-            .filter(|&&ct| self.holdings[ct as usize] > 0)
-            .map(|&ct| CollateralHolding {
-                collateral_type: ct,
-                face_value: self.holdings[ct as usize],
-            })
-            .collect()
+        self.holdings.values().cloned().collect()
     }
 
     /// コンテンツハッシュ。
     #[must_use]
     pub fn content_hash(&self) -> u64 {
-        let mut data = [0u8; 40];
-        data[0..8].copy_from_slice(&self.account_id.to_le_bytes());
-        for (i, &h) in self.holdings.iter().enumerate() {
-            data[8 + i * 8..16 + i * 8].copy_from_slice(&h.to_le_bytes());
+        let mut data = Vec::with_capacity(8 + self.holdings.len() * 24);
+        data.extend_from_slice(&self.account_id.to_le_bytes());
+        for h in self.holdings.values() {
+            data.extend_from_slice(&h.asset_id.to_le_bytes());
+            data.extend_from_slice(&h.quantity.to_le_bytes());
+            data.extend_from_slice(&h.price.to_le_bytes());
         }
         fnv1a(&data)
     }
 }
 
+// ── Mark-to-Market ─────────────────────────────────────────────────────
+
+/// 時点の資産価格一覧。
+#[derive(Debug, Clone, Default)]
+pub struct PriceSnapshot {
+    /// 価格の時点（ナノ秒）。
+    pub timestamp_ns: u64,
+    /// 資産 ID 別の単価（ティック単位）。
+    pub prices: HashMap<u64, i64>,
+}
+
+impl PriceSnapshot {
+    /// 空のスナップショットを作る。
+    #[must_use]
+    pub fn new(timestamp_ns: u64) -> Self {
+        Self {
+            timestamp_ns,
+            prices: HashMap::new(),
+        }
+    }
+
+    /// 単価を設定する。
+    pub fn insert(&mut self, asset_id: u64, price: i64) {
+        self.prices.insert(asset_id, price);
+    }
+
+    /// 単価を返す。
+    #[must_use]
+    pub fn price(&self, asset_id: u64) -> Option<i64> {
+        self.prices.get(&asset_id).copied()
+    }
+}
+
+/// 保有 1 件の再評価結果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HoldingRevaluation {
+    /// 資産 ID。
+    pub asset_id: u64,
+    /// 旧単価。
+    pub old_price: i64,
+    /// 新単価。
+    pub new_price: i64,
+    /// 評価額の変化。
+    pub value_change: i64,
+}
+
+/// アカウント 1 件の再評価結果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountRevaluation {
+    /// アカウント ID。
+    pub account_id: u64,
+    /// 再評価前の評価額。
+    pub market_before: i64,
+    /// 再評価後の評価額。
+    pub market_after: i64,
+    /// 再評価前のヘアカット後価値。
+    pub adjusted_before: i64,
+    /// 再評価後のヘアカット後価値。
+    pub adjusted_after: i64,
+    /// 証拠金所要額（該当する要件の `total_margin` 合計）。
+    pub requirement: i64,
+    /// 不足額（`requirement - adjusted_after`、不足がなければ 0）。
+    pub shortfall: i64,
+    /// 単価が変わった保有。
+    pub changes: Vec<HoldingRevaluation>,
+    /// 有効な価格がなく据え置いた銘柄（バケット資産を除く）。
+    pub missing_prices: Vec<u64>,
+}
+
+impl AccountRevaluation {
+    /// 担保が所要額を下回っているか。
+    #[inline]
+    #[must_use]
+    pub const fn is_short(&self) -> bool {
+        self.shortfall > 0
+    }
+
+    /// ヘアカット後価値の変化。
+    #[inline]
+    #[must_use]
+    pub const fn adjusted_change(&self) -> i64 {
+        self.adjusted_after.saturating_sub(self.adjusted_before)
+    }
+}
+
+/// 全アカウントの再評価結果。
+#[derive(Debug, Clone)]
+pub struct RevaluationReport {
+    /// 使用した価格の時点。
+    pub timestamp_ns: u64,
+    /// アカウント別の結果（入力順）。
+    pub accounts: Vec<AccountRevaluation>,
+    /// 評価額変化の合計。
+    pub total_market_change: i64,
+    /// ヘアカット後価値変化の合計。
+    pub total_adjusted_change: i64,
+}
+
+impl RevaluationReport {
+    /// 担保不足のアカウント。
+    pub fn short_accounts(&self) -> impl Iterator<Item = &AccountRevaluation> {
+        self.accounts.iter().filter(|a| a.is_short())
+    }
+}
+
+impl CollateralAccount {
+    /// 価格スナップショットで全保有を再評価する。
+    ///
+    /// バケット資産（資産 ID 0〜3）は単価固定のため対象外。`requirement` と比較して
+    /// 不足額を算出する。
+    pub fn revalue(&mut self, prices: &PriceSnapshot, requirement: i64) -> AccountRevaluation {
+        let market_before = self.total_face_value();
+        let adjusted_before = self.total_adjusted_value();
+        let mut changes = Vec::new();
+        let mut missing_prices = Vec::new();
+
+        let asset_ids: Vec<u64> = self
+            .holdings
+            .keys()
+            .copied()
+            .filter(|&id| id > CollateralType::Equity as u64)
+            .collect();
+        for asset_id in asset_ids {
+            let Some(new_price) = prices.price(asset_id).filter(|&p| p > 0) else {
+                missing_prices.push(asset_id);
+                continue;
+            };
+            let Some(holding) = self.holdings.get(&asset_id) else {
+                continue;
+            };
+            let (old_price, old_value) = (holding.price, holding.face_value);
+            if new_price == old_price {
+                continue;
+            }
+            self.set_price(asset_id, new_price);
+            let new_value = self.holdings.get(&asset_id).map_or(0, |h| h.face_value);
+            changes.push(HoldingRevaluation {
+                asset_id,
+                old_price,
+                new_price,
+                value_change: new_value.saturating_sub(old_value),
+            });
+        }
+
+        let adjusted_after = self.total_adjusted_value();
+        AccountRevaluation {
+            account_id: self.account_id,
+            market_before,
+            market_after: self.total_face_value(),
+            adjusted_before,
+            adjusted_after,
+            requirement,
+            shortfall: requirement.saturating_sub(adjusted_after).max(0),
+            changes,
+            missing_prices,
+        }
+    }
+}
+
+/// 全アカウントを価格スナップショットで再評価し、証拠金所要額との過不足を判定する。
+///
+/// 同じアカウントの要件が複数ある場合は `total_margin` を合計する。要件のない
+/// アカウントの所要額は 0。
+pub fn revalue_collateral(
+    accounts: &mut [CollateralAccount],
+    prices: &PriceSnapshot,
+    requirements: &[MarginRequirement],
+) -> RevaluationReport {
+    let mut required: HashMap<u64, i64> = HashMap::new();
+    for r in requirements {
+        let total = required.entry(r.account_id).or_insert(0);
+        *total = total.saturating_add(r.total_margin);
+    }
+
+    let results: Vec<AccountRevaluation> = accounts
+        .iter_mut()
+        .map(|acc| {
+            let requirement = required.get(&acc.account_id()).copied().unwrap_or(0);
+            acc.revalue(prices, requirement)
+        })
+        .collect();
+
+    RevaluationReport {
+        timestamp_ns: prices.timestamp_ns,
+        total_market_change: saturating_sum(
+            results
+                .iter()
+                .map(|r| r.market_after.saturating_sub(r.market_before)),
+        ),
+        total_adjusted_change: saturating_sum(
+            results.iter().map(AccountRevaluation::adjusted_change),
+        ),
+        accounts: results,
+    }
+}
+
 // ── Concentration Limits ───────────────────────────────────────────────
 
-/// 集中リスク制限（タイプ別上限比率）。
+/// 集中リスク制限（タイプ別・発行体別の上限比率と絶対上限）。
 ///
-/// 各タイプの担保が全担保（ヘアカット後）に占める割合の上限を設定する。
-/// 上限は bps で表現（10000 = 100%）。
+/// 比率上限は bps で表現（10000 = 100%）し、10000 以上は制限なしとみなす。比率の分母は
+/// [`check_concentration`] では全担保（ヘアカット後）、
+/// [`check_concentration_against_requirement`] では証拠金所要額。
+/// 絶対上限はヘアカット後価値で表現する。
 #[derive(Debug, Clone)]
 pub struct ConcentrationLimits {
     /// Cash の上限 bps（通常 10000 = 制限なし）。
@@ -204,6 +980,12 @@ pub struct ConcentrationLimits {
     pub corp_bond_max_bps: u32,
     /// 株式の上限 bps。
     pub equity_max_bps: u32,
+    /// 単一発行体の上限 bps。現金と発行体 ID 0（バケット資産）は対象外。
+    pub issuer_max_bps: u32,
+    /// タイプ別の絶対上限（`CollateralType as usize` で索引、`None` = 制限なし）。
+    pub type_max_value: [Option<i64>; 4],
+    /// 単一発行体の絶対上限（`None` = 制限なし）。
+    pub issuer_max_value: Option<i64>,
 }
 
 impl Default for ConcentrationLimits {
@@ -213,24 +995,107 @@ impl Default for ConcentrationLimits {
             gov_bond_max_bps: 10_000, // 制限なし
             corp_bond_max_bps: 5_000, // 50%
             equity_max_bps: 3_000,    // 30%
+            issuer_max_bps: 10_000,   // 制限なし
+            type_max_value: [None; 4],
+            issuer_max_value: None,
+        }
+    }
+}
+
+impl ConcentrationLimits {
+    /// 制限なし。
+    #[must_use]
+    pub const fn unlimited() -> Self {
+        Self {
+            cash_max_bps: 10_000,
+            gov_bond_max_bps: 10_000,
+            corp_bond_max_bps: 10_000,
+            equity_max_bps: 10_000,
+            issuer_max_bps: 10_000,
+            type_max_value: [None; 4],
+            issuer_max_value: None,
+        }
+    }
+
+    /// 担保タイプの比率上限（bps）。
+    #[inline]
+    #[must_use]
+    pub const fn type_max_bps(&self, collateral_type: CollateralType) -> u32 {
+        match collateral_type {
+            CollateralType::Cash => self.cash_max_bps,
+            CollateralType::GovernmentBond => self.gov_bond_max_bps,
+            CollateralType::CorporateBond => self.corp_bond_max_bps,
+            CollateralType::Equity => self.equity_max_bps,
         }
     }
+
+    /// 分母 `basis` に対する担保タイプの許容額（比率上限と絶対上限の小さい方）。
+    #[must_use]
+    pub fn type_limit_value(&self, collateral_type: CollateralType, basis: i64) -> i64 {
+        limit_value(
+            self.type_max_bps(collateral_type),
+            self.type_max_value[collateral_type as usize],
+            basis,
+        )
+    }
+
+    /// 分母 `basis` に対する単一発行体の許容額。
+    #[must_use]
+    pub fn issuer_limit_value(&self, basis: i64) -> i64 {
+        limit_value(self.issuer_max_bps, self.issuer_max_value, basis)
+    }
+}
+
+/// 発行体制限の対象となる発行体 ID（現金とバケット資産は `None`）。
+#[inline]
+#[must_use]
+pub const fn limited_issuer(holding: &CollateralHolding) -> Option<u64> {
+    match (holding.collateral_type, holding.issuer_id) {
+        (CollateralType::Cash, _) | (_, 0) => None,
+        (_, issuer_id) => Some(issuer_id),
+    }
+}
+
+/// 比率上限と絶対上限から許容額を求める（制限なしは `i64::MAX`）。
+fn limit_value(max_bps: u32, max_value: Option<i64>, basis: i64) -> i64 {
+    let share = if max_bps >= 10_000 {
+        i64::MAX
+    } else {
+        (basis.max(0) as i128 * max_bps as i128 / 10_000) as i64
+    };
+    share.min(max_value.unwrap_or(i64::MAX).max(0))
+}
+
+/// 集中リスク制限の対象。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConcentrationScope {
+    /// 担保タイプ。
+    CollateralType(CollateralType),
+    /// 発行体。
+    Issuer(u64),
 }
 
 /// 集中リスク違反。
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ConcentrationBreach {
-    /// 違反した担保タイプ。
-    pub collateral_type: CollateralType,
-    /// 現在の比率（bps）。
+    /// 違反した対象。
+    pub scope: ConcentrationScope,
+    /// 対象のヘアカット後価値。
+    pub value: i64,
+    /// 許容額（比率上限と絶対上限の小さい方）。
+    pub limit_value: i64,
+    /// 超過額（価値をゼロとみなすべき額）。
+    pub excess: i64,
+    /// 分母に対する現在の比率（bps）。
     pub current_bps: u32,
-    /// 上限（bps）。
+    /// 比率上限（bps）。
     pub limit_bps: u32,
 }
 
-/// 担保アカウントの集中リスクをチェックする。
+/// 担保アカウントの集中リスクをチェックする（分母は全担保のヘアカット後価値）。
 ///
-/// 違反があった場合、全違反の一覧を返す。
+/// 違反があった場合、全違反の一覧を返す。タイプ別の違反が先、発行体別の違反は
+/// 発行体 ID 順。
 #[must_use]
 pub fn check_concentration(
     account: &CollateralAccount,
@@ -240,30 +1105,642 @@ pub fn check_concentration(
     if total <= 0 {
         return Vec::new();
     }
+    concentration_breaches(account, limits, total)
+}
 
-    let types_and_limits = [
-        (CollateralType::Cash, limits.cash_max_bps),
-        (CollateralType::GovernmentBond, limits.gov_bond_max_bps),
-        (CollateralType::CorporateBond, limits.corp_bond_max_bps),
-        (CollateralType::Equity, limits.equity_max_bps),
+/// 証拠金所要額を分母として集中リスクをチェックする。
+///
+/// 所要額が 0 以下の場合、比率上限は適用せず絶対上限のみを判定する。
+#[must_use]
+pub fn check_concentration_against_requirement(
+    account: &CollateralAccount,
+    limits: &ConcentrationLimits,
+    requirement: i64,
+) -> Vec<ConcentrationBreach> {
+    let basis = if requirement > 0 { requirement } else { 0 };
+    let limits = if basis == 0 {
+        ConcentrationLimits {
+            type_max_value: limits.type_max_value,
+            issuer_max_value: limits.issuer_max_value,
+            ..ConcentrationLimits::unlimited()
+        }
+    } else {
+        limits.clone()
+    };
+    concentration_breaches(account, &limits, basis)
+}
+
+fn concentration_breaches(
+    account: &CollateralAccount,
+    limits: &ConcentrationLimits,
+    basis: i64,
+) -> Vec<ConcentrationBreach> {
+    let mut by_type = [0_i64; 4];
+    let mut by_issuer: BTreeMap<u64, i64> = BTreeMap::new();
+    for h in account.holdings.values() {
+        let adjusted = account.holding_adjusted_value(h);
+        let slot = &mut by_type[h.collateral_type as usize];
+        *slot = slot.saturating_add(adjusted);
+        if let Some(issuer_id) = limited_issuer(h) {
+            let slot = by_issuer.entry(issuer_id).or_insert(0);
+            *slot = slot.saturating_add(adjusted);
+        }
+    }
+
+    let breach = |scope, value: i64, limit_value: i64, limit_bps| {
+        (value > limit_value).then(|| ConcentrationBreach {
+            scope,
+            value,
+            limit_value,
+            excess: value - limit_value,
+            current_bps: if basis > 0 {
+                (value as i128 * 10_000 / basis as i128).min(u32::MAX as i128) as u32
+            } else {
+                u32::MAX
+            },
+            limit_bps,
+        })
+    };
+
+    let types = [
+        CollateralType::Cash,
+        CollateralType::GovernmentBond,
+        CollateralType::CorporateBond,
+        CollateralType::Equity,
     ];
+    let mut breaches: Vec<ConcentrationBreach> = types
+        .into_iter()
+        .filter_map(|ct| {
+            breach(
+                ConcentrationScope::CollateralType(ct),
+                by_type[ct as usize],
+                limits.type_limit_value(ct, basis),
+                limits.type_max_bps(ct),
+            )
+        })
+        .collect();
+    let issuer_limit = limits.issuer_limit_value(basis);
+    breaches.extend(by_issuer.into_iter().filter_map(|(issuer_id, value)| {
+        breach(
+            ConcentrationScope::Issuer(issuer_id),
+            value,
+            issuer_limit,
+            limits.issuer_max_bps,
+        )
+    }));
+    breaches
+}
+
+// ── Eligible Value ─────────────────────────────────────────────────────
+
+/// 集中リスク制限の超過分の扱い。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExcessTreatment {
+    /// 超過分を価値ゼロとして除外する。
+    Exclude,
+    /// 超過分に追加ヘアカット（bps）を適用する。
+    Haircut(u32),
+}
+
+impl ExcessTreatment {
+    /// 超過分から控除する割合（bps）。
+    #[inline]
+    #[must_use]
+    pub const fn deduction_bps(self) -> u32 {
+        match self {
+            Self::Exclude => 10_000,
+            Self::Haircut(bps) => {
+                if bps > 10_000 {
+                    10_000
+                } else {
+                    bps
+                }
+            }
+        }
+    }
+}
+
+/// 集中リスク調整後の担保価値。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EligibleValue {
+    /// 調整前のヘアカット後価値。
+    pub adjusted_value: i64,
+    /// 集中リスク調整後の適格価値。
+    pub eligible_value: i64,
+    /// 控除額（`adjusted_value - eligible_value`）。
+    pub deduction: i64,
+    /// 調整の根拠となった違反。
+    pub breaches: Vec<ConcentrationBreach>,
+}
+
+impl CollateralAccount {
+    /// 集中リスク制限の超過分を控除した適格価値（比率の分母は全担保のヘアカット後価値）。
+    #[must_use]
+    pub fn eligible_value(
+        &self,
+        limits: &ConcentrationLimits,
+        treatment: ExcessTreatment,
+    ) -> EligibleValue {
+        let total = self.total_adjusted_value();
+        let breaches = check_concentration(self, limits);
+        self.apply_concentration(limits, total, breaches, treatment)
+    }
+
+    /// 証拠金所要額を分母として超過分を控除した適格価値。
+    #[must_use]
+    pub fn eligible_value_against_requirement(
+        &self,
+        limits: &ConcentrationLimits,
+        requirement: i64,
+        treatment: ExcessTreatment,
+    ) -> EligibleValue {
+        let breaches = check_concentration_against_requirement(self, limits, requirement);
+        let basis = requirement.max(0);
+        let limits = if basis == 0 {
+            ConcentrationLimits {
+                type_max_value: limits.type_max_value,
+                issuer_max_value: limits.issuer_max_value,
+                ..ConcentrationLimits::unlimited()
+            }
+        } else {
+            limits.clone()
+        };
+        self.apply_concentration(&limits, basis, breaches, treatment)
+    }
+
+    /// 超過分を保有に按分して控除する。
+    ///
+    /// まずタイプ別の超過をタイプ内の保有に按分し、控除後の価値で発行体別の超過を
+    /// 按分する。重複する超過を二重に控除しない。
+    fn apply_concentration(
+        &self,
+        limits: &ConcentrationLimits,
+        basis: i64,
+        breaches: Vec<ConcentrationBreach>,
+        treatment: ExcessTreatment,
+    ) -> EligibleValue {
+        let rate = treatment.deduction_bps() as i128;
+        let mut values: Vec<(&CollateralHolding, i64)> = self
+            .holdings
+            .values()
+            .map(|h| (h, self.holding_adjusted_value(h)))
+            .collect();
+        let adjusted_value = saturating_sum(values.iter().map(|&(_, v)| v));
+
+        let deduct = |values: &mut Vec<(&CollateralHolding, i64)>,
+                      in_scope: &dyn Fn(&CollateralHolding) -> bool,
+                      limit: i64| {
+            let total = saturating_sum(values.iter().filter(|(h, _)| in_scope(h)).map(|&(_, v)| v));
+            if total <= limit || total <= 0 {
+                return;
+            }
+            let excess = (total - limit) as i128;
+            for (h, v) in values.iter_mut() {
+                if in_scope(h) {
+                    let share = *v as i128 * excess / total as i128;
+                    *v -= (share * rate / 10_000) as i64;
+                }
+            }
+        };
+
+        for ct in [
+            CollateralType::Cash,
+            CollateralType::GovernmentBond,
+            CollateralType::CorporateBond,
+            CollateralType::Equity,
+        ] {
+            let limit = limits.type_limit_value(ct, basis);
+            deduct(&mut values, &|h| h.collateral_type == ct, limit);
+        }
+        let issuer_limit = limits.issuer_limit_value(basis);
+        let issuers: BTreeSet<u64> = values
+            .iter()
+            .filter_map(|(h, _)| limited_issuer(h))
+            .collect();
+        for issuer_id in issuers {
+            deduct(
+                &mut values,
+                &|h| limited_issuer(h) == Some(issuer_id),
+                issuer_limit,
+            );
+        }
+
+        let eligible_value = saturating_sum(values.iter().map(|&(_, v)| v));
+        EligibleValue {
+            adjusted_value,
+            eligible_value,
+            deduction: adjusted_value.saturating_sub(eligible_value),
+            breaches,
+        }
+    }
+}
+
+// ── Substitution ───────────────────────────────────────────────────────
+
+/// 担保差し替えの拒否理由。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubstitutionError {
+    /// 数量が正でない。
+    InvalidQuantity,
+    /// 引き出す銘柄と預け入れる銘柄が同じ。
+    SameAsset,
+    /// 預け入れる銘柄が予約 ID・既存保有の属性と矛盾する。
+    InvalidAsset(CollateralError),
+    /// 引き出す銘柄の保有が不足している。
+    InsufficientHoldings {
+        /// 資産 ID。
+        asset_id: u64,
+        /// 現在の保有数量。
+        held: i64,
+        /// 引き出そうとした数量。
+        requested: i64,
+    },
+    /// 差し替え後のヘアカット後価値が所要額を下回る。
+    InsufficientCollateral {
+        /// 差し替え後のヘアカット後価値。
+        adjusted_value: i64,
+        /// 証拠金所要額。
+        requirement: i64,
+    },
+    /// 差し替え後に集中リスク制限を超える。
+    ConcentrationBreached(Vec<ConcentrationBreach>),
+    /// 預け入れ後の数量または評価額が `i64` の範囲を超える。
+    Overflow,
+}
 
-    let mut breaches = Vec::new();
-    for (ct, limit_bps) in types_and_limits {
-        let adjusted = account.adjusted_value(ct);
-        if adjusted <= 0 {
-            continue;
-        }
-        let current_bps = (adjusted * 10_000 / total) as u32;
-        if current_bps > limit_bps {
-            breaches.push(ConcentrationBreach {
-                collateral_type: ct,
-                current_bps,
-                limit_bps,
+/// 成立した担保差し替えの結果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Substitution {
+    /// 引き出した資産 ID。
+    pub released_asset_id: u64,
+    /// 預け入れた資産 ID。
+    pub delivered_asset_id: u64,
+    /// 差し替え前のヘアカット後価値。
+    pub adjusted_before: i64,
+    /// 差し替え後のヘアカット後価値。
+    pub adjusted_after: i64,
+}
+
+impl CollateralAccount {
+    /// 担保を差し替える（`release_asset_id` を引き出し `deliver` を預け入れる）。
+    ///
+    /// 差し替え後のヘアカット後価値が `requirement` 以上で、`limits` の集中リスク制限に
+    /// 新たに違反しない場合のみ両方の処理を適用する。差し替え前から違反している対象は、
+    /// 超過額が増えない限り拒否しない（違反を減らす差し替えは受け付ける）。拒否した
+    /// 場合は保有を変更しない。
+    ///
+    /// # Errors
+    ///
+    /// 数量・保有の不足、預け入れる銘柄の不整合、数量・評価額のあふれ、所要額の不足、
+    /// 集中リスク違反の場合は [`SubstitutionError`]。
+    pub fn substitute(
+        &mut self,
+        release_asset_id: u64,
+        release_quantity: i64,
+        deliver: &CollateralAsset,
+        deliver_quantity: i64,
+        requirement: i64,
+        limits: &ConcentrationLimits,
+    ) -> Result<Substitution, SubstitutionError> {
+        if release_quantity <= 0 || deliver_quantity <= 0 {
+            return Err(SubstitutionError::InvalidQuantity);
+        }
+        if release_asset_id == deliver.asset_id {
+            return Err(SubstitutionError::SameAsset);
+        }
+        self.check_asset(deliver)
+            .map_err(SubstitutionError::InvalidAsset)?;
+        let held = self.holding(release_asset_id).map_or(0, |h| h.quantity);
+        if held < release_quantity {
+            return Err(SubstitutionError::InsufficientHoldings {
+                asset_id: release_asset_id,
+                held,
+                requested: release_quantity,
             });
         }
+
+        let adjusted_before = self.total_adjusted_value();
+        let breaches_before = check_concentration(self, limits);
+        let saved = self.holdings.clone();
+        self.debit(release_asset_id, release_quantity);
+        if self.try_credit(deliver, deliver_quantity).is_err() {
+            self.holdings = saved;
+            return Err(SubstitutionError::Overflow);
+        }
+
+        let adjusted_after = self.total_adjusted_value();
+        let rejection = if adjusted_after < requirement {
+            Some(SubstitutionError::InsufficientCollateral {
+                adjusted_value: adjusted_after,
+                requirement,
+            })
+        } else {
+            let breaches: Vec<ConcentrationBreach> = check_concentration(self, limits)
+                .into_iter()
+                .filter(|b| {
+                    breaches_before
+                        .iter()
+                        .find(|before| before.scope == b.scope)
+                        .is_none_or(|before| b.excess > before.excess)
+                })
+                .collect();
+            (!breaches.is_empty()).then_some(SubstitutionError::ConcentrationBreached(breaches))
+        };
+        if let Some(err) = rejection {
+            self.holdings = saved;
+            return Err(err);
+        }
+
+        self.record(CollateralEventKind::Substitution {
+            released_asset_id: release_asset_id,
+            released_quantity: release_quantity,
+            delivered_asset_id: deliver.asset_id,
+            delivered_quantity: deliver_quantity,
+        });
+        Ok(Substitution {
+            released_asset_id: release_asset_id,
+            delivered_asset_id: deliver.asset_id,
+            adjusted_before,
+            adjusted_after,
+        })
+    }
+}
+
+// ── History ────────────────────────────────────────────────────────────
+
+/// 担保取引の種類。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollateralEventKind {
+    /// 預け入れ。
+    Deposit {
+        /// 資産 ID。
+        asset_id: u64,
+        /// 数量。
+        quantity: i64,
+    },
+    /// 引き出し。
+    Withdrawal {
+        /// 資産 ID。
+        asset_id: u64,
+        /// 数量。
+        quantity: i64,
+    },
+    /// 担保差し替え。
+    Substitution {
+        /// 引き出した資産 ID。
+        released_asset_id: u64,
+        /// 引き出した数量。
+        released_quantity: i64,
+        /// 預け入れた資産 ID。
+        delivered_asset_id: u64,
+        /// 預け入れた数量。
+        delivered_quantity: i64,
+    },
+    /// 現金担保の利息（正は受取、負は支払）。
+    Interest {
+        /// 計上額。
+        amount: i64,
+        /// 計上期間の開始（ナノ秒）。
+        from_ns: u64,
+    },
+}
+
+/// 担保取引の記録。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollateralEvent {
+    /// アカウント内の連番（0 始まり）。
+    pub sequence: u64,
+    /// 時刻（ナノ秒）。
+    pub timestamp_ns: u64,
+    /// 種類。
+    pub kind: CollateralEventKind,
+    /// 取引後の現金担保の評価額。
+    pub cash_after: i64,
+}
+
+impl CollateralAccount {
+    /// 現在時刻を設定する。以後の取引はこの時刻で記録される。時刻は単調増加とする。
+    pub fn set_time(&mut self, timestamp_ns: u64) {
+        self.now_ns = timestamp_ns;
+    }
+
+    /// 現在時刻（ナノ秒）。
+    #[inline]
+    #[must_use]
+    pub const fn now_ns(&self) -> u64 {
+        self.now_ns
+    }
+
+    /// 全取引履歴（時刻順）。
+    #[must_use]
+    pub fn history(&self) -> &[CollateralEvent] {
+        &self.history
+    }
+
+    /// `from_ns`〜`to_ns`（両端を含む）の取引履歴。
+    pub fn history_between(
+        &self,
+        from_ns: u64,
+        to_ns: u64,
+    ) -> impl Iterator<Item = &CollateralEvent> {
+        self.history
+            .iter()
+            .filter(move |e| e.timestamp_ns >= from_ns && e.timestamp_ns <= to_ns)
+    }
+
+    /// `timestamp_ns` 時点の資産 ID 別保有数量を履歴から再現する。
+    #[must_use]
+    pub fn quantities_at(&self, timestamp_ns: u64) -> BTreeMap<u64, i64> {
+        let mut quantities: BTreeMap<u64, i64> = BTreeMap::new();
+        let mut apply = |asset_id: u64, delta: i64| {
+            let q = quantities.entry(asset_id).or_insert(0);
+            *q = q.saturating_add(delta);
+            if *q == 0 {
+                quantities.remove(&asset_id);
+            }
+        };
+        for e in self
+            .history
+            .iter()
+            .take_while(|e| e.timestamp_ns <= timestamp_ns)
+        {
+            match e.kind {
+                CollateralEventKind::Deposit { asset_id, quantity } => apply(asset_id, quantity),
+                CollateralEventKind::Withdrawal { asset_id, quantity } => {
+                    apply(asset_id, -quantity);
+                }
+                CollateralEventKind::Substitution {
+                    released_asset_id,
+                    released_quantity,
+                    delivered_asset_id,
+                    delivered_quantity,
+                } => {
+                    apply(released_asset_id, -released_quantity);
+                    apply(delivered_asset_id, delivered_quantity);
+                }
+                CollateralEventKind::Interest { amount, .. } => {
+                    apply(CollateralType::Cash as u64, amount);
+                }
+            }
+        }
+        quantities
+    }
+
+    /// `timestamp_ns` 時点の現金担保の評価額。
+    #[must_use]
+    pub fn cash_at(&self, timestamp_ns: u64) -> i64 {
+        self.history
+            .iter()
+            .take_while(|e| e.timestamp_ns <= timestamp_ns)
+            .last()
+            .map_or(0, |e| e.cash_after)
+    }
+
+    fn record(&mut self, kind: CollateralEventKind) {
+        self.history.push(CollateralEvent {
+            sequence: self.history.len() as u64,
+            timestamp_ns: self.now_ns,
+            kind,
+            cash_after: self.face_value(CollateralType::Cash),
+        });
+    }
+}
+
+// ── Interest Accrual ───────────────────────────────────────────────────
+
+const NS_PER_DAY: i128 = 86_400_000_000_000;
+
+/// 日数計算規約。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DayCount {
+    /// 実日数 / 360。
+    Act360,
+    /// 実日数 / 365。
+    Act365Fixed,
+}
+
+impl DayCount {
+    /// 年間の基準日数。
+    #[inline]
+    #[must_use]
+    pub const fn basis_days(self) -> i128 {
+        match self {
+            Self::Act360 => 360,
+            Self::Act365Fixed => 365,
+        }
+    }
+}
+
+/// 現金担保の付利設定。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InterestConfig {
+    /// 年利（bps）。負の場合はメンバーが支払う。
+    pub rate_bps: i32,
+    /// 日数計算規約。
+    pub day_count: DayCount,
+}
+
+impl Default for InterestConfig {
+    fn default() -> Self {
+        Self {
+            rate_bps: 0,
+            day_count: DayCount::Act360,
+        }
+    }
+}
+
+/// 期間の利息計算結果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterestAccrual {
+    /// アカウント ID。
+    pub account_id: u64,
+    /// 期間の開始（ナノ秒）。
+    pub from_ns: u64,
+    /// 期間の終了（ナノ秒）。
+    pub to_ns: u64,
+    /// 年利（bps）。
+    pub rate_bps: i32,
+    /// 日数計算規約。
+    pub day_count: DayCount,
+    /// 残高 × 日数の合計（ナノ秒単位で積算し、日数換算で切り捨て）。表示用で、
+    /// 利息はナノ秒単位の積算値から計算する。
+    pub balance_days: i128,
+    /// 利息（正は受取、負は支払）。残高 × ナノ秒に利率を掛けてから最後に一度だけ
+    /// 割り、負の無限大方向に丸める（受取利息は切り捨て、支払利息は切り上げ）。
+    pub interest: i64,
+}
+
+impl CollateralAccount {
+    /// `from_ns`〜`to_ns` の現金担保の利息を履歴から計算する（計上はしない）。
+    ///
+    /// 端数日も利息に含め、端数は負の無限大方向に丸めるため、丸めがメンバーに
+    /// 有利になることはない。
+    #[must_use]
+    pub fn interest_accrual(
+        &self,
+        config: &InterestConfig,
+        from_ns: u64,
+        to_ns: u64,
+    ) -> InterestAccrual {
+        let mut balance_ns: i128 = 0;
+        if to_ns > from_ns {
+            let mut t = from_ns;
+            let mut cash = self.cash_at(from_ns) as i128;
+            for e in self
+                .history
+                .iter()
+                .filter(|e| e.timestamp_ns > from_ns && e.timestamp_ns < to_ns)
+            {
+                balance_ns = balance_ns.saturating_add(cash * (e.timestamp_ns - t) as i128);
+                t = e.timestamp_ns;
+                cash = e.cash_after as i128;
+            }
+            balance_ns = balance_ns.saturating_add(cash * (to_ns - t) as i128);
+        }
+        let balance_days = balance_ns / NS_PER_DAY;
+        let interest = balance_ns
+            .saturating_mul(config.rate_bps as i128)
+            .div_euclid(10_000 * config.day_count.basis_days() * NS_PER_DAY);
+        InterestAccrual {
+            account_id: self.account_id,
+            from_ns,
+            to_ns,
+            rate_bps: config.rate_bps,
+            day_count: config.day_count,
+            balance_days,
+            interest: interest.clamp(i64::MIN as i128, i64::MAX as i128) as i64,
+        }
+    }
+
+    /// 前回の計上から現在時刻までの利息を現金担保に計上する。
+    ///
+    /// 受取利息は現金として預け入れ、支払利息は現金担保から差し引く（現金担保を
+    /// 超える分は差し引かない）。戻り値の `interest` は実際に計上した額。
+    pub fn post_interest(&mut self, config: &InterestConfig) -> InterestAccrual {
+        let from_ns = self.accrued_to_ns;
+        let mut accrual = self.interest_accrual(config, from_ns, self.now_ns);
+        let cash = CollateralType::Cash as u64;
+        let held = self.holding(cash).map_or(0, |h| h.quantity);
+        let amount = accrual.interest.max(-held);
+        if amount > 0 {
+            self.credit(&CollateralAsset::bucket(CollateralType::Cash), amount);
+        } else if amount < 0 {
+            self.debit(cash, -amount);
+        }
+        if amount != 0 {
+            self.record(CollateralEventKind::Interest { amount, from_ns });
+        }
+        self.accrued_to_ns = self.now_ns;
+        accrual.interest = amount;
+        accrual
+    }
+
+    /// 利息を計上済みの時刻（ナノ秒）。
+    #[inline]
+    #[must_use]
+    pub const fn accrued_to_ns(&self) -> u64 {
+        self.accrued_to_ns
     }
-    breaches
 }
 
 // ── Tests ──────────────────────────────────────────────────────────────
@@ -419,6 +1896,555 @@ mod tests {
         assert_ne!(a.content_hash(), b.content_hash());
     }
 
+    // ── Per-Asset Holdings ─────────────────────────────────────────
+
+    fn bond(
+        asset_id: u64,
+        rating: CreditRating,
+        maturity_days: u32,
+        price: i64,
+    ) -> CollateralAsset {
+        CollateralAsset {
+            asset_id,
+            collateral_type: CollateralType::GovernmentBond,
+            issuer_id: 7,
+            rating,
+            maturity_days,
+            currency: *b"JPY",
+            volatility_bps: 500,
+            price,
+        }
+    }
+
+    #[test]
+    fn distinct_bonds_held_separately() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit_asset(&bond(0x1001, CreditRating::Aaa, 200, 100), 50);
+        acc.deposit_asset(&bond(0x1002, CreditRating::A, 3_000, 98), 100);
+        assert_eq!(acc.holdings().len(), 2);
+        assert_eq!(acc.holding(0x1001).unwrap().face_value, 5_000);
+        assert_eq!(acc.holding(0x1002).unwrap().face_value, 9_800);
+        assert_eq!(acc.face_value(CollateralType::GovernmentBond), 14_800);
+    }
+
+    #[test]
+    fn haircut_derived_from_attributes() {
+        let cfg = HaircutConfig::default();
+        let short_aaa = CollateralHolding::new(&bond(1_000, CreditRating::Aaa, 200, 1), 1);
+        let long_bbb = CollateralHolding::new(&bond(1_001, CreditRating::Bbb, 4_000, 1), 1);
+        // 300 base; BBB +300; >10y +400.
+        assert_eq!(cfg.holding_haircut_bps(&short_aaa), 300);
+        assert_eq!(cfg.holding_haircut_bps(&long_bbb), 1_000);
+
+        let mut acc = CollateralAccount::new(1, cfg);
+        acc.deposit_asset(&bond(1_001, CreditRating::Bbb, 4_000, 100), 100);
+        assert_eq!(acc.adjusted_value(CollateralType::GovernmentBond), 9_000);
+    }
+
+    #[test]
+    fn haircut_capped_at_full_value() {
+        let cfg = HaircutConfig::default();
+        let junk = CollateralAsset {
+            collateral_type: CollateralType::Equity,
+            ..bond(1_003, CreditRating::Ccc, 5_000, 10)
+        };
+        // 2500 base; CCC +4000; >10y +400.
+        let holding = CollateralHolding::new(&junk, 10);
+        assert_eq!(cfg.holding_haircut_bps(&holding), 6_900);
+        let cfg = HaircutConfig {
+            equity_bps: 9_000,
+            ..HaircutConfig::default()
+        };
+        assert_eq!(cfg.holding_haircut_bps(&holding), 10_000);
+    }
+
+    #[test]
+    fn bucket_deposit_unaffected_by_addons() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::CorporateBond, 10_000);
+        let h = acc.holding(CollateralType::CorporateBond as u64).unwrap();
+        assert_eq!(h.rating, CreditRating::Aaa);
+        assert_eq!(h.currency, NO_CURRENCY);
+        assert_eq!(acc.adjusted_value(CollateralType::CorporateBond), 9_000);
+    }
+
+    #[test]
+    fn redeposit_keeps_attributes_and_price() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        assert!(acc.deposit_asset(&bond(0x2001, CreditRating::Aa, 700, 100), 10));
+        // 単価だけ異なる預け入れは保有の時価で評価する。
+        assert!(acc.deposit_asset(&bond(0x2001, CreditRating::Aa, 700, 95), 5));
+        let h = acc.holding(0x2001).unwrap();
+        assert_eq!(h.quantity, 15);
+        assert_eq!(h.price, 100);
+        assert_eq!(h.face_value, 1_500);
+
+        // 属性が異なる預け入れは拒否し、保有を変更しない。
+        let downgraded = bond(0x2001, CreditRating::A, 700, 100);
+        assert!(!acc.deposit_asset(&downgraded, 5));
+        assert_eq!(
+            acc.try_deposit_asset(&downgraded, 5),
+            Err(CollateralError::AssetMismatch(0x2001))
+        );
+        let equity = CollateralAsset {
+            collateral_type: CollateralType::Equity,
+            ..bond(0x2001, CreditRating::Aa, 700, 100)
+        };
+        assert!(!acc.deposit_asset(&equity, 5));
+        let other_issuer = CollateralAsset {
+            issuer_id: 99,
+            ..bond(0x2001, CreditRating::Aa, 700, 100)
+        };
+        assert!(!acc.deposit_asset(&other_issuer, 5));
+        let h = acc.holding(0x2001).unwrap();
+        assert_eq!(h.quantity, 15);
+        assert_eq!(h.rating, CreditRating::Aa);
+        assert_eq!(acc.history().len(), 2);
+    }
+
+    #[test]
+    fn reserved_asset_ids_rejected() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        for id in 0..=3 {
+            let fake = bond(id, CreditRating::Aaa, 100, 10);
+            assert!(!acc.deposit_asset(&fake, 10));
+            assert_eq!(
+                acc.try_deposit_asset(&fake, 10),
+                Err(CollateralError::ReservedAssetId(id))
+            );
+        }
+        assert!(acc.holdings().is_empty());
+
+        // バケット資産そのものは受け付ける。
+        let cash = CollateralAsset::bucket(CollateralType::Cash);
+        assert!(acc.deposit_asset(&cash, 10));
+        acc.try_deposit_asset(&CollateralAsset::bucket(CollateralType::Equity), 5)
+            .unwrap();
+        let priced_cash = CollateralAsset { price: 2, ..cash };
+        assert_eq!(
+            acc.try_deposit_asset(&priced_cash, 1),
+            Err(CollateralError::ReservedAssetId(0))
+        );
+        assert_eq!(acc.face_value(CollateralType::Cash), 10);
+    }
+
+    #[test]
+    fn withdraw_asset_partial_and_full() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit_asset(&bond(0x3001, CreditRating::Aaa, 100, 10), 10);
+        assert!(!acc.withdraw_asset(0x3001, 11));
+        assert!(!acc.withdraw_asset(0x9999, 1));
+        assert!(acc.withdraw_asset(0x3001, 4));
+        assert_eq!(acc.holding(0x3001).unwrap().face_value, 60);
+        assert!(acc.withdraw_asset(0x3001, 6));
+        assert!(acc.holding(0x3001).is_none());
+        assert!(acc.holdings().is_empty());
+    }
+
+    #[test]
+    fn maturity_buckets() {
+        assert_eq!(maturity_bucket(0), None);
+        assert_eq!(maturity_bucket(1), Some(0));
+        assert_eq!(maturity_bucket(365), Some(0));
+        assert_eq!(maturity_bucket(366), Some(1));
+        assert_eq!(maturity_bucket(3_650), Some(2));
+        assert_eq!(maturity_bucket(3_651), Some(3));
+    }
+
+    #[test]
+    fn rating_investment_grade() {
+        assert!(CreditRating::Bbb.is_investment_grade());
+        assert!(!CreditRating::Bb.is_investment_grade());
+        assert!(!CreditRating::NotRated.is_investment_grade());
+    }
+
+    // ── Haircut Schedule ───────────────────────────────────────────
+
+    fn schedule() -> HaircutSchedule {
+        let mut s = HaircutSchedule {
+            volatility_bands_bps: vec![1_000, 3_000],
+            ..HaircutSchedule::default()
+        };
+        for (band, bps) in [(0, 150), (1, 400), (2, 900)] {
+            s.insert(
+                HaircutGridKey {
+                    collateral_type: CollateralType::GovernmentBond,
+                    rating: CreditRating::Aaa,
+                    maturity_bucket: Some(1),
+                    volatility_band: band,
+                },
+                bps,
+            );
+        }
+        s
+    }
+
+    fn holding(asset: CollateralAsset) -> CollateralHolding {
+        CollateralHolding::new(&asset, 100)
+    }
+
+    #[test]
+    fn schedule_grid_lookup_by_volatility() {
+        let s = schedule();
+        let mut asset = bond(1, CreditRating::Aaa, 1_000, 100);
+        for (vol, expected) in [(500, 150), (1_000, 150), (2_000, 400), (9_000, 900)] {
+            asset.volatility_bps = vol;
+            assert_eq!(s.haircut_bps(&holding(asset.clone())), expected);
+        }
+        assert_eq!(s.volatility_band(3_001), 2);
+    }
+
+    #[test]
+    fn schedule_falls_back_to_config() {
+        let s = schedule();
+        // Not in the grid: 300 base + A 100 + ≤1y 0.
+        let h = holding(bond(1, CreditRating::A, 100, 100));
+        assert_eq!(s.base_bps(&h), 400);
+        assert_eq!(s.haircut_bps(&h), 400);
+    }
+
+    #[test]
+    fn schedule_addons_accumulate() {
+        fn long_dated(h: &CollateralHolding) -> bool {
+            h.maturity_days > 3_650
+        }
+        let mut s = schedule();
+        s.addons = vec![
+            HaircutAddOn {
+                condition: AddOnCondition::Issuer(7),
+                bps: 1_000,
+            },
+            HaircutAddOn {
+                condition: AddOnCondition::Currency(*b"USD"),
+                bps: 800,
+            },
+            HaircutAddOn {
+                condition: AddOnCondition::Custom(long_dated),
+                bps: 50,
+            },
+            HaircutAddOn {
+                condition: AddOnCondition::RatingBelow(CreditRating::Bbb),
+                bps: 2_000,
+            },
+        ];
+        let h = holding(bond(1, CreditRating::Aaa, 1_000, 100));
+        assert_eq!(s.addon_bps(&h), 1_000);
+        assert_eq!(s.haircut_bps(&h), 1_150);
+
+        let mut usd = bond(2, CreditRating::Bb, 5_000, 100);
+        usd.currency = *b"USD";
+        usd.issuer_id = 8;
+        // 300 + BB 1000 + >10y 400 = 1700 base; USD 800 + long 50 + below BBB 2000.
+        assert_eq!(s.haircut_bps(&holding(usd)), 4_550);
+    }
+
+    #[test]
+    fn schedule_capped_at_full_value() {
+        let mut s = schedule();
+        s.addons.push(HaircutAddOn {
+            condition: AddOnCondition::CollateralType(CollateralType::GovernmentBond),
+            bps: u32::MAX,
+        });
+        assert_eq!(
+            s.haircut_bps(&holding(bond(1, CreditRating::Aaa, 1_000, 1))),
+            10_000
+        );
+    }
+
+    #[test]
+    fn schedule_plugs_into_account() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit_asset(&bond(0x10, CreditRating::Aaa, 1_000, 100), 100);
+        // Config: 300 base + 1-5y 100.
+        assert_eq!(acc.adjusted_value(CollateralType::GovernmentBond), 9_600);
+
+        let mut s = schedule();
+        s.addons.push(HaircutAddOn {
+            condition: AddOnCondition::Asset(0x10),
+            bps: 850,
+        });
+        acc.set_haircut_model(Box::new(s));
+        assert_eq!(acc.adjusted_value(CollateralType::GovernmentBond), 9_000);
+        assert_eq!(acc.total_adjusted_value(), 9_000);
+
+        acc.clear_haircut_model();
+        assert_eq!(acc.total_adjusted_value(), 9_600);
+    }
+
+    // ── Mark-to-Market ─────────────────────────────────────────────
+
+    fn requirement(account_id: u64, total_margin: i64) -> MarginRequirement {
+        MarginRequirement {
+            account_id,
+            initial_margin: total_margin,
+            variation_margin: 0,
+            stress_margin: 0,
+            total_margin,
+            content_hash: 0,
+        }
+    }
+
+    #[test]
+    fn revalue_reports_changes() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, 1_000);
+        acc.deposit_asset(&bond(0x10, CreditRating::Aaa, 100, 100), 100);
+        acc.deposit_asset(&bond(0x11, CreditRating::Aaa, 100, 50), 10);
+
+        let mut prices = PriceSnapshot::new(99);
+        prices.insert(0x10, 90);
+        prices.insert(0x11, 50);
+        let r = acc.revalue(&prices, 0);
+
+        assert_eq!(r.market_before, 11_500);
+        assert_eq!(r.market_after, 10_500);
+        assert_eq!(
+            r.changes,
+            vec![HoldingRevaluation {
+                asset_id: 0x10,
+                old_price: 100,
+                new_price: 90,
+                value_change: -1_000,
+            }]
+        );
+        // Gov bonds ≤1y: 3% haircut.
+        assert_eq!(r.adjusted_change(), -970);
+        assert!(r.missing_prices.is_empty());
+        assert_eq!(acc.holding(0x10).unwrap().face_value, 9_000);
+    }
+
+    #[test]
+    fn revalue_missing_and_invalid_prices_kept() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit_asset(&bond(0x10, CreditRating::Aaa, 100, 100), 1);
+        acc.deposit_asset(&bond(0x11, CreditRating::Aaa, 100, 100), 1);
+        let mut prices = PriceSnapshot::new(0);
+        prices.insert(0x11, -5);
+        let r = acc.revalue(&prices, 0);
+        assert_eq!(r.missing_prices, vec![0x10, 0x11]);
+        assert!(r.changes.is_empty());
+        assert_eq!(acc.total_face_value(), 200);
+    }
+
+    #[test]
+    fn revalue_flags_shortfall() {
+        let mut a = CollateralAccount::new(1, HaircutConfig::default());
+        a.deposit_asset(&bond(0x10, CreditRating::Aaa, 100, 100), 100);
+        let mut b = CollateralAccount::new(2, HaircutConfig::default());
+        b.deposit(CollateralType::Cash, 20_000);
+        let mut accounts = [a, b];
+
+        let mut prices = PriceSnapshot::new(5);
+        prices.insert(0x10, 80);
+        let report = revalue_collateral(
+            &mut accounts,
+            &prices,
+            &[
+                requirement(1, 5_000),
+                requirement(1, 3_000),
+                requirement(2, 1_000),
+            ],
+        );
+
+        assert_eq!(report.timestamp_ns, 5);
+        assert_eq!(report.total_market_change, -2_000);
+        let short: Vec<_> = report.short_accounts().collect();
+        assert_eq!(short.len(), 1);
+        assert_eq!(short[0].account_id, 1);
+        assert_eq!(short[0].requirement, 8_000);
+        // 8000 × 0.97 = 7760 → short by 240.
+        assert_eq!(short[0].shortfall, 240);
+        assert!(!report.accounts[1].is_short());
+    }
+
+    #[test]
+    fn set_price_rejects_invalid() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit_asset(&bond(0x10, CreditRating::Aaa, 100, 100), 1);
+        assert!(!acc.set_price(0x10, 0));
+        assert!(!acc.set_price(0x99, 10));
+        assert!(acc.set_price(0x10, 120));
+        assert_eq!(acc.total_face_value(), 120);
+    }
+
+    // ── Substitution ───────────────────────────────────────────────
+
+    #[test]
+    fn substitute_applies_both_legs() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, 10_000);
+        let bond_a = bond(0x10, CreditRating::Aaa, 100, 100);
+        let sub = acc
+            .substitute(
+                CollateralType::Cash as u64,
+                4_000,
+                &bond_a,
+                50,
+                9_000,
+                &ConcentrationLimits::unlimited(),
+            )
+            .unwrap();
+        assert_eq!(sub.adjusted_before, 10_000);
+        // 5000 × 0.97 = 4850.
+        assert_eq!(sub.adjusted_after, 10_850);
+        assert_eq!(acc.face_value(CollateralType::Cash), 6_000);
+        assert_eq!(acc.holding(0x10).unwrap().quantity, 50);
+    }
+
+    #[test]
+    fn substitute_rejects_shortfall_and_rolls_back() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, 10_000);
+        let hash = acc.content_hash();
+        let bond_a = bond(0x10, CreditRating::Aaa, 100, 100);
+        let err = acc
+            .substitute(
+                CollateralType::Cash as u64,
+                5_000,
+                &bond_a,
+                50,
+                10_000,
+                &ConcentrationLimits::unlimited(),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err,
+            SubstitutionError::InsufficientCollateral {
+                adjusted_value: 9_850,
+                requirement: 10_000,
+            }
+        );
+        assert_eq!(acc.content_hash(), hash);
+        assert!(acc.holding(0x10).is_none());
+    }
+
+    #[test]
+    fn substitute_rejects_concentration_breach() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, 10_000);
+        let hash = acc.content_hash();
+        let stock = CollateralAsset {
+            asset_id: 0x20,
+            collateral_type: CollateralType::Equity,
+            issuer_id: 9,
+            rating: CreditRating::NotRated,
+            maturity_days: 0,
+            currency: NO_CURRENCY,
+            volatility_bps: 3_000,
+            price: 1_000,
+        };
+        let err = acc
+            .substitute(
+                CollateralType::Cash as u64,
+                8_000,
+                &stock,
+                8,
+                0,
+                &ConcentrationLimits::default(),
+            )
+            .unwrap_err();
+        assert!(matches!(
+            &err,
+            SubstitutionError::ConcentrationBreached(b)
+                if b[0].scope == ConcentrationScope::CollateralType(CollateralType::Equity)
+        ));
+        assert_eq!(acc.content_hash(), hash);
+    }
+
+    #[test]
+    fn substitute_allows_reducing_existing_breach() {
+        let stock = |asset_id| CollateralAsset {
+            asset_id,
+            collateral_type: CollateralType::Equity,
+            issuer_id: 9,
+            rating: CreditRating::NotRated,
+            maturity_days: 0,
+            currency: NO_CURRENCY,
+            volatility_bps: 3_000,
+            price: 1_000,
+        };
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, 2_000);
+        acc.deposit_asset(&stock(0x20), 8);
+        let limits = ConcentrationLimits::default();
+        let before = check_concentration(&acc, &limits);
+        assert_eq!(before.len(), 1);
+
+        // 株式を現金に替えて違反を減らす差し替えは受け付ける（違反は残る）。
+        let cash = CollateralAsset::bucket(CollateralType::Cash);
+        acc.substitute(0x20, 2, &cash, 1_000, 0, &limits).unwrap();
+        let after = check_concentration(&acc, &limits);
+        assert_eq!(after.len(), 1);
+        assert!(after[0].excess < before[0].excess);
+
+        // 超過額を増やす差し替えは拒否する。
+        let hash = acc.content_hash();
+        let err = acc
+            .substitute(0, 1_000, &stock(0x21), 1, 0, &limits)
+            .unwrap_err();
+        assert!(matches!(
+            &err,
+            SubstitutionError::ConcentrationBreached(b)
+                if b.len() == 1
+                    && b[0].scope == ConcentrationScope::CollateralType(CollateralType::Equity)
+        ));
+        assert_eq!(acc.content_hash(), hash);
+    }
+
+    #[test]
+    fn substitute_validates_inputs() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, 100);
+        let bond_a = bond(0x10, CreditRating::Aaa, 100, 100);
+        let limits = ConcentrationLimits::unlimited();
+        assert_eq!(
+            acc.substitute(0, 0, &bond_a, 1, 0, &limits),
+            Err(SubstitutionError::InvalidQuantity)
+        );
+        assert_eq!(
+            acc.substitute(0, 200, &bond_a, 1, 0, &limits),
+            Err(SubstitutionError::InsufficientHoldings {
+                asset_id: 0,
+                held: 100,
+                requested: 200,
+            })
+        );
+        let cash = CollateralAsset::bucket(CollateralType::Cash);
+        assert_eq!(
+            acc.substitute(0, 10, &cash, 10, 0, &limits),
+            Err(SubstitutionError::SameAsset)
+        );
+        let fake_equity = bond(3, CreditRating::Aaa, 100, 100);
+        assert_eq!(
+            acc.substitute(0, 10, &fake_equity, 1, 0, &limits),
+            Err(SubstitutionError::InvalidAsset(
+                CollateralError::ReservedAssetId(3)
+            ))
+        );
+        assert_eq!(acc.holding(0).unwrap().quantity, 100);
+    }
+
+    #[test]
+    fn substitute_rejects_overflow_and_rolls_back() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, 100);
+        let bond_a = bond(0x10, CreditRating::Aaa, 100, 100);
+        acc.try_deposit_asset(&bond_a, i64::MAX / 100).unwrap();
+        let hash = acc.content_hash();
+        let limits = ConcentrationLimits::unlimited();
+        assert_eq!(
+            acc.substitute(0, 10, &bond_a, 1, 0, &limits),
+            Err(SubstitutionError::Overflow)
+        );
+        let big = bond(0x11, CreditRating::Aaa, 100, 2);
+        assert_eq!(
+            acc.substitute(0, 10, &big, i64::MAX, 0, &limits),
+            Err(SubstitutionError::Overflow)
+        );
+        assert_eq!(acc.content_hash(), hash);
+        assert_eq!(acc.history().len(), 2);
+    }
+
     // ── Concentration Limits ───────────────────────────────────────
 
     #[test]
@@ -436,9 +2462,15 @@ mod tests {
         acc.deposit(CollateralType::Equity, 10_000);
         let breaches = check_concentration(&acc, &ConcentrationLimits::default());
         assert_eq!(breaches.len(), 1);
-        assert_eq!(breaches[0].collateral_type, CollateralType::Equity);
+        assert_eq!(
+            breaches[0].scope,
+            ConcentrationScope::CollateralType(CollateralType::Equity)
+        );
         assert!(breaches[0].current_bps > 3_000);
         assert_eq!(breaches[0].limit_bps, 3_000);
+        // 7500 のうち 30% = 2250 を超える 5250 が超過。
+        assert_eq!(breaches[0].limit_value, 2_250);
+        assert_eq!(breaches[0].excess, 5_250);
     }
 
     #[test]
@@ -448,7 +2480,10 @@ mod tests {
         acc.deposit(CollateralType::CorporateBond, 10_000);
         let breaches = check_concentration(&acc, &ConcentrationLimits::default());
         assert_eq!(breaches.len(), 1);
-        assert_eq!(breaches[0].collateral_type, CollateralType::CorporateBond);
+        assert_eq!(
+            breaches[0].scope,
+            ConcentrationScope::CollateralType(CollateralType::CorporateBond)
+        );
     }
 
     #[test]
@@ -480,7 +2515,10 @@ mod tests {
     #[test]
     fn concentration_breach_equality() {
         let b1 = ConcentrationBreach {
-            collateral_type: CollateralType::Equity,
+            scope: ConcentrationScope::CollateralType(CollateralType::Equity),
+            value: 5000,
+            limit_value: 3000,
+            excess: 2000,
             current_bps: 5000,
             limit_bps: 3000,
         };
@@ -488,6 +2526,382 @@ mod tests {
         assert_eq!(b1, b2);
     }
 
+    fn issued(asset_id: u64, issuer_id: u64, price: i64) -> CollateralAsset {
+        CollateralAsset {
+            issuer_id,
+            ..bond(asset_id, CreditRating::Aaa, 100, price)
+        }
+    }
+
+    #[test]
+    fn concentration_issuer_limit() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, 10_000);
+        acc.deposit_asset(&issued(0x10, 5, 100), 60);
+        acc.deposit_asset(&issued(0x11, 5, 100), 40);
+        acc.deposit_asset(&issued(0x12, 6, 100), 10);
+        let limits = ConcentrationLimits {
+            issuer_max_bps: 4_000,
+            ..ConcentrationLimits::unlimited()
+        };
+        let breaches = check_concentration(&acc, &limits);
+        // 総額 10000 + 9700 + 970 = 20670、上限 40% = 8268。
+        assert_eq!(breaches.len(), 1);
+        assert_eq!(breaches[0].scope, ConcentrationScope::Issuer(5));
+        assert_eq!(breaches[0].value, 9_700);
+        assert_eq!(breaches[0].limit_value, 8_268);
+        assert_eq!(breaches[0].excess, 1_432);
+    }
+
+    #[test]
+    fn concentration_issuer_ignores_cash_and_buckets() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, 10_000);
+        acc.deposit(CollateralType::GovernmentBond, 10_000);
+        let limits = ConcentrationLimits {
+            issuer_max_bps: 100,
+            ..ConcentrationLimits::unlimited()
+        };
+        assert!(check_concentration(&acc, &limits).is_empty());
+    }
+
+    #[test]
+    fn concentration_absolute_caps() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, 10_000);
+        acc.deposit_asset(&issued(0x10, 5, 100), 50);
+        let mut limits = ConcentrationLimits::unlimited();
+        limits.type_max_value[CollateralType::GovernmentBond as usize] = Some(4_000);
+        limits.issuer_max_value = Some(4_500);
+        let breaches = check_concentration(&acc, &limits);
+        assert_eq!(breaches.len(), 2);
+        assert_eq!(
+            breaches[0].scope,
+            ConcentrationScope::CollateralType(CollateralType::GovernmentBond)
+        );
+        assert_eq!(breaches[0].excess, 850);
+        assert_eq!(breaches[1].scope, ConcentrationScope::Issuer(5));
+        assert_eq!(breaches[1].excess, 350);
+    }
+
+    #[test]
+    fn concentration_against_requirement() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, 50_000);
+        acc.deposit(CollateralType::Equity, 4_000);
+        let limits = ConcentrationLimits::default();
+        // 全担保比では株式 3000 / 53000 ≈ 5.7%。
+        assert!(check_concentration(&acc, &limits).is_empty());
+        // 所要額 5000 の 30% = 1500 を超える。
+        let breaches = check_concentration_against_requirement(&acc, &limits, 5_000);
+        assert_eq!(breaches.len(), 1);
+        assert_eq!(breaches[0].current_bps, 6_000);
+        assert_eq!(breaches[0].excess, 1_500);
+        // 現金 100% は制限なしなので所要額を超えても違反にならない。
+        assert!(breaches
+            .iter()
+            .all(|b| b.scope != ConcentrationScope::CollateralType(CollateralType::Cash)));
+        // 所要額 0 では比率上限は適用しない。
+        assert!(check_concentration_against_requirement(&acc, &limits, 0).is_empty());
+    }
+
+    #[test]
+    fn eligible_value_excludes_type_excess() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, 7_000);
+        acc.deposit(CollateralType::Equity, 8_000);
+        // 調整前 7000 + 6000 = 13000、株式上限 30% = 3900。
+        let ev = acc.eligible_value(&ConcentrationLimits::default(), ExcessTreatment::Exclude);
+        assert_eq!(ev.adjusted_value, 13_000);
+        assert_eq!(ev.deduction, 2_100);
+        assert_eq!(ev.eligible_value, 10_900);
+        assert_eq!(ev.breaches.len(), 1);
+        assert_eq!(ev.breaches[0].excess, ev.deduction);
+    }
+
+    #[test]
+    fn eligible_value_extra_haircut() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, 7_000);
+        acc.deposit(CollateralType::Equity, 8_000);
+        let ev = acc.eligible_value(
+            &ConcentrationLimits::default(),
+            ExcessTreatment::Haircut(5_000),
+        );
+        assert_eq!(ev.deduction, 1_050);
+        assert_eq!(ExcessTreatment::Haircut(20_000).deduction_bps(), 10_000);
+    }
+
+    #[test]
+    fn eligible_value_no_double_count() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, 5_000);
+        acc.deposit_asset(&issued(0x10, 5, 100), 100);
+        let mut limits = ConcentrationLimits::unlimited();
+        limits.type_max_value[CollateralType::GovernmentBond as usize] = Some(6_000);
+        limits.issuer_max_value = Some(7_000);
+        // 国債 9700 → タイプ上限 6000 に削減後、発行体上限 7000 は超えない。
+        let ev = acc.eligible_value(&limits, ExcessTreatment::Exclude);
+        assert_eq!(ev.breaches.len(), 2);
+        assert_eq!(ev.deduction, 3_700);
+        assert_eq!(ev.eligible_value, 11_000);
+    }
+
+    #[test]
+    fn eligible_value_against_requirement() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, 2_000);
+        acc.deposit(CollateralType::Equity, 4_000);
+        let limits = ConcentrationLimits::default();
+        // 所要額 5000 の 30% = 1500 を超える株式 1500 を除外。
+        let ev = acc.eligible_value_against_requirement(&limits, 5_000, ExcessTreatment::Exclude);
+        assert_eq!(ev.adjusted_value, 5_000);
+        assert_eq!(ev.eligible_value, 3_500);
+        let ev = acc.eligible_value_against_requirement(&limits, 0, ExcessTreatment::Exclude);
+        assert_eq!(ev.deduction, 0);
+    }
+
+    #[test]
+    fn eligible_value_clean_account() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, 1_000);
+        let ev = acc.eligible_value(&ConcentrationLimits::default(), ExcessTreatment::Exclude);
+        assert_eq!(ev.eligible_value, acc.total_adjusted_value());
+        assert!(ev.breaches.is_empty());
+    }
+
+    #[test]
+    fn haircut_config_new_validates() {
+        let cfg = HaircutConfig::new(0, 200, 800, 2_000).unwrap();
+        assert_eq!(cfg.gov_bond_bps, 200);
+        assert_eq!(
+            cfg.rating_addon_bps,
+            HaircutConfig::default().rating_addon_bps
+        );
+        assert_eq!(
+            HaircutConfig::new(0, 200, 800, 10_001).unwrap_err(),
+            CollateralError::HaircutOutOfRange(10_001)
+        );
+        let mut cfg = HaircutConfig::default();
+        cfg.maturity_addon_bps[3] = 20_000;
+        assert_eq!(
+            cfg.validate(),
+            Err(CollateralError::HaircutOutOfRange(20_000))
+        );
+    }
+
+    #[test]
+    fn apply_haircut_large_and_out_of_range() {
+        let cfg = HaircutConfig::default();
+        // i64 で計算すると value * 7500 があふれる。
+        assert_eq!(
+            cfg.apply_haircut(CollateralType::Equity, i64::MAX),
+            (i64::MAX as i128 * 7_500 / 10_000) as i64
+        );
+        let bad = HaircutConfig {
+            equity_bps: 12_000,
+            ..HaircutConfig::default()
+        };
+        assert_eq!(bad.apply_haircut(CollateralType::Equity, 1_000), 0);
+        assert_eq!(
+            bad.try_apply_haircut(CollateralType::Equity, 1_000),
+            Err(CollateralError::HaircutOutOfRange(12_000))
+        );
+        assert_eq!(
+            cfg.try_apply_haircut(CollateralType::GovernmentBond, 10_000),
+            Ok(9_700)
+        );
+    }
+
+    #[test]
+    fn try_deposit_detects_overflow() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.try_deposit(CollateralType::Cash, i64::MAX - 10)
+            .unwrap();
+        assert_eq!(
+            acc.try_deposit(CollateralType::Cash, 11),
+            Err(CollateralError::Overflow)
+        );
+        assert_eq!(acc.face_value(CollateralType::Cash), i64::MAX - 10);
+        assert_eq!(
+            acc.try_deposit(CollateralType::Cash, 0),
+            Err(CollateralError::InvalidQuantity(0))
+        );
+        // 数量は収まるが評価額があふれる。
+        let pricey = bond(0x10, CreditRating::Aaa, 100, i64::MAX / 2);
+        assert_eq!(
+            acc.try_deposit_asset(&pricey, 3),
+            Err(CollateralError::Overflow)
+        );
+        assert!(acc.holding(0x10).is_none());
+    }
+
+    #[test]
+    fn totals_saturate() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, i64::MAX);
+        acc.deposit(CollateralType::GovernmentBond, i64::MAX);
+        assert_eq!(acc.total_face_value(), i64::MAX);
+        assert_eq!(acc.total_adjusted_value(), i64::MAX);
+        assert_eq!(saturating_sum([i64::MIN, -1]), i64::MIN);
+    }
+
+    const DAY: u64 = 86_400_000_000_000;
+
+    #[test]
+    fn history_records_operations() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.set_time(10);
+        acc.deposit(CollateralType::Cash, 1_000);
+        acc.set_time(20);
+        assert!(acc.withdraw(CollateralType::Cash, 300));
+        assert!(!acc.withdraw(CollateralType::Cash, 5_000));
+        acc.deposit(CollateralType::Cash, 0);
+        acc.set_time(30);
+        acc.substitute(
+            CollateralType::Cash as u64,
+            200,
+            &bond(0x10, CreditRating::Aaa, 100, 100),
+            3,
+            0,
+            &ConcentrationLimits::unlimited(),
+        )
+        .unwrap();
+
+        let h = acc.history();
+        assert_eq!(h.len(), 3);
+        assert_eq!(
+            h[0].kind,
+            CollateralEventKind::Deposit {
+                asset_id: 0,
+                quantity: 1_000,
+            }
+        );
+        assert_eq!(
+            (h[1].sequence, h[1].timestamp_ns, h[1].cash_after),
+            (1, 20, 700)
+        );
+        assert!(matches!(
+            h[2].kind,
+            CollateralEventKind::Substitution { .. }
+        ));
+        assert_eq!(h[2].cash_after, 500);
+        assert_eq!(acc.history_between(15, 30).count(), 2);
+    }
+
+    #[test]
+    fn rejected_substitution_not_recorded() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, 100);
+        let _ = acc.substitute(
+            CollateralType::Cash as u64,
+            100,
+            &bond(0x10, CreditRating::Aaa, 100, 1),
+            1,
+            100,
+            &ConcentrationLimits::unlimited(),
+        );
+        assert_eq!(acc.history().len(), 1);
+    }
+
+    #[test]
+    fn quantities_reproduced_from_history() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.set_time(1);
+        acc.deposit(CollateralType::Cash, 1_000);
+        acc.deposit_asset(&bond(0x10, CreditRating::Aaa, 100, 100), 5);
+        acc.set_time(2);
+        acc.withdraw_asset(0x10, 5);
+        acc.try_deposit(CollateralType::Cash, 50).unwrap();
+
+        let at1 = acc.quantities_at(1);
+        assert_eq!(at1.get(&0), Some(&1_000));
+        assert_eq!(at1.get(&0x10), Some(&5));
+        let at2 = acc.quantities_at(2);
+        assert_eq!(at2.get(&0), Some(&1_050));
+        assert!(!at2.contains_key(&0x10));
+        assert!(acc.quantities_at(0).is_empty());
+        assert_eq!(acc.cash_at(1), 1_000);
+        assert_eq!(acc.cash_at(0), 0);
+    }
+
+    #[test]
+    fn interest_accrual_time_weighted() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, 3_600_000);
+        acc.set_time(10 * DAY);
+        acc.deposit(CollateralType::Cash, 3_600_000);
+        let cfg = InterestConfig {
+            rate_bps: 500,
+            day_count: DayCount::Act360,
+        };
+        // 3.6M × 10 日 + 7.2M × 20 日 = 180M 残高日、× 5% / 360 = 25000。
+        let a = acc.interest_accrual(&cfg, 0, 30 * DAY);
+        assert_eq!(a.balance_days, 180_000_000);
+        assert_eq!(a.interest, 25_000);
+        let act365 = InterestConfig {
+            day_count: DayCount::Act365Fixed,
+            ..cfg
+        };
+        assert_eq!(acc.interest_accrual(&act365, 0, 30 * DAY).interest, 24_657);
+        assert_eq!(acc.interest_accrual(&cfg, 5 * DAY, 5 * DAY).interest, 0);
+    }
+
+    #[test]
+    fn interest_accrual_counts_fractional_days() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, 1_000_001);
+        let receive = InterestConfig {
+            rate_bps: 3_650,
+            day_count: DayCount::Act365Fixed,
+        };
+        // 1,000,001 × 36.5% × 0.5 日 / 365 = 500.0005。
+        let a = acc.interest_accrual(&receive, 0, DAY / 2);
+        assert_eq!(a.balance_days, 500_000);
+        assert_eq!(a.interest, 500);
+        // 支払利息は切り上げる。
+        let pay = InterestConfig {
+            rate_bps: -3_650,
+            ..receive
+        };
+        assert_eq!(acc.interest_accrual(&pay, 0, DAY / 2).interest, -501);
+        assert_eq!(acc.interest_accrual(&pay, 0, 1).interest, -1);
+    }
+
+    #[test]
+    fn post_interest_credits_and_charges() {
+        let mut acc = CollateralAccount::new(1, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, 3_600_000);
+        acc.set_time(360 * DAY);
+        let paid = acc.post_interest(&InterestConfig {
+            rate_bps: 100,
+            day_count: DayCount::Act360,
+        });
+        assert_eq!(paid.interest, 36_000);
+        assert_eq!(acc.face_value(CollateralType::Cash), 3_636_000);
+        assert_eq!(acc.accrued_to_ns(), 360 * DAY);
+        assert_eq!(
+            acc.history().last().unwrap().kind,
+            CollateralEventKind::Interest {
+                amount: 36_000,
+                from_ns: 0,
+            }
+        );
+
+        // 同時刻の再計上は 0。
+        assert_eq!(acc.post_interest(&InterestConfig::default()).interest, 0);
+
+        // マイナス金利は現金担保を超えて差し引かない。
+        acc.set_time(720 * DAY);
+        let charged = acc.post_interest(&InterestConfig {
+            rate_bps: -20_000,
+            day_count: DayCount::Act360,
+        });
+        assert_eq!(charged.interest, -3_636_000);
+        assert_eq!(acc.face_value(CollateralType::Cash), 0);
+        assert_eq!(acc.quantities_at(720 * DAY).get(&0), None);
+    }
+
     #[test]
     fn collateral_type_ordering() {
         assert!(CollateralType::Cash < CollateralType::GovernmentBond);
@@ -527,6 +2941,7 @@ mod tests {
             gov_bond_bps: 500,
             corp_bond_bps: 2000,
             equity_bps: 5000,
+            ..HaircutConfig::default()
         };
         // Cash 1%: 10000 * 9900/10000 = 9900
         assert_eq!(cfg.apply_haircut(CollateralType::Cash, 10_000), 9_900);
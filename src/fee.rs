@@ -0,0 +1,607 @@
+// ALICE-Settlement — Clearing fee and commission engine
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Moroya Sakamoto
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::netting::NetObligation;
+use crate::trade::Trade;
+
+/// Basis-point denominator (1 bp = 1/10 000).
+const BPS: i128 = 10_000;
+
+// ── Schedule ───────────────────────────────────────────────────────────
+
+/// Invalid [`FeeSchedule`] value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeeError {
+    /// A flat or per-lot rate is negative.  `account_id` is `None` for the
+    /// default rates.
+    NegativeRate { account_id: Option<u64> },
+    /// A volume tier discount exceeds 10 000 bps.
+    DiscountOutOfRange(u32),
+}
+
+/// Fee rates charged to one side of each trade and obligation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FeeRates {
+    /// Flat fee per trade.
+    pub per_trade: i64,
+    /// Flat fee per cleared net obligation.
+    pub per_obligation: i64,
+    /// Fee per started lot of traded quantity.
+    pub per_lot: i64,
+    /// Quantity making up one lot.  Zero disables the per-lot fee.
+    pub lot_size: u64,
+    /// Fee in basis points of traded notional (`price * quantity`).
+    pub notional_bps: u32,
+}
+
+impl FeeRates {
+    /// True when no rate is negative.
+    #[inline]
+    #[must_use]
+    pub const fn is_valid(&self) -> bool {
+        self.per_trade >= 0 && self.per_obligation >= 0 && self.per_lot >= 0
+    }
+}
+
+/// Volume discount applied once a member's notional in the cycle reaches
+/// `min_notional`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VolumeTier {
+    /// Minimum cycle notional for the tier to apply.
+    pub min_notional: u128,
+    /// Discount off the gross fee in basis points.
+    pub discount_bps: u32,
+}
+
+/// Fee schedule with per-member rate overrides and volume tiers.
+#[derive(Debug, Clone, Default)]
+pub struct FeeSchedule {
+    /// Rates for members without an override.
+    pub default_rates: FeeRates,
+    /// Per-member rate overrides, replacing the default rates entirely.
+    pub member_rates: HashMap<u64, FeeRates>,
+    /// Volume discount tiers; the highest qualifying tier applies.
+    pub tiers: Vec<VolumeTier>,
+}
+
+impl FeeSchedule {
+    /// Create a schedule with `default_rates` and no overrides or tiers.
+    ///
+    /// # Errors
+    ///
+    /// [`FeeError::NegativeRate`] if a rate is negative.
+    pub fn new(default_rates: FeeRates) -> Result<Self, FeeError> {
+        if !default_rates.is_valid() {
+            return Err(FeeError::NegativeRate { account_id: None });
+        }
+        Ok(Self {
+            default_rates,
+            ..Self::default()
+        })
+    }
+
+    /// Add a rate override for `account_id`.
+    ///
+    /// # Errors
+    ///
+    /// [`FeeError::NegativeRate`] if a rate is negative.
+    pub fn with_member_rates(mut self, account_id: u64, rates: FeeRates) -> Result<Self, FeeError> {
+        if !rates.is_valid() {
+            return Err(FeeError::NegativeRate {
+                account_id: Some(account_id),
+            });
+        }
+        self.member_rates.insert(account_id, rates);
+        Ok(self)
+    }
+
+    /// Add a volume tier.
+    ///
+    /// # Errors
+    ///
+    /// [`FeeError::DiscountOutOfRange`] if the discount exceeds 10 000 bps.
+    pub fn with_tier(mut self, tier: VolumeTier) -> Result<Self, FeeError> {
+        if i128::from(tier.discount_bps) > BPS {
+            return Err(FeeError::DiscountOutOfRange(tier.discount_bps));
+        }
+        self.tiers.push(tier);
+        Ok(self)
+    }
+
+    /// Check a schedule assembled field by field.
+    ///
+    /// # Errors
+    ///
+    /// The first invalid value; member overrides are checked in ascending
+    /// `account_id` order.
+    pub fn validate(&self) -> Result<(), FeeError> {
+        if !self.default_rates.is_valid() {
+            return Err(FeeError::NegativeRate { account_id: None });
+        }
+        let mut members: Vec<(&u64, &FeeRates)> = self.member_rates.iter().collect();
+        members.sort_unstable_by_key(|(id, _)| **id);
+        if let Some((&id, _)) = members.into_iter().find(|(_, r)| !r.is_valid()) {
+            return Err(FeeError::NegativeRate {
+                account_id: Some(id),
+            });
+        }
+        match self.tiers.iter().find(|t| i128::from(t.discount_bps) > BPS) {
+            Some(t) => Err(FeeError::DiscountOutOfRange(t.discount_bps)),
+            None => Ok(()),
+        }
+    }
+
+    /// Rates that apply to `account_id`.
+    #[inline]
+    #[must_use]
+    pub fn rates_for(&self, account_id: u64) -> &FeeRates {
+        self.member_rates
+            .get(&account_id)
+            .unwrap_or(&self.default_rates)
+    }
+
+    /// Discount in basis points earned by a cycle notional of `notional`.
+    #[must_use]
+    pub fn discount_bps(&self, notional: u128) -> u32 {
+        self.tiers
+            .iter()
+            .filter(|t| notional >= t.min_notional)
+            .max_by_key(|t| t.min_notional)
+            .map_or(0, |t| t.discount_bps.min(BPS as u32))
+    }
+}
+
+// ── Fee Obligations ────────────────────────────────────────────────────
+
+/// Fee owed by one member for one symbol in a cycle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeObligation {
+    /// Member charged.
+    pub account_id: u64,
+    /// Symbol the fee relates to.
+    pub symbol_hash: u64,
+    /// Per-trade component.
+    pub trade_fee: i64,
+    /// Per-obligation component.
+    pub obligation_fee: i64,
+    /// Per-lot component.
+    pub lot_fee: i64,
+    /// Notional (bps) component.
+    pub notional_fee: i64,
+    /// Volume discount deducted from the gross fee.
+    pub discount: i64,
+    /// Net amount payable.
+    pub amount: i64,
+}
+
+impl FeeObligation {
+    /// Sum of the fee components before discount.
+    #[inline]
+    #[must_use]
+    pub const fn gross(&self) -> i64 {
+        self.trade_fee
+            .saturating_add(self.obligation_fee)
+            .saturating_add(self.lot_fee)
+            .saturating_add(self.notional_fee)
+    }
+}
+
+/// Activity of one member in one symbol.
+#[derive(Debug, Clone, Copy, Default)]
+struct FeeUsage {
+    trades: u64,
+    obligations: u64,
+    lots: u128,
+    notional: u128,
+}
+
+// ── Fee Engine ─────────────────────────────────────────────────────────
+
+/// Accumulates trades during a netting cycle and prices them against a
+/// [`FeeSchedule`].
+///
+/// Feed the engine the same trades as the
+/// [`NettingEngine`](crate::netting::NettingEngine); both buyer and seller
+/// are charged.  Per-obligation fees are added for the obligations passed to
+/// [`compute_fees`](Self::compute_fees), typically those that cleared.
+pub struct FeeEngine {
+    schedule: FeeSchedule,
+    usage: BTreeMap<(u64, u64), FeeUsage>,
+    trade_count: usize,
+}
+
+impl FeeEngine {
+    /// Create an engine for a validated `schedule`.
+    ///
+    /// # Errors
+    ///
+    /// See [`FeeSchedule::validate`].
+    pub fn try_new(schedule: FeeSchedule) -> Result<Self, FeeError> {
+        schedule.validate()?;
+        Ok(Self::new(schedule))
+    }
+
+    /// Create an engine for `schedule`.  Schedules built with
+    /// [`FeeSchedule::new`] are already valid; use
+    /// [`try_new`](Self::try_new) for one assembled field by field.
+    #[must_use]
+    pub fn new(schedule: FeeSchedule) -> Self {
+        Self {
+            schedule,
+            usage: BTreeMap::new(),
+            trade_count: 0,
+        }
+    }
+
+    /// The fee schedule.
+    #[inline]
+    #[must_use]
+    pub const fn schedule(&self) -> &FeeSchedule {
+        &self.schedule
+    }
+
+    /// Accumulate a trade for both counterparties.
+    pub fn add_trade(&mut self, trade: &Trade) {
+        let notional = (trade.price as i128 * trade.quantity as i128).unsigned_abs();
+        for account_id in [trade.buyer_id, trade.seller_id] {
+            let lot_size = self.schedule.rates_for(account_id).lot_size;
+            let u = self
+                .usage
+                .entry((account_id, trade.symbol_hash))
+                .or_default();
+            u.trades += 1;
+            if lot_size > 0 {
+                u.lots += trade.quantity.div_ceil(lot_size) as u128;
+            }
+            u.notional = u.notional.saturating_add(notional);
+        }
+        self.trade_count += 1;
+    }
+
+    /// Number of trades accumulated.
+    #[inline]
+    #[must_use]
+    pub const fn trade_count(&self) -> usize {
+        self.trade_count
+    }
+
+    /// Price the accumulated trades plus `obligations`.
+    ///
+    /// Each obligation adds one per-obligation fee to both its deliverer and
+    /// receiver.  Volume tiers are selected on each member's total notional
+    /// across all symbols.  Results are ordered by `(account_id,
+    /// symbol_hash)`; zero fees are omitted.
+    #[must_use]
+    pub fn compute_fees(&self, obligations: &[NetObligation]) -> Vec<FeeObligation> {
+        let mut usage = self.usage.clone();
+        for ob in obligations {
+            for account_id in [ob.deliverer_id, ob.receiver_id] {
+                usage
+                    .entry((account_id, ob.symbol_hash))
+                    .or_default()
+                    .obligations += 1;
+            }
+        }
+
+        let mut member_notional: HashMap<u64, u128> = HashMap::new();
+        for (&(account_id, _), u) in &usage {
+            let total = member_notional.entry(account_id).or_insert(0);
+            *total = total.saturating_add(u.notional);
+        }
+
+        usage
+            .iter()
+            .filter_map(|(&(account_id, symbol_hash), u)| {
+                let rates = self.schedule.rates_for(account_id);
+                let discount_bps = self
+                    .schedule
+                    .discount_bps(member_notional.get(&account_id).copied().unwrap_or(0));
+                let fee = price(account_id, symbol_hash, rates, u, discount_bps);
+                (fee.amount != 0).then_some(fee)
+            })
+            .collect()
+    }
+
+    /// Reset the engine for the next cycle, keeping the schedule.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.usage.clear();
+        self.trade_count = 0;
+    }
+}
+
+fn price(
+    account_id: u64,
+    symbol_hash: u64,
+    rates: &FeeRates,
+    usage: &FeeUsage,
+    discount_bps: u32,
+) -> FeeObligation {
+    let trade_fee = rates.per_trade as i128 * usage.trades as i128;
+    let obligation_fee = rates.per_obligation as i128 * usage.obligations as i128;
+    let lot_fee = (rates.per_lot as i128).saturating_mul(usage.lots.min(i128::MAX as u128) as i128);
+    let notional_fee = usage
+        .notional
+        .saturating_mul(rates.notional_bps as u128)
+        .min(i128::MAX as u128) as i128
+        / BPS;
+    let gross = trade_fee
+        .saturating_add(obligation_fee)
+        .saturating_add(lot_fee)
+        .saturating_add(notional_fee);
+    let discount = gross.saturating_mul(discount_bps as i128) / BPS;
+
+    FeeObligation {
+        account_id,
+        symbol_hash,
+        trade_fee: clamp_i64(trade_fee),
+        obligation_fee: clamp_i64(obligation_fee),
+        lot_fee: clamp_i64(lot_fee),
+        notional_fee: clamp_i64(notional_fee),
+        discount: clamp_i64(discount),
+        amount: clamp_i64(gross - discount),
+    }
+}
+
+#[inline(always)]
+fn clamp_i64(v: i128) -> i64 {
+    v.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+// ── Tests ──────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trade::SettlementStatus;
+
+    fn trade(id: u64, sym: u64, buyer: u64, seller: u64, price: i64, qty: u64) -> Trade {
+        Trade {
+            trade_id: id,
+            symbol_hash: sym,
+            buyer_id: buyer,
+            seller_id: seller,
+            price,
+            quantity: qty,
+            timestamp_ns: 0,
+            status: SettlementStatus::Pending,
+        }
+    }
+
+    fn ob(sym: u64, deliverer: u64, receiver: u64) -> NetObligation {
+        NetObligation {
+            symbol_hash: sym,
+            deliverer_id: deliverer,
+            receiver_id: receiver,
+            net_quantity: 1,
+            net_payment: 1,
+            trade_count: 1,
+        }
+    }
+
+    #[test]
+    fn schedule_rejects_negative_rates() {
+        let negative = FeeRates {
+            per_lot: -1,
+            lot_size: 10,
+            ..FeeRates::default()
+        };
+        assert_eq!(
+            FeeSchedule::new(negative).unwrap_err(),
+            FeeError::NegativeRate { account_id: None }
+        );
+        assert_eq!(
+            FeeSchedule::new(FeeRates::default())
+                .unwrap()
+                .with_member_rates(7, negative)
+                .unwrap_err(),
+            FeeError::NegativeRate {
+                account_id: Some(7)
+            }
+        );
+        assert_eq!(
+            FeeSchedule::new(FeeRates::default())
+                .unwrap()
+                .with_tier(VolumeTier {
+                    min_notional: 1,
+                    discount_bps: 10_001,
+                })
+                .unwrap_err(),
+            FeeError::DiscountOutOfRange(10_001)
+        );
+
+        let mut assembled = FeeSchedule::default();
+        assembled.member_rates.insert(
+            3,
+            FeeRates {
+                per_trade: -5,
+                ..FeeRates::default()
+            },
+        );
+        assert_eq!(
+            FeeEngine::try_new(assembled).err(),
+            Some(FeeError::NegativeRate {
+                account_id: Some(3)
+            })
+        );
+        assert!(FeeEngine::try_new(FeeSchedule::default()).is_ok());
+    }
+
+    #[test]
+    fn per_trade_fee_charged_to_both_sides() {
+        let mut fe = FeeEngine::new(FeeSchedule {
+            default_rates: FeeRates {
+                per_trade: 5,
+                ..FeeRates::default()
+            },
+            ..FeeSchedule::default()
+        });
+        fe.add_trade(&trade(1, 0xA, 1, 2, 100, 10));
+        fe.add_trade(&trade(2, 0xA, 2, 1, 100, 4));
+        let fees = fe.compute_fees(&[]);
+        assert_eq!(fees.len(), 2);
+        assert!(fees.iter().all(|f| f.amount == 10 && f.trade_fee == 10));
+        assert_eq!(fe.trade_count(), 2);
+    }
+
+    #[test]
+    fn per_obligation_fee() {
+        let fe = FeeEngine::new(FeeSchedule {
+            default_rates: FeeRates {
+                per_obligation: 7,
+                ..FeeRates::default()
+            },
+            ..FeeSchedule::default()
+        });
+        let fees = fe.compute_fees(&[ob(0xA, 1, 2), ob(0xB, 1, 3)]);
+        assert_eq!(fees.len(), 4);
+        assert!(fees.iter().all(|f| f.obligation_fee == 7));
+    }
+
+    #[test]
+    fn per_lot_fee_rounds_up() {
+        let mut fe = FeeEngine::new(FeeSchedule {
+            default_rates: FeeRates {
+                per_lot: 3,
+                lot_size: 100,
+                ..FeeRates::default()
+            },
+            ..FeeSchedule::default()
+        });
+        fe.add_trade(&trade(1, 0xA, 1, 2, 10, 250)); // 3 lots
+        fe.add_trade(&trade(2, 0xA, 1, 2, 10, 100)); // 1 lot
+        let fees = fe.compute_fees(&[]);
+        assert_eq!(fees[0].lot_fee, 12);
+    }
+
+    #[test]
+    fn notional_bps_fee() {
+        let mut fe = FeeEngine::new(FeeSchedule {
+            default_rates: FeeRates {
+                notional_bps: 25,
+                ..FeeRates::default()
+            },
+            ..FeeSchedule::default()
+        });
+        fe.add_trade(&trade(1, 0xA, 1, 2, 50_000, 10)); // notional 500_000
+        let fees = fe.compute_fees(&[]);
+        assert_eq!(fees[0].notional_fee, 1_250);
+    }
+
+    #[test]
+    fn volume_tier_discount_uses_total_notional() {
+        let mut fe = FeeEngine::new(FeeSchedule {
+            default_rates: FeeRates {
+                notional_bps: 10,
+                ..FeeRates::default()
+            },
+            member_rates: HashMap::new(),
+            tiers: vec![
+                VolumeTier {
+                    min_notional: 1_000_000,
+                    discount_bps: 1_000,
+                },
+                VolumeTier {
+                    min_notional: 2_000_000,
+                    discount_bps: 2_500,
+                },
+            ],
+        });
+        // Member 1 trades 1.5M across two symbols; member 3 only 0.5M.
+        fe.add_trade(&trade(1, 0xA, 1, 2, 1_000, 1_000));
+        fe.add_trade(&trade(2, 0xB, 1, 3, 1_000, 500));
+        let fees = fe.compute_fees(&[]);
+        let m1: Vec<_> = fees.iter().filter(|f| f.account_id == 1).collect();
+        assert_eq!(m1[0].notional_fee, 1_000);
+        assert_eq!(m1[0].discount, 100);
+        assert_eq!(m1[0].amount, 900);
+        assert_eq!(m1[1].amount, 450);
+        let m3 = fees.iter().find(|f| f.account_id == 3).unwrap();
+        assert_eq!(m3.discount, 0);
+        assert_eq!(m3.amount, 500);
+    }
+
+    #[test]
+    fn member_override_replaces_default() {
+        let mut member_rates = HashMap::new();
+        member_rates.insert(
+            2,
+            FeeRates {
+                per_trade: 1,
+                ..FeeRates::default()
+            },
+        );
+        let mut fe = FeeEngine::new(FeeSchedule {
+            default_rates: FeeRates {
+                per_trade: 10,
+                notional_bps: 100,
+                ..FeeRates::default()
+            },
+            member_rates,
+            tiers: Vec::new(),
+        });
+        fe.add_trade(&trade(1, 0xA, 1, 2, 100, 1));
+        let fees = fe.compute_fees(&[]);
+        assert_eq!(fees[0].amount, 11); // 10 + 1% of 100
+        assert_eq!(fees[1].account_id, 2);
+        assert_eq!(fees[1].amount, 1);
+    }
+
+    #[test]
+    fn zero_fees_omitted_and_ordered() {
+        let mut fe = FeeEngine::new(FeeSchedule::default());
+        fe.add_trade(&trade(1, 0xA, 1, 2, 100, 1));
+        assert!(fe.compute_fees(&[ob(0xA, 1, 2)]).is_empty());
+
+        let mut fe = FeeEngine::new(FeeSchedule {
+            default_rates: FeeRates {
+                per_trade: 1,
+                ..FeeRates::default()
+            },
+            ..FeeSchedule::default()
+        });
+        fe.add_trade(&trade(1, 0xB, 9, 3, 1, 1));
+        fe.add_trade(&trade(2, 0xA, 9, 3, 1, 1));
+        let keys: Vec<_> = fe
+            .compute_fees(&[])
+            .iter()
+            .map(|f| (f.account_id, f.symbol_hash))
+            .collect();
+        assert_eq!(keys, vec![(3, 0xA), (3, 0xB), (9, 0xA), (9, 0xB)]);
+    }
+
+    #[test]
+    fn clear_resets_usage() {
+        let mut fe = FeeEngine::new(FeeSchedule {
+            default_rates: FeeRates {
+                per_trade: 1,
+                ..FeeRates::default()
+            },
+            ..FeeSchedule::default()
+        });
+        fe.add_trade(&trade(1, 0xA, 1, 2, 1, 1));
+        fe.clear();
+        assert_eq!(fe.trade_count(), 0);
+        assert!(fe.compute_fees(&[]).is_empty());
+    }
+
+    #[test]
+    fn extreme_inputs_saturate() {
+        let mut fe = FeeEngine::new(FeeSchedule {
+            default_rates: FeeRates {
+                per_lot: i64::MAX,
+                lot_size: 1,
+                notional_bps: u32::MAX,
+                ..FeeRates::default()
+            },
+            ..FeeSchedule::default()
+        });
+        fe.add_trade(&trade(1, 0xA, 1, 2, i64::MAX, u64::MAX));
+        let fees = fe.compute_fees(&[]);
+        assert_eq!(fees[0].amount, i64::MAX);
+        assert_eq!(fees[0].gross(), i64::MAX);
+    }
+}
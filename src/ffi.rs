@@ -1,6 +1,6 @@
 //! C FFI for ALICE-Settlement
 //!
-//! Provides 22 `extern "C"` functions for Unity / UE5 / native integration.
+//! Provides 23 `extern "C"` functions for Unity / UE5 / native integration.
 //!
 //! License: AGPL-3.0-only
 //! Author: Moroya Sakamoto
@@ -209,7 +209,7 @@ pub unsafe extern "C" fn alice_clearing_house_new() -> *mut ClearingHouse {
     Box::into_raw(Box::new(ClearingHouse::new()))
 }
 
-/// アカウントを登録する。
+/// アカウントを登録する。成功=0, 無効なポインタ=-1, 登録済みのID=-2。
 ///
 /// # Safety
 ///
@@ -219,9 +219,13 @@ pub unsafe extern "C" fn alice_clearing_house_register_account(
     ch: *mut ClearingHouse,
     id: u64,
     initial_balance: i64,
-) {
-    if !ch.is_null() {
-        (*ch).register_account(id, initial_balance);
+) -> i32 {
+    if ch.is_null() {
+        return -1;
+    }
+    match (*ch).register_account(id, initial_balance) {
+        Ok(()) => 0,
+        Err(_) => -2,
     }
 }
 
@@ -241,7 +245,8 @@ pub unsafe extern "C" fn alice_clearing_house_get_balance(
     (*ch).get_account(id).map_or(i64::MIN, |acc| acc.balance)
 }
 
-/// ネット債務をクリアリングする。成功=0, 残高不足=-1, アカウント未登録=-2。
+/// ネット債務をクリアリングする。成功=0, 残高不足=-1, アカウント未登録=-2,
+/// アカウント停止中・デフォルト・閉鎖済み=-3。
 ///
 /// # Safety
 ///
@@ -271,6 +276,7 @@ pub unsafe extern "C" fn alice_clearing_house_clear_obligation(
         Ok(()) => 0,
         Err(crate::clearing::ClearingError::InsufficientBalance { .. }) => -1,
         Err(crate::clearing::ClearingError::AccountNotFound(_)) => -2,
+        Err(_) => -3,
     }
 }
 
@@ -286,9 +292,9 @@ pub unsafe extern "C" fn alice_clearing_house_destroy(ch: *mut ClearingHouse) {
     }
 }
 
-// ── MarginEngine (4) ────────────────────────────────────────────────
+// ── MarginEngine (5) ────────────────────────────────────────────────
 
-/// MarginEngineを新規作成する。
+/// MarginEngineを新規作成する。レートがNaN・負数、またはフロアが負数の場合はNULLを返す。
 ///
 /// # Safety
 ///
@@ -302,10 +308,31 @@ pub unsafe extern "C" fn alice_margin_engine_new(
     let config = MarginConfig {
         initial_margin_rate,
         variation_margin_rate,
-        stress_scenarios: vec![0.85, 0.90, 0.95, 1.05, 1.10, 1.15],
         margin_floor,
+        ..MarginConfig::default()
     };
-    Box::into_raw(Box::new(MarginEngine::new(config)))
+    match MarginEngine::try_new(config) {
+        Ok(engine) => Box::into_raw(Box::new(engine)),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// シンボルの時価を設定する。
+///
+/// # Safety
+///
+/// `engine`は有効なポインタであること。
+#[no_mangle]
+pub unsafe extern "C" fn alice_margin_engine_set_mark_price(
+    engine: *mut MarginEngine,
+    symbol_hash: u64,
+    mark_price: i64,
+) -> i32 {
+    if engine.is_null() {
+        return -1;
+    }
+    (*engine).set_mark_price(symbol_hash, mark_price);
+    0
 }
 
 /// 単一債務に対するマージンを計算する。
@@ -569,8 +596,8 @@ mod tests {
             let ch = alice_clearing_house_new();
             assert!(!ch.is_null());
 
-            alice_clearing_house_register_account(ch, 100, 50_000);
-            alice_clearing_house_register_account(ch, 200, 10_000);
+            assert_eq!(alice_clearing_house_register_account(ch, 100, 50_000), 0);
+            assert_eq!(alice_clearing_house_register_account(ch, 200, 10_000), 0);
 
             let bal = alice_clearing_house_get_balance(ch, 100);
             assert_eq!(bal, 50_000);
@@ -599,7 +626,19 @@ mod tests {
             assert_eq!(req.initial_margin, 250);
             assert_eq!(req.total_margin, 5_250);
 
+            assert_eq!(alice_margin_engine_set_mark_price(engine, 0, 400), 0);
+            let rc = alice_margin_engine_compute_obligation(engine, 100, 200, 10, 5_000, &mut req);
+            assert_eq!(rc, 0);
+            assert_eq!(req.initial_margin, 200);
+            assert_eq!(
+                alice_margin_engine_set_mark_price(ptr::null_mut(), 0, 400),
+                -1
+            );
+
             alice_margin_engine_destroy(engine);
+
+            assert!(alice_margin_engine_new(f64::NAN, 1.0, 100).is_null());
+            assert!(alice_margin_engine_new(0.05, -1.0, 100).is_null());
         }
     }
 
@@ -3,8 +3,10 @@
     Copyright (C) 2026 Moroya Sakamoto
 */
 
+use crate::clearing::AccountStatus;
+
 /// A settlement journal entry for audit purposes.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct JournalEntry {
     /// Sequential entry number.
     pub sequence: u64,
@@ -15,7 +17,7 @@ pub struct JournalEntry {
 }
 
 /// Events that can be recorded in the settlement journal.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum JournalEvent {
     TradeReceived {
         trade_id: u64,
@@ -35,6 +37,67 @@ pub enum JournalEvent {
         trade_id: u64,
         reason: String,
     },
+    CashDeposited {
+        account_id: u64,
+        amount: i64,
+        idempotency_key: u64,
+    },
+    CashWithdrawn {
+        account_id: u64,
+        amount: i64,
+        idempotency_key: u64,
+    },
+    CashTransferred {
+        from_id: u64,
+        to_id: u64,
+        amount: i64,
+        idempotency_key: u64,
+    },
+    AccountRegistered {
+        account_id: u64,
+        balance: i64,
+    },
+    ObligationCleared {
+        symbol_hash: u64,
+        deliverer_id: u64,
+        receiver_id: u64,
+        net_quantity: u64,
+        net_payment: i64,
+    },
+    ObligationFailed {
+        symbol_hash: u64,
+        deliverer_id: u64,
+        receiver_id: u64,
+        net_quantity: u64,
+        net_payment: i64,
+        reason: String,
+    },
+    MarginPosted {
+        account_id: u64,
+        amount: i64,
+    },
+    MarginReleased {
+        account_id: u64,
+        amount: i64,
+    },
+    WaterfallApplied {
+        account_id: u64,
+        absorbed: [i64; 5],
+    },
+    FeeCharged {
+        account_id: u64,
+        symbol_hash: u64,
+        amount: i64,
+    },
+    AccountStatusChanged {
+        account_id: u64,
+        from: AccountStatus,
+        to: AccountStatus,
+    },
+    OverdraftLimitSet {
+        account_id: u64,
+        limit: i64,
+    },
 }
 
 /// Append-only settlement journal for audit trail.
@@ -57,6 +120,17 @@ impl SettlementJournal {
         }
     }
 
+    /// Create an empty journal that continues an earlier one: the first
+    /// recorded entry will have sequence `next_sequence`.
+    #[inline(always)]
+    #[must_use]
+    pub const fn resume(next_sequence: u64) -> Self {
+        Self {
+            entries: Vec::new(),
+            next_seq: next_sequence,
+        }
+    }
+
     /// Append an event to the journal.
     pub fn record(&mut self, timestamp_ns: u64, event: JournalEvent) {
         let sequence = self.next_seq;
@@ -68,6 +142,25 @@ impl SettlementJournal {
         });
     }
 
+    /// Append an entry recorded elsewhere, keeping its sequence and
+    /// timestamp.  Returns `false` (and appends nothing) unless its sequence
+    /// is the next expected one.
+    pub fn append(&mut self, entry: JournalEntry) -> bool {
+        if entry.sequence != self.next_seq {
+            return false;
+        }
+        self.next_seq += 1;
+        self.entries.push(entry);
+        true
+    }
+
+    /// Sequence number the next recorded entry will receive.
+    #[inline(always)]
+    #[must_use]
+    pub const fn next_sequence(&self) -> u64 {
+        self.next_seq
+    }
+
     /// Return a slice of all journal entries in order.
     #[inline(always)]
     #[must_use]
@@ -262,4 +355,21 @@ mod tests {
         let last = journal.last_entry().unwrap();
         assert_eq!(last.sequence, 1000);
     }
+
+    #[test]
+    fn test_journal_resume_and_append() {
+        let mut source = SettlementJournal::new();
+        for i in 0..3u64 {
+            source.record(i, JournalEvent::TradeReceived { trade_id: i });
+        }
+        let mut resumed = SettlementJournal::resume(3);
+        assert!(resumed.is_empty());
+        assert_eq!(resumed.next_sequence(), 3);
+        // Out-of-order entries are rejected.
+        assert!(!resumed.append(source.entries()[0].clone()));
+        assert!(resumed.append(source.entries()[2].clone()));
+        assert_eq!(resumed.entries()[0], source.entries()[2]);
+        resumed.record(9, JournalEvent::TradeReceived { trade_id: 9 });
+        assert_eq!(resumed.last_entry().unwrap().sequence, 4);
+    }
 }
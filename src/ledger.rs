@@ -0,0 +1,448 @@
+// ALICE-Settlement — Double-entry general ledger for cash movements
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Moroya Sakamoto
+
+use std::collections::HashMap;
+
+use crate::waterfall::WaterfallLayer;
+
+// ── Types ──────────────────────────────────────────────────────────────
+
+/// A general-ledger account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LedgerAccount {
+    /// Funds outside the clearing house (treasury, banks, opening balances).
+    External,
+    /// A member's available cash balance.
+    MemberCash(u64),
+    /// Margin held on behalf of a member.
+    MemberMargin(u64),
+    /// Resources of a default waterfall layer (excluding the defaulter's
+    /// margin, which is held in [`LedgerAccount::MemberMargin`]).
+    Waterfall(WaterfallLayer),
+    /// Loss arising from a member's default, absorbed by the waterfall.
+    DefaultLoss(u64),
+    /// Fees collected by the clearing house.
+    FeeIncome,
+}
+
+/// Business reason for a ledger entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerEntryKind {
+    /// Opening balance or balance replacement on account registration.
+    Adjustment,
+    /// Cash leg of a cleared net obligation.
+    ClearingTransfer { symbol_hash: u64 },
+    /// Cash moved from a member's balance into margin.
+    MarginPosted,
+    /// Margin returned to a member's balance.
+    MarginReleased,
+    /// Loss absorbed by a waterfall layer.
+    WaterfallDraw { layer: WaterfallLayer },
+    /// Cash paid in from outside the clearing house.
+    CashDeposit,
+    /// Cash paid out of the clearing house.
+    CashWithdrawal,
+    /// Cash moved between two members.
+    CashTransfer,
+    /// Clearing, exchange or settlement fee charged to a member.
+    Fee { symbol_hash: u64 },
+}
+
+/// A balanced double-entry record.
+///
+/// `amount` is added to the `debit` account and subtracted from the `credit`
+/// account, so every entry nets to zero across the ledger.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LedgerEntry {
+    /// Sequential entry identifier, starting at 1.
+    pub entry_id: u64,
+    /// Business reason.
+    pub kind: LedgerEntryKind,
+    /// Account whose balance increases.
+    pub debit: LedgerAccount,
+    /// Account whose balance decreases.
+    pub credit: LedgerAccount,
+    /// Amount in ticks.
+    pub amount: i64,
+}
+
+/// A difference between a book balance and its ledger balance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LedgerMismatch {
+    /// Ledger account that disagrees.
+    pub account: LedgerAccount,
+    /// Balance derived from the ledger.
+    pub ledger_balance: i64,
+    /// Balance held in the book of record (e.g. `ClearingAccount`).
+    pub book_balance: i64,
+}
+
+// ── General Ledger ─────────────────────────────────────────────────────
+
+/// Append-only double-entry general ledger.
+///
+/// Balances are maintained incrementally so lookups are O(1); [`verify`]
+/// recomputes them from the entries for a full audit.
+///
+/// [`verify`]: GeneralLedger::verify
+pub struct GeneralLedger {
+    entries: Vec<LedgerEntry>,
+    balances: HashMap<LedgerAccount, i128>,
+    next_id: u64,
+}
+
+impl GeneralLedger {
+    /// Create an empty ledger.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            balances: HashMap::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Post a balanced entry moving `amount` from `credit` to `debit`.
+    ///
+    /// Zero amounts are not recorded.  Returns the entry identifier, or
+    /// `None` when nothing was posted.
+    pub fn post(
+        &mut self,
+        kind: LedgerEntryKind,
+        debit: LedgerAccount,
+        credit: LedgerAccount,
+        amount: i64,
+    ) -> Option<u64> {
+        if amount == 0 {
+            return None;
+        }
+        let entry_id = self.next_id;
+        self.next_id += 1;
+        *self.balances.entry(debit).or_insert(0) += amount as i128;
+        *self.balances.entry(credit).or_insert(0) -= amount as i128;
+        self.entries.push(LedgerEntry {
+            entry_id,
+            kind,
+            debit,
+            credit,
+            amount,
+        });
+        Some(entry_id)
+    }
+
+    /// Post the registration of member `id` with `initial_balance` against
+    /// [`LedgerAccount::External`].
+    pub fn post_registration(&mut self, id: u64, initial_balance: i64) {
+        self.post(
+            LedgerEntryKind::Adjustment,
+            LedgerAccount::MemberCash(id),
+            LedgerAccount::External,
+            initial_balance,
+        );
+    }
+
+    /// Post every absorbing layer of a waterfall as a draw against
+    /// `defaulter_id`'s default loss.  `absorbed` is indexed by
+    /// `WaterfallLayer as usize` (see
+    /// [`WaterfallResult::absorbed_by_layer`](crate::waterfall::WaterfallResult::absorbed_by_layer)).
+    ///
+    /// The defaulter-margin layer is drawn from the defaulter's
+    /// [`LedgerAccount::MemberMargin`]; the remaining layers are drawn from
+    /// their [`LedgerAccount::Waterfall`] accounts.
+    pub fn post_waterfall(&mut self, defaulter_id: u64, absorbed: &[i64; 5]) {
+        for layer in WaterfallLayer::ALL {
+            let source = match layer {
+                WaterfallLayer::DefaulterMargin => LedgerAccount::MemberMargin(defaulter_id),
+                other => LedgerAccount::Waterfall(other),
+            };
+            self.post(
+                LedgerEntryKind::WaterfallDraw { layer },
+                LedgerAccount::DefaultLoss(defaulter_id),
+                source,
+                absorbed[layer as usize],
+            );
+        }
+    }
+
+    /// Balance of a ledger account (debits minus credits), clamped to `i64`.
+    #[must_use]
+    pub fn balance(&self, account: LedgerAccount) -> i64 {
+        self.balances
+            .get(&account)
+            .map_or(0, |&b| b.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+
+    /// Non-zero balances of house accounts (fee income, waterfall layers and
+    /// default losses), ordered by account.
+    #[must_use]
+    pub fn house_balances(&self) -> Vec<(LedgerAccount, i64)> {
+        let mut out: Vec<(LedgerAccount, i64)> = self
+            .balances
+            .keys()
+            .filter(|acc| {
+                !matches!(
+                    acc,
+                    LedgerAccount::External
+                        | LedgerAccount::MemberCash(_)
+                        | LedgerAccount::MemberMargin(_)
+                )
+            })
+            .map(|&acc| (acc, self.balance(acc)))
+            .filter(|&(_, b)| b != 0)
+            .collect();
+        out.sort_unstable_by_key(|&(acc, _)| acc);
+        out
+    }
+
+    /// Sum of all account balances.  Always zero for a consistent ledger.
+    #[must_use]
+    pub fn trial_balance(&self) -> i128 {
+        self.balances.values().sum()
+    }
+
+    /// Recompute every balance from the entries and compare with the cached
+    /// balances.  Returns `true` when they agree and the ledger nets to zero.
+    #[must_use]
+    pub fn verify(&self) -> bool {
+        let mut recomputed: HashMap<LedgerAccount, i128> = HashMap::new();
+        for e in &self.entries {
+            *recomputed.entry(e.debit).or_insert(0) += e.amount as i128;
+            *recomputed.entry(e.credit).or_insert(0) -= e.amount as i128;
+        }
+        let cached_agree = self
+            .balances
+            .iter()
+            .all(|(acc, &b)| recomputed.get(acc).copied().unwrap_or(0) == b);
+        let recomputed_agree = recomputed
+            .iter()
+            .all(|(acc, &b)| self.balances.get(acc).copied().unwrap_or(0) == b);
+        cached_agree && recomputed_agree && self.trial_balance() == 0
+    }
+
+    /// Compare book balances with ledger balances.
+    ///
+    /// Returns one [`LedgerMismatch`] per disagreeing account, in input
+    /// order.
+    pub fn reconcile<I>(&self, book: I) -> Vec<LedgerMismatch>
+    where
+        I: IntoIterator<Item = (LedgerAccount, i64)>,
+    {
+        book.into_iter()
+            .filter_map(|(account, book_balance)| {
+                let ledger_balance = self.balance(account);
+                (ledger_balance != book_balance).then_some(LedgerMismatch {
+                    account,
+                    ledger_balance,
+                    book_balance,
+                })
+            })
+            .collect()
+    }
+
+    /// All entries in posting order.
+    #[inline]
+    #[must_use]
+    pub fn entries(&self) -> &[LedgerEntry] {
+        &self.entries
+    }
+
+    /// Entries that touch `account`, in posting order.
+    #[must_use]
+    pub fn entries_for(&self, account: LedgerAccount) -> Vec<&LedgerEntry> {
+        self.entries
+            .iter()
+            .filter(|e| e.debit == account || e.credit == account)
+            .collect()
+    }
+
+    /// Number of entries.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// True when no entries have been posted.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Default for GeneralLedger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ── Tests ──────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::waterfall::{DefaultWaterfall, WaterfallConfig};
+
+    #[test]
+    fn empty_ledger() {
+        let gl = GeneralLedger::new();
+        assert!(gl.is_empty());
+        assert_eq!(gl.balance(LedgerAccount::External), 0);
+        assert_eq!(gl.trial_balance(), 0);
+        assert!(gl.verify());
+    }
+
+    #[test]
+    fn post_is_balanced() {
+        let mut gl = GeneralLedger::new();
+        let id = gl.post(
+            LedgerEntryKind::Adjustment,
+            LedgerAccount::MemberCash(1),
+            LedgerAccount::External,
+            1_000,
+        );
+        assert_eq!(id, Some(1));
+        assert_eq!(gl.balance(LedgerAccount::MemberCash(1)), 1_000);
+        assert_eq!(gl.balance(LedgerAccount::External), -1_000);
+        assert_eq!(gl.trial_balance(), 0);
+        assert!(gl.verify());
+    }
+
+    #[test]
+    fn zero_amount_not_recorded() {
+        let mut gl = GeneralLedger::new();
+        let id = gl.post(
+            LedgerEntryKind::MarginPosted,
+            LedgerAccount::MemberMargin(1),
+            LedgerAccount::MemberCash(1),
+            0,
+        );
+        assert!(id.is_none());
+        assert!(gl.is_empty());
+    }
+
+    #[test]
+    fn entry_ids_increment() {
+        let mut gl = GeneralLedger::new();
+        for i in 0..5 {
+            gl.post(
+                LedgerEntryKind::Adjustment,
+                LedgerAccount::MemberCash(i),
+                LedgerAccount::External,
+                10,
+            );
+        }
+        for (i, e) in gl.entries().iter().enumerate() {
+            assert_eq!(e.entry_id, i as u64 + 1);
+        }
+    }
+
+    #[test]
+    fn entries_for_filters_by_account() {
+        let mut gl = GeneralLedger::new();
+        gl.post(
+            LedgerEntryKind::Adjustment,
+            LedgerAccount::MemberCash(1),
+            LedgerAccount::External,
+            100,
+        );
+        gl.post(
+            LedgerEntryKind::Adjustment,
+            LedgerAccount::MemberCash(2),
+            LedgerAccount::External,
+            100,
+        );
+        gl.post(
+            LedgerEntryKind::ClearingTransfer { symbol_hash: 0xAB },
+            LedgerAccount::MemberCash(2),
+            LedgerAccount::MemberCash(1),
+            40,
+        );
+        assert_eq!(gl.entries_for(LedgerAccount::MemberCash(1)).len(), 2);
+        assert_eq!(gl.entries_for(LedgerAccount::External).len(), 2);
+        assert_eq!(gl.balance(LedgerAccount::MemberCash(2)), 140);
+    }
+
+    #[test]
+    fn waterfall_draws_posted_per_layer() {
+        let wf = DefaultWaterfall::new(WaterfallConfig {
+            defaulter_margin: 100,
+            defaulter_fund: 50,
+            ccp_first_loss: 30,
+            members_fund: 200,
+            ccp_capital: 500,
+        });
+        let result = wf.absorb_loss(170);
+
+        let mut gl = GeneralLedger::new();
+        gl.post_waterfall(7, &result.absorbed_by_layer());
+
+        // Three layers absorb (100, 50, 20); zero layers are skipped.
+        assert_eq!(gl.len(), 3);
+        assert_eq!(gl.balance(LedgerAccount::DefaultLoss(7)), 170);
+        assert_eq!(gl.balance(LedgerAccount::MemberMargin(7)), -100);
+        assert_eq!(
+            gl.balance(LedgerAccount::Waterfall(WaterfallLayer::DefaulterFund)),
+            -50
+        );
+        assert_eq!(
+            gl.balance(LedgerAccount::Waterfall(WaterfallLayer::CcpFirstLoss)),
+            -20
+        );
+        assert!(gl.verify());
+    }
+
+    #[test]
+    fn negative_amount_reverses_direction() {
+        let mut gl = GeneralLedger::new();
+        gl.post(
+            LedgerEntryKind::ClearingTransfer { symbol_hash: 1 },
+            LedgerAccount::MemberCash(2),
+            LedgerAccount::MemberCash(1),
+            -300,
+        );
+        assert_eq!(gl.balance(LedgerAccount::MemberCash(1)), 300);
+        assert_eq!(gl.balance(LedgerAccount::MemberCash(2)), -300);
+        assert!(gl.verify());
+    }
+
+    #[test]
+    fn reconcile_reports_disagreements() {
+        let mut gl = GeneralLedger::new();
+        gl.post(
+            LedgerEntryKind::Adjustment,
+            LedgerAccount::MemberCash(1),
+            LedgerAccount::External,
+            100,
+        );
+        let mismatches = gl.reconcile([
+            (LedgerAccount::MemberCash(1), 100),
+            (LedgerAccount::MemberCash(2), 5),
+        ]);
+        assert_eq!(
+            mismatches,
+            vec![LedgerMismatch {
+                account: LedgerAccount::MemberCash(2),
+                ledger_balance: 0,
+                book_balance: 5,
+            }]
+        );
+    }
+
+    #[test]
+    fn extreme_amounts_do_not_overflow() {
+        let mut gl = GeneralLedger::new();
+        for _ in 0..3 {
+            gl.post(
+                LedgerEntryKind::Adjustment,
+                LedgerAccount::MemberCash(1),
+                LedgerAccount::External,
+                i64::MAX,
+            );
+        }
+        assert_eq!(gl.balance(LedgerAccount::MemberCash(1)), i64::MAX);
+        assert_eq!(gl.balance(LedgerAccount::External), i64::MIN);
+        assert_eq!(gl.trial_balance(), 0);
+    }
+}
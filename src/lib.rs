@@ -27,9 +27,21 @@
 //! | [`trade`] | `Trade` and `SettlementStatus` lifecycle types |
 //! | [`netting`] | Bilateral and multilateral netting of trade obligations |
 //! | [`clearing`] | `ClearingHouse` account management and fund transfer |
+//! | [`fee`] | Fee schedules with per-trade, per-lot, bps and tiered fees |
+//! | [`allocation`] | Cheapest-to-deliver collateral allocation across margin requirements |
+//! | [`collateral`] | Per-asset collateral holdings, haircuts and type, issuer and absolute concentration limits |
 //! | [`margin`] | SPAN-style margin computation (initial, variation, stress) |
+//! | [`margin_call`] | Margin calls, excess returns, deadlines and default escalation |
+//! | [`audit`] | Balance conservation audit against external flows and the ledger |
+//! | [`ledger`] | Double-entry general ledger for clearing, margin and waterfall cash |
 //! | [`journal`] | Append-only settlement journal with hash-chained entries |
 //! | [`replay`] | Deterministic journal replay and verification |
+//! | [`risk`] | Pre-clearing gross, net, position and concentration limits |
+//! | [`sharded`] | Thread-safe sharded clearing with parallel conflict-free waves |
+//! | [`snapshot`] | Versioned, hash-checked binary snapshots of clearing state |
+//! | [`span`] | SPAN portfolio margin with 16-scenario risk arrays and spread charges |
+//! | [`statement`] | End-of-day per-member statements with CSV and JSON export |
+//! | [`var`] | Historical-simulation VaR and expected-shortfall margin with APC buffers |
 //! | [`waterfall`] | Default waterfall cascade for loss absorption |
 //!
 //! # Quick Start
@@ -60,30 +72,77 @@
 //! assert_eq!(obligations[0].net_quantity, 7); // 10 - 3
 //! ```
 
+pub mod allocation;
+pub mod audit;
 pub mod clearing;
 pub mod collateral;
+pub mod fee;
 #[cfg(feature = "ffi")]
 pub mod ffi;
 pub mod journal;
+pub mod ledger;
 /// SPAN-style margin computation (initial, variation, stress).
 pub mod margin;
+pub mod margin_call;
 pub mod netting;
 /// Deterministic journal replay and verification.
 pub mod replay;
+pub mod risk;
+pub mod sharded;
+pub mod snapshot;
+pub mod span;
+pub mod statement;
 pub mod trade;
+pub mod var;
 /// Default waterfall cascade for loss absorption.
 pub mod waterfall;
 
-pub use clearing::{ClearingAccount, ClearingError, ClearingHouse, ClearingResult};
+pub use allocation::{
+    Allocation, AllocationEngine, AllocationPlan, AllocationPreferences, RequirementCoverage,
+};
+pub use audit::{AuditReport, AuditViolation, BalanceAuditor, ExternalFlows};
+pub use clearing::{
+    AccountStatus, ClearingAccount, ClearingError, ClearingHouse, ClearingResult, FeeResult,
+};
 pub use collateral::{
-    check_concentration, CollateralAccount, CollateralHolding, CollateralType, ConcentrationBreach,
-    ConcentrationLimits, HaircutConfig,
+    check_concentration, check_concentration_against_requirement, limited_issuer, maturity_bucket,
+    revalue_collateral, AccountRevaluation, AddOnCondition, CollateralAccount, CollateralAsset,
+    CollateralError, CollateralEvent, CollateralEventKind, CollateralHolding, CollateralType,
+    ConcentrationBreach, ConcentrationLimits, ConcentrationScope, CreditRating, DayCount,
+    EligibleValue, ExcessTreatment, HaircutAddOn, HaircutConfig, HaircutGridKey, HaircutModel,
+    HaircutSchedule, HoldingRevaluation, InterestAccrual, InterestConfig, PriceSnapshot,
+    RevaluationReport, Substitution, SubstitutionError,
 };
+pub use fee::{FeeEngine, FeeError, FeeObligation, FeeRates, FeeSchedule, VolumeTier};
 pub use journal::{JournalEntry, JournalEvent, SettlementJournal};
-pub use margin::{MarginConfig, MarginEngine, MarginRequirement};
+pub use ledger::{GeneralLedger, LedgerAccount, LedgerEntry, LedgerEntryKind, LedgerMismatch};
+pub use margin::{
+    compute_requirements, net_positions, rate_to_fixed, Aggregation, CompositeMarginModel,
+    MarginConfig, MarginConfigError, MarginEngine, MarginModel, MarginModelError, MarginOffset,
+    MarginRequirement, NetPosition, RoundingMode, SymbolMarginParams, RATE_SCALE,
+};
+pub use margin_call::{
+    CallDirection, Escalation, MarginCall, MarginCallConfig, MarginCallError, MarginCallManager,
+    MarginCallStatus,
+};
 pub use netting::{multilateral_net, NetObligation, NettingEngine};
 pub use replay::{ReplayDiscrepancy, ReplayResult, ReplayStep, ReplayVerifier};
+pub use risk::{
+    BreachKind, MemberExposure, MemberLimits, RiskBreach, RiskChecker, RiskLimits, RiskReport,
+};
+pub use sharded::ShardedClearingHouse;
+pub use snapshot::{ClearingSnapshot, SnapshotError, SNAPSHOT_VERSION};
+pub use span::{
+    CommodityRisk, InterCommoditySpread, RiskArray, SpanCommodity, SpanEngine, SpanError,
+    SpanInstrument, SpanPosition, SpanProduct, SpanResult, SpanScenario, SCENARIO_COUNT,
+    SPAN_SCENARIOS,
+};
+pub use statement::{ObligationSide, Statement, StatementLine, StatementLineKind};
 pub use trade::{SettlementStatus, Trade};
+pub use var::{
+    positions_from_obligations, AntiProcyclicality, HistoricalVarModel, RiskMeasure, VarConfig,
+    VarError, VarPosition, VarResult,
+};
 pub use waterfall::{
     DefaultWaterfall, LayerAbsorption, WaterfallConfig, WaterfallLayer, WaterfallResult,
 };
@@ -2,8 +2,13 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Copyright (C) 2026 Moroya Sakamoto
 
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
 use crate::fnv1a;
 use crate::netting::NetObligation;
+use crate::span::SpanError;
+use crate::var::VarError;
 
 // ── Configuration ──────────────────────────────────────────────────────
 
@@ -19,6 +24,12 @@ pub struct MarginConfig {
     pub stress_scenarios: Vec<f64>,
     /// Absolute minimum margin floor.
     pub margin_floor: i64,
+    /// Per-symbol overrides keyed by `symbol_hash`.
+    pub symbols: HashMap<u64, SymbolMarginParams>,
+    /// Correlation offsets between hedged symbols, applied in order.
+    pub offsets: Vec<MarginOffset>,
+    /// Rounding of margin charges to whole units.
+    pub rounding: RoundingMode,
 }
 
 impl Default for MarginConfig {
@@ -28,14 +39,363 @@ impl Default for MarginConfig {
             variation_margin_rate: 1.0,
             stress_scenarios: vec![0.85, 0.90, 0.95, 1.05, 1.10, 1.15],
             margin_floor: 100,
+            symbols: HashMap::new(),
+            offsets: Vec::new(),
+            rounding: RoundingMode::Up,
         }
     }
 }
 
+impl MarginConfig {
+    /// Set the overrides for `symbol_hash`.
+    #[must_use]
+    pub fn with_symbol(mut self, symbol_hash: u64, params: SymbolMarginParams) -> Self {
+        self.symbols.insert(symbol_hash, params);
+        self
+    }
+
+    /// Append a correlation offset.
+    #[must_use]
+    pub fn with_offset(mut self, offset: MarginOffset) -> Self {
+        self.offsets.push(offset);
+        self
+    }
+
+    /// Check that every rate is a finite, non-negative number, every
+    /// stress multiplier is non-negative, floors are non-negative and
+    /// offset rates lie in 0.0–1.0.
+    ///
+    /// # Errors
+    ///
+    /// The first invalid value found; per-symbol overrides are checked in
+    /// ascending `symbol_hash` order.
+    pub fn validate(&self) -> Result<(), MarginConfigError> {
+        match FixedParams::from_config(self) {
+            (_, Some(err)) => Err(err),
+            (_, None) => Ok(()),
+        }
+    }
+
+    /// Initial margin rate applied to `symbol_hash`.
+    #[must_use]
+    pub fn initial_margin_rate_for(&self, symbol_hash: u64) -> f64 {
+        self.symbols
+            .get(&symbol_hash)
+            .and_then(|p| p.initial_margin_rate)
+            .unwrap_or(self.initial_margin_rate)
+    }
+
+    /// Stress scenarios applied to `symbol_hash`.
+    #[must_use]
+    pub fn stress_scenarios_for(&self, symbol_hash: u64) -> &[f64] {
+        self.symbols
+            .get(&symbol_hash)
+            .and_then(|p| p.stress_scenarios.as_deref())
+            .unwrap_or(&self.stress_scenarios)
+    }
+
+    /// Minimum initial margin for any exposure to `symbol_hash` (0 = none).
+    #[must_use]
+    pub fn margin_floor_for(&self, symbol_hash: u64) -> i64 {
+        self.symbols.get(&symbol_hash).map_or(0, |p| p.margin_floor)
+    }
+}
+
+/// Margin parameters for one symbol.  `None` falls back to the global
+/// [`MarginConfig`] value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SymbolMarginParams {
+    /// Initial margin rate as a fraction of notional.
+    pub initial_margin_rate: Option<f64>,
+    /// Stress scenario price-shock multipliers.
+    pub stress_scenarios: Option<Vec<f64>>,
+    /// Minimum initial margin whenever the account has exposure to the
+    /// symbol (0 = none).  The global floor still applies to the total.
+    pub margin_floor: i64,
+}
+
+/// Margin relief between two correlated symbols.
+///
+/// When an account's net exposures in `symbol_a` and `symbol_b` have
+/// opposite signs, `offset_rate` of the smaller leg's initial and stress
+/// margin is credited against each leg.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarginOffset {
+    /// First symbol.
+    pub symbol_a: u64,
+    /// Second symbol.
+    pub symbol_b: u64,
+    /// Fraction of the smaller leg credited (0.0–1.0).
+    pub offset_rate: f64,
+}
+
+/// Invalid [`MarginConfig`] value.  `symbol_hash` is `None` for the
+/// global parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarginConfigError {
+    /// Initial margin rate is NaN, infinite, negative or too large.
+    InvalidInitialMarginRate { symbol_hash: Option<u64> },
+    /// Variation margin rate is NaN, infinite, negative or too large.
+    InvalidVariationMarginRate,
+    /// Stress multiplier at `index` is NaN, infinite, negative or too large.
+    InvalidStressScenario {
+        symbol_hash: Option<u64>,
+        index: usize,
+    },
+    /// Margin floor is negative.
+    NegativeMarginFloor { symbol_hash: Option<u64> },
+    /// Offset at `index` has a rate outside 0.0–1.0.
+    InvalidOffsetRate { index: usize },
+}
+
+// ── Fixed Point ────────────────────────────────────────────────────────
+
+/// Fixed-point scale of margin rates: a rate of `1.0` is `RATE_SCALE`.
+pub const RATE_SCALE: i64 = 1_000_000_000;
+
+/// Rounding of a fixed-point product to whole units.  Directions refer to
+/// magnitude, so rounding is symmetric around zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RoundingMode {
+    /// Toward zero.
+    Down,
+    /// Away from zero.
+    #[default]
+    Up,
+    /// To nearest, ties away from zero.
+    HalfUp,
+    /// To nearest, ties to even.
+    HalfEven,
+}
+
+impl RoundingMode {
+    /// `amount × rate / RATE_SCALE` rounded with this mode, saturating at
+    /// the `i64` range.  Pure integer arithmetic, so the result is
+    /// identical on every platform.
+    #[must_use]
+    pub fn apply(self, amount: i64, rate: i64) -> i64 {
+        let product = i128::from(amount) * i128::from(rate);
+        let scale = i128::from(RATE_SCALE);
+        let quotient = product / scale;
+        let twice_rem = (product % scale).abs() * 2;
+        let away = match self {
+            Self::Down => false,
+            Self::Up => twice_rem != 0,
+            Self::HalfUp => twice_rem >= scale,
+            Self::HalfEven => twice_rem > scale || (twice_rem == scale && quotient % 2 != 0),
+        };
+        let rounded = if away {
+            quotient + product.signum()
+        } else {
+            quotient
+        };
+        i64::try_from(rounded).unwrap_or(if rounded < 0 { i64::MIN } else { i64::MAX })
+    }
+}
+
+/// Convert a fractional rate to fixed point (rounded to the nearest
+/// `1 / RATE_SCALE`).  Returns `None` for NaN, infinite, negative or
+/// unrepresentably large rates.
+#[must_use]
+pub fn rate_to_fixed(rate: f64) -> Option<i64> {
+    if !rate.is_finite() || rate < 0.0 {
+        return None;
+    }
+    let scaled = (rate * RATE_SCALE as f64).round();
+    (scaled < i64::MAX as f64).then_some(scaled as i64)
+}
+
+/// Fixed-point form of a [`MarginConfig`], resolved once per engine.
+#[derive(Debug, Clone)]
+struct FixedParams {
+    initial_rate: i64,
+    variation_rate: i64,
+    /// Shock magnitudes `|multiplier − 1|`.
+    shocks: Vec<i64>,
+    symbols: HashMap<u64, FixedSymbolParams>,
+    /// `(symbol_a, symbol_b, rate)`.
+    offsets: Vec<(u64, u64, i64)>,
+}
+
+#[derive(Debug, Clone)]
+struct FixedSymbolParams {
+    initial_rate: Option<i64>,
+    shocks: Option<Vec<i64>>,
+    floor: i64,
+}
+
+impl FixedParams {
+    /// Convert `config`, sanitising invalid values: NaN and negative rates
+    /// become zero, oversized rates saturate and offset rates are clamped
+    /// to 0.0–1.0.  The first invalid value is returned alongside.
+    fn from_config(config: &MarginConfig) -> (Self, Option<MarginConfigError>) {
+        let mut c = Converter { error: None };
+
+        let initial_rate = c.rate(
+            config.initial_margin_rate,
+            MarginConfigError::InvalidInitialMarginRate { symbol_hash: None },
+        );
+        let variation_rate = c.rate(
+            config.variation_margin_rate,
+            MarginConfigError::InvalidVariationMarginRate,
+        );
+        let shocks = c.shocks(&config.stress_scenarios, None);
+        c.floor(config.margin_floor, None);
+
+        let mut symbol_ids: Vec<u64> = config.symbols.keys().copied().collect();
+        symbol_ids.sort_unstable();
+        let mut symbols = HashMap::with_capacity(symbol_ids.len());
+        for symbol_hash in symbol_ids {
+            let p = &config.symbols[&symbol_hash];
+            let sym = Some(symbol_hash);
+            let fixed = FixedSymbolParams {
+                initial_rate: p.initial_margin_rate.map(|r| {
+                    c.rate(
+                        r,
+                        MarginConfigError::InvalidInitialMarginRate { symbol_hash: sym },
+                    )
+                }),
+                shocks: p.stress_scenarios.as_deref().map(|s| c.shocks(s, sym)),
+                floor: c.floor(p.margin_floor, sym),
+            };
+            symbols.insert(symbol_hash, fixed);
+        }
+
+        let offsets = config
+            .offsets
+            .iter()
+            .enumerate()
+            .map(|(index, off)| {
+                let err = MarginConfigError::InvalidOffsetRate { index };
+                let rate = c.rate(off.offset_rate, err.clone());
+                if rate > RATE_SCALE {
+                    c.reject(err);
+                }
+                (off.symbol_a, off.symbol_b, rate.min(RATE_SCALE))
+            })
+            .collect();
+
+        let params = Self {
+            initial_rate,
+            variation_rate,
+            shocks,
+            symbols,
+            offsets,
+        };
+        (params, c.error)
+    }
+}
+
+/// Converts configuration values, remembering the first invalid one.
+struct Converter {
+    error: Option<MarginConfigError>,
+}
+
+impl Converter {
+    fn reject(&mut self, err: MarginConfigError) {
+        self.error.get_or_insert(err);
+    }
+
+    fn rate(&mut self, value: f64, err: MarginConfigError) -> i64 {
+        rate_to_fixed(value).unwrap_or_else(|| {
+            self.reject(err);
+            if value > 0.0 {
+                i64::MAX
+            } else {
+                0
+            }
+        })
+    }
+
+    /// Shock magnitudes `|multiplier − 1|` of `scenarios`.
+    fn shocks(&mut self, scenarios: &[f64], symbol_hash: Option<u64>) -> Vec<i64> {
+        scenarios
+            .iter()
+            .enumerate()
+            .map(|(index, &m)| {
+                let err = MarginConfigError::InvalidStressScenario { symbol_hash, index };
+                let m = self.rate(m, err);
+                m.saturating_sub(RATE_SCALE).saturating_abs()
+            })
+            .collect()
+    }
+
+    fn floor(&mut self, value: i64, symbol_hash: Option<u64>) -> i64 {
+        if value < 0 {
+            self.reject(MarginConfigError::NegativeMarginFloor { symbol_hash });
+        }
+        value
+    }
+}
+
+// ── Net Position ───────────────────────────────────────────────────────
+
+/// Signed position of an account in one symbol, aggregated from obligations.
+///
+/// The receiver of an obligation is long and the deliverer short.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetPosition {
+    /// Symbol hash.
+    pub symbol_hash: u64,
+    /// Signed quantity (positive = long).
+    pub quantity: i64,
+    /// Signed contract value of the aggregated obligations (positive = long).
+    pub contract_value: i64,
+}
+
+impl NetPosition {
+    /// Signed market exposure at `mark_price`, or the contract value when
+    /// no mark is available.
+    #[inline]
+    #[must_use]
+    pub const fn exposure(&self, mark_price: Option<i64>) -> i64 {
+        match mark_price {
+            Some(mark) => self.quantity.saturating_mul(mark),
+            None => self.contract_value,
+        }
+    }
+}
+
+/// Aggregate `obligations` into the per-symbol positions of `account_id`,
+/// ordered by symbol.  Symbols that net to nothing are omitted.
+#[must_use]
+pub fn net_positions(account_id: u64, obligations: &[NetObligation]) -> Vec<NetPosition> {
+    let mut positions: BTreeMap<u64, NetPosition> = BTreeMap::new();
+    for ob in obligations {
+        let quantity = i64::try_from(ob.net_quantity).unwrap_or(i64::MAX);
+        let (quantity, value) = if ob.receiver_id == account_id {
+            (quantity, ob.net_payment)
+        } else if ob.deliverer_id == account_id {
+            (-quantity, ob.net_payment.saturating_neg())
+        } else {
+            continue;
+        };
+        let p = positions.entry(ob.symbol_hash).or_insert(NetPosition {
+            symbol_hash: ob.symbol_hash,
+            quantity: 0,
+            contract_value: 0,
+        });
+        p.quantity = p.quantity.saturating_add(quantity);
+        p.contract_value = p.contract_value.saturating_add(value);
+    }
+    positions
+        .into_values()
+        .filter(|p| p.quantity != 0 || p.contract_value != 0)
+        .collect()
+}
+
+/// Per-symbol margin components of a portfolio.
+#[derive(Debug, Clone, Copy)]
+struct SymbolLeg {
+    net_exposure: i64,
+    initial: i64,
+    stress: i64,
+}
+
 // ── Margin Requirement ─────────────────────────────────────────────────
 
 /// Computed margin requirement for an account.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MarginRequirement {
     /// Account for which margin was computed.
     pub account_id: u64,
@@ -57,32 +417,89 @@ pub struct MarginRequirement {
 ///
 /// Computes initial, variation, and stress margin requirements based on
 /// net obligations.  Stress margin evaluates worst-case exposure across
-/// configurable price-shock scenarios.
+/// configurable price-shock scenarios.  Positions are valued at the mark
+/// price of their symbol when one has been set.
+///
+/// Rates are converted to fixed point (see [`RATE_SCALE`]) when the engine
+/// is created and all margin arithmetic is integer, rounded with
+/// [`MarginConfig::rounding`], so requirements and their `content_hash`
+/// are bit-identical across platforms.
 pub struct MarginEngine {
     config: MarginConfig,
-    /// Pre-computed reciprocal: 1.0 / 1.0 (placeholder for future per-symbol
-    /// multipliers).  Avoids division in hot path.
-    _rcp_one: f64,
+    fixed: FixedParams,
+    marks: HashMap<u64, i64>,
 }
 
 impl MarginEngine {
     /// Create a new margin engine with the given configuration.
+    ///
+    /// Invalid values are sanitised rather than rejected: NaN and negative
+    /// rates are treated as zero.  Use [`try_new`](Self::try_new) to reject
+    /// them.
+    #[deprecated(note = "invalid rates are silently treated as zero; use `MarginEngine::try_new`")]
     #[must_use]
-    pub const fn new(config: MarginConfig) -> Self {
+    pub fn new(config: MarginConfig) -> Self {
+        let (fixed, _) = FixedParams::from_config(&config);
         Self {
             config,
-            _rcp_one: 1.0,
+            fixed,
+            marks: HashMap::new(),
+        }
+    }
+
+    /// Create a new margin engine, rejecting an invalid configuration.
+    ///
+    /// # Errors
+    ///
+    /// See [`MarginConfig::validate`].
+    pub fn try_new(config: MarginConfig) -> Result<Self, MarginConfigError> {
+        let (fixed, error) = FixedParams::from_config(&config);
+        if let Some(err) = error {
+            return Err(err);
         }
+        Ok(Self {
+            config,
+            fixed,
+            marks: HashMap::new(),
+        })
+    }
+
+    /// Set the mark price of `symbol_hash`.
+    pub fn set_mark_price(&mut self, symbol_hash: u64, mark_price: i64) {
+        self.marks.insert(symbol_hash, mark_price);
+    }
+
+    /// Mark price of `symbol_hash`, if set.
+    #[inline]
+    #[must_use]
+    pub fn mark_price(&self, symbol_hash: u64) -> Option<i64> {
+        self.marks.get(&symbol_hash).copied()
+    }
+
+    /// Remove all mark prices.
+    pub fn clear_mark_prices(&mut self) {
+        self.marks.clear();
     }
 
     /// Compute margin for a single obligation from the deliverer's perspective.
+    ///
+    /// The notional is `net_quantity` at the symbol's mark price, or
+    /// `net_payment` when no mark is set.
     #[must_use]
     pub fn compute_obligation_margin(&self, obligation: &NetObligation) -> MarginRequirement {
-        let notional = obligation.net_payment.unsigned_abs() as i64;
+        let symbol = obligation.symbol_hash;
+        let quantity = i64::try_from(obligation.net_quantity).unwrap_or(i64::MAX);
+        let notional = match self.mark_price(symbol) {
+            Some(mark) => quantity.saturating_mul(mark).saturating_abs(),
+            None => obligation.net_payment.saturating_abs(),
+        };
 
-        let initial = (notional as f64 * self.config.initial_margin_rate) as i64;
-        let variation = (notional as f64 * self.config.variation_margin_rate) as i64;
-        let stress = self.worst_case_stress(notional);
+        let initial = self.symbol_initial_margin(symbol, notional);
+        let variation = self
+            .config
+            .rounding
+            .apply(notional, self.fixed.variation_rate);
+        let stress = self.worst_case_stress(symbol, notional);
 
         let base = initial.saturating_add(variation);
         let total = base.max(stress).max(self.config.margin_floor);
@@ -99,31 +516,60 @@ impl MarginEngine {
 
     /// Compute portfolio margin for a single account across multiple obligations.
     ///
-    /// Obligations where the account is deliverer contribute short exposure;
-    /// obligations where the account is receiver contribute long exposure.
+    /// Obligations are aggregated into per-symbol [`NetPosition`]s (see
+    /// [`net_positions`]), so long and short obligations in the same symbol
+    /// offset, and margined with
+    /// [`compute_position_margin`](Self::compute_position_margin).
     #[must_use]
     pub fn compute_portfolio_margin(
         &self,
         account_id: u64,
         obligations: &[NetObligation],
     ) -> MarginRequirement {
-        let mut total_notional: i64 = 0;
+        self.compute_position_margin(account_id, &net_positions(account_id, obligations))
+    }
+
+    /// Compute margin for `account_id` holding `positions`.
+    ///
+    /// Each position's exposure is valued at its symbol's mark price (or
+    /// contract value without one).  Initial and stress margin are computed
+    /// per symbol with that symbol's parameters, reduced by any configured
+    /// [`MarginOffset`]s, and summed; variation margin applies to the net
+    /// exposure across symbols.
+    #[must_use]
+    pub fn compute_position_margin(
+        &self,
+        account_id: u64,
+        positions: &[NetPosition],
+    ) -> MarginRequirement {
         let mut net_exposure: i64 = 0;
+        let mut legs: BTreeMap<u64, SymbolLeg> = BTreeMap::new();
 
-        for ob in obligations {
-            if ob.deliverer_id == account_id {
-                total_notional = total_notional.saturating_add(ob.net_payment.abs());
-                net_exposure = net_exposure.saturating_sub(ob.net_payment);
-            } else if ob.receiver_id == account_id {
-                total_notional = total_notional.saturating_add(ob.net_payment.abs());
-                net_exposure = net_exposure.saturating_add(ob.net_payment);
-            }
+        for p in positions {
+            let exposure = p.exposure(self.mark_price(p.symbol_hash));
+            net_exposure = net_exposure.saturating_add(exposure);
+            let leg = legs.entry(p.symbol_hash).or_insert(SymbolLeg {
+                net_exposure: 0,
+                initial: 0,
+                stress: 0,
+            });
+            leg.net_exposure = leg.net_exposure.saturating_add(exposure);
+        }
+        for (&symbol, leg) in &mut legs {
+            let gross = leg.net_exposure.saturating_abs();
+            leg.initial = self.symbol_initial_margin(symbol, gross);
+            leg.stress = self.worst_case_stress(symbol, gross);
         }
+        self.apply_offsets(&mut legs);
 
-        let initial = (total_notional as f64 * self.config.initial_margin_rate) as i64;
-        let variation =
-            (net_exposure.unsigned_abs() as f64 * self.config.variation_margin_rate) as i64;
-        let stress = self.worst_case_stress(total_notional);
+        let initial = legs
+            .values()
+            .fold(0_i64, |t, l| t.saturating_add(l.initial));
+        let variation = self
+            .config
+            .rounding
+            .apply(net_exposure.saturating_abs(), self.fixed.variation_rate);
+        let stress = legs.values().fold(0_i64, |t, l| t.saturating_add(l.stress));
 
         let base = initial.saturating_add(variation);
         let total = base.max(stress).max(self.config.margin_floor);
@@ -138,12 +584,79 @@ impl MarginEngine {
         }
     }
 
-    /// Evaluate worst-case loss across all stress scenarios.
-    fn worst_case_stress(&self, notional: i64) -> i64 {
+    /// Initial margin on `notional` of `symbol`, floored at the symbol's
+    /// minimum when there is any exposure.
+    fn symbol_initial_margin(&self, symbol: u64, notional: i64) -> i64 {
+        let params = self.fixed.symbols.get(&symbol);
+        let rate = params
+            .and_then(|p| p.initial_rate)
+            .unwrap_or(self.fixed.initial_rate);
+        let initial = self.config.rounding.apply(notional, rate);
+        match params {
+            Some(p) if notional != 0 => initial.max(p.floor),
+            _ => initial,
+        }
+    }
+
+    /// Credit each hedged offset pair against both of its legs.  Credits
+    /// always round toward zero so rounding never reduces margin.
+    ///
+    /// Offsets are applied in configuration order and each consumes the
+    /// exposure it hedges, so a leg's exposure is credited at most once
+    /// across all of its offsets.
+    fn apply_offsets(&self, legs: &mut BTreeMap<u64, SymbolLeg>) {
+        let base = legs.clone();
+        let mut unhedged: BTreeMap<u64, i64> = base
+            .iter()
+            .map(|(&symbol, leg)| (symbol, leg.net_exposure.saturating_abs()))
+            .collect();
+        for &(symbol_a, symbol_b, rate) in &self.fixed.offsets {
+            if symbol_a == symbol_b {
+                continue;
+            }
+            let (Some(a), Some(b)) = (base.get(&symbol_a), base.get(&symbol_b)) else {
+                continue;
+            };
+            if a.net_exposure.signum() * b.net_exposure.signum() >= 0 {
+                continue;
+            }
+            let matched = unhedged[&symbol_a].min(unhedged[&symbol_b]);
+            if matched == 0 {
+                continue;
+            }
+            // Charges are linear in exposure, so the hedged share of each
+            // leg's charge is its charge scaled by the matched exposure.
+            let share = |leg: &SymbolLeg, charge: i64| {
+                (charge as i128 * matched as i128 / leg.net_exposure.unsigned_abs() as i128) as i64
+            };
+            let initial_credit =
+                RoundingMode::Down.apply(share(a, a.initial).min(share(b, b.initial)), rate);
+            let stress_credit =
+                RoundingMode::Down.apply(share(a, a.stress).min(share(b, b.stress)), rate);
+            for symbol in [symbol_a, symbol_b] {
+                if let Some(left) = unhedged.get_mut(&symbol) {
+                    *left -= matched;
+                }
+                if let Some(leg) = legs.get_mut(&symbol) {
+                    leg.initial -= initial_credit;
+                    leg.stress -= stress_credit;
+                }
+            }
+        }
+    }
+
+    /// Evaluate worst-case loss on `notional` of `symbol` across its
+    /// stress scenarios.
+    fn worst_case_stress(&self, symbol: u64, notional: i64) -> i64 {
+        let shocks = self
+            .fixed
+            .symbols
+            .get(&symbol)
+            .and_then(|p| p.shocks.as_deref())
+            .unwrap_or(&self.fixed.shocks);
         let mut worst: i64 = 0;
-        for &scenario in &self.config.stress_scenarios {
-            let shocked = (notional as f64 * scenario) as i64;
-            let loss = (shocked - notional).abs();
+        for &shock in shocks {
+            let loss = self.config.rounding.apply(notional, shock);
             // Branchless max
             let gt = (loss > worst) as i64;
             worst = gt * loss + (1 - gt) * worst;
@@ -151,7 +664,7 @@ impl MarginEngine {
         worst
     }
 
-    fn hash_requirement(account_id: u64, total: i64) -> u64 {
+    pub(crate) fn hash_requirement(account_id: u64, total: i64) -> u64 {
         let mut data = [0u8; 16];
         data[0..8].copy_from_slice(&account_id.to_le_bytes());
         data[8..16].copy_from_slice(&total.to_le_bytes());
@@ -166,6 +679,226 @@ impl MarginEngine {
     }
 }
 
+// ── Margin Model ───────────────────────────────────────────────────────
+
+/// Error returned by a [`MarginModel`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarginModelError {
+    /// Historical-simulation model error.
+    Var(VarError),
+    /// SPAN model error.
+    Span(SpanError),
+    /// Error from an in-house [`MarginModel`] implementation.
+    Model(String),
+}
+
+impl fmt::Display for MarginModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Var(e) => write!(f, "VaR model error: {e:?}"),
+            Self::Span(e) => write!(f, "SPAN model error: {e:?}"),
+            Self::Model(msg) => write!(f, "margin model error: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for MarginModelError {}
+
+impl From<VarError> for MarginModelError {
+    fn from(e: VarError) -> Self {
+        Self::Var(e)
+    }
+}
+
+impl From<SpanError> for MarginModelError {
+    fn from(e: SpanError) -> Self {
+        Self::Span(e)
+    }
+}
+
+/// A margin methodology.
+///
+/// Implemented by [`MarginEngine`],
+/// [`HistoricalVarModel`](crate::var::HistoricalVarModel) and
+/// [`SpanEngine`](crate::span::SpanEngine); in-house models implement it to
+/// run through the same pipeline.
+pub trait MarginModel {
+    /// Margin for `account_id` across `obligations`.
+    ///
+    /// # Errors
+    ///
+    /// [`MarginModelError`] if the model cannot price the portfolio.
+    fn portfolio_margin(
+        &self,
+        account_id: u64,
+        obligations: &[NetObligation],
+    ) -> Result<MarginRequirement, MarginModelError>;
+
+    /// Margin for a single obligation from the deliverer's perspective.
+    ///
+    /// # Errors
+    ///
+    /// [`MarginModelError`] if the model cannot price the obligation.
+    fn obligation_margin(
+        &self,
+        obligation: &NetObligation,
+    ) -> Result<MarginRequirement, MarginModelError> {
+        self.portfolio_margin(obligation.deliverer_id, std::slice::from_ref(obligation))
+    }
+}
+
+impl MarginModel for MarginEngine {
+    fn portfolio_margin(
+        &self,
+        account_id: u64,
+        obligations: &[NetObligation],
+    ) -> Result<MarginRequirement, MarginModelError> {
+        Ok(self.compute_portfolio_margin(account_id, obligations))
+    }
+
+    fn obligation_margin(
+        &self,
+        obligation: &NetObligation,
+    ) -> Result<MarginRequirement, MarginModelError> {
+        Ok(self.compute_obligation_margin(obligation))
+    }
+}
+
+/// Margin for every account in `account_ids` under `model`.
+///
+/// # Errors
+///
+/// The first [`MarginModelError`] returned by the model.
+pub fn compute_requirements(
+    model: &dyn MarginModel,
+    account_ids: &[u64],
+    obligations: &[NetObligation],
+) -> Result<Vec<MarginRequirement>, MarginModelError> {
+    account_ids
+        .iter()
+        .map(|&id| model.portfolio_margin(id, obligations))
+        .collect()
+}
+
+/// How [`CompositeMarginModel`] combines its models.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Aggregation {
+    /// Use the requirement with the largest total.
+    Max,
+    /// Add the requirements component by component.
+    Sum,
+}
+
+/// A margin model combining several models.
+pub struct CompositeMarginModel {
+    aggregation: Aggregation,
+    models: Vec<Box<dyn MarginModel + Send + Sync>>,
+}
+
+impl CompositeMarginModel {
+    /// Create an empty composite.  With no models every requirement is zero.
+    #[must_use]
+    pub fn new(aggregation: Aggregation) -> Self {
+        Self {
+            aggregation,
+            models: Vec::new(),
+        }
+    }
+
+    /// Add a model.
+    #[must_use]
+    pub fn with(mut self, model: Box<dyn MarginModel + Send + Sync>) -> Self {
+        self.models.push(model);
+        self
+    }
+
+    /// Add a model.
+    pub fn push(&mut self, model: Box<dyn MarginModel + Send + Sync>) {
+        self.models.push(model);
+    }
+
+    /// Number of models.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.models.len()
+    }
+
+    /// True when no model has been added.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
+    }
+
+    /// Aggregation rule.
+    #[inline]
+    #[must_use]
+    pub const fn aggregation(&self) -> Aggregation {
+        self.aggregation
+    }
+
+    fn combine(
+        &self,
+        account_id: u64,
+        requirements: impl Iterator<Item = Result<MarginRequirement, MarginModelError>>,
+    ) -> Result<MarginRequirement, MarginModelError> {
+        let mut out = MarginRequirement {
+            account_id,
+            initial_margin: 0,
+            variation_margin: 0,
+            stress_margin: 0,
+            total_margin: 0,
+            content_hash: 0,
+        };
+        for req in requirements {
+            let req = req?;
+            match self.aggregation {
+                Aggregation::Max => {
+                    if req.total_margin > out.total_margin {
+                        out = req;
+                    }
+                }
+                Aggregation::Sum => {
+                    out.initial_margin = out.initial_margin.saturating_add(req.initial_margin);
+                    out.variation_margin =
+                        out.variation_margin.saturating_add(req.variation_margin);
+                    out.stress_margin = out.stress_margin.saturating_add(req.stress_margin);
+                    out.total_margin = out.total_margin.saturating_add(req.total_margin);
+                }
+            }
+        }
+        out.account_id = account_id;
+        out.content_hash = MarginEngine::hash_requirement(account_id, out.total_margin);
+        Ok(out)
+    }
+}
+
+impl MarginModel for CompositeMarginModel {
+    fn portfolio_margin(
+        &self,
+        account_id: u64,
+        obligations: &[NetObligation],
+    ) -> Result<MarginRequirement, MarginModelError> {
+        self.combine(
+            account_id,
+            self.models
+                .iter()
+                .map(|m| m.portfolio_margin(account_id, obligations)),
+        )
+    }
+
+    fn obligation_margin(
+        &self,
+        obligation: &NetObligation,
+    ) -> Result<MarginRequirement, MarginModelError> {
+        self.combine(
+            obligation.deliverer_id,
+            self.models.iter().map(|m| m.obligation_margin(obligation)),
+        )
+    }
+}
+
 // ── Tests ──────────────────────────────────────────────────────────────
 
 #[cfg(test)]
@@ -173,7 +906,7 @@ mod tests {
     use super::*;
 
     fn default_engine() -> MarginEngine {
-        MarginEngine::new(MarginConfig::default())
+        MarginEngine::try_new(MarginConfig::default()).unwrap()
     }
 
     fn make_obligation(
@@ -225,8 +958,9 @@ mod tests {
             variation_margin_rate: 0.0,
             stress_scenarios: vec![1.0], // no shock
             margin_floor: 500,
+            ..MarginConfig::default()
         };
-        let engine = MarginEngine::new(config);
+        let engine = MarginEngine::try_new(config).unwrap();
         let ob = make_obligation(100, 200, 1, 10); // tiny obligation
         let req = engine.compute_obligation_margin(&ob);
 
@@ -241,8 +975,9 @@ mod tests {
             variation_margin_rate: 0.0,
             stress_scenarios: vec![0.70, 0.95, 1.05, 1.30], // ±30% is worst
             margin_floor: 0,
+            ..MarginConfig::default()
         };
-        let engine = MarginEngine::new(config);
+        let engine = MarginEngine::try_new(config).unwrap();
         let ob = make_obligation(1, 2, 10, 10_000);
         let req = engine.compute_obligation_margin(&ob);
 
@@ -292,9 +1027,8 @@ mod tests {
         ];
         let req = engine.compute_portfolio_margin(100, &obs);
 
-        // total_notional = 4000 + 3000 = 7000
-        // net_exposure = -4000 + 3000 = -1000
-        assert_eq!(req.initial_margin, 350); // 7000 * 0.05
+        // Same symbol: short 5 (−4000) and long 3 (+3000) net to −1000
+        assert_eq!(req.initial_margin, 50); // 1000 * 0.05
         assert_eq!(req.variation_margin, 1_000); // |−1000| * 1.0
     }
 
@@ -359,8 +1093,9 @@ mod tests {
             variation_margin_rate: 0.0,
             stress_scenarios: vec![],
             margin_floor: 42,
+            ..MarginConfig::default()
         };
-        let engine = MarginEngine::new(config);
+        let engine = MarginEngine::try_new(config).unwrap();
         let ob = make_obligation(1, 2, 10, 10_000);
         let req = engine.compute_obligation_margin(&ob);
         assert_eq!(req.stress_margin, 0);
@@ -397,8 +1132,9 @@ mod tests {
             variation_margin_rate: 0.0,
             stress_scenarios: vec![1.0, 1.0, 1.0],
             margin_floor: 0,
+            ..MarginConfig::default()
         };
-        let engine = MarginEngine::new(config);
+        let engine = MarginEngine::try_new(config).unwrap();
         let ob = make_obligation(1, 2, 10, 5_000);
         let req = engine.compute_obligation_margin(&ob);
         assert_eq!(req.stress_margin, 0);
@@ -432,4 +1168,547 @@ mod tests {
         // Should not panic; total_margin must be non-negative.
         assert!(req.total_margin >= 0);
     }
+
+    // ── Per-Symbol Parameters ──────────────────────────────────────
+
+    const SYM_A: u64 = 0xA;
+    const SYM_B: u64 = 0xB;
+
+    fn symbol_obligation(
+        symbol_hash: u64,
+        deliverer_id: u64,
+        receiver_id: u64,
+        net_payment: i64,
+    ) -> NetObligation {
+        NetObligation {
+            symbol_hash,
+            ..make_obligation(deliverer_id, receiver_id, 1, net_payment)
+        }
+    }
+
+    fn initial_only() -> MarginConfig {
+        MarginConfig {
+            initial_margin_rate: 0.05,
+            variation_margin_rate: 0.0,
+            stress_scenarios: vec![],
+            margin_floor: 0,
+            ..MarginConfig::default()
+        }
+    }
+
+    #[test]
+    fn symbol_overrides_rate_and_scenarios() {
+        let config = MarginConfig::default().with_symbol(
+            SYM_A,
+            SymbolMarginParams {
+                initial_margin_rate: Some(0.10),
+                stress_scenarios: Some(vec![0.5]),
+                margin_floor: 0,
+            },
+        );
+        assert!((config.initial_margin_rate_for(SYM_B) - 0.05).abs() < 1e-12);
+        let engine = MarginEngine::try_new(config).unwrap();
+
+        let req = engine.compute_obligation_margin(&symbol_obligation(SYM_A, 1, 2, 10_000));
+        assert_eq!(req.initial_margin, 1_000);
+        assert_eq!(req.stress_margin, 5_000);
+        assert_eq!(req.total_margin, 11_000);
+
+        // Unconfigured symbols keep the global parameters.
+        let req = engine.compute_obligation_margin(&symbol_obligation(SYM_B, 1, 2, 10_000));
+        assert_eq!(req.initial_margin, 500);
+        assert_eq!(req.stress_margin, 1_500);
+    }
+
+    #[test]
+    fn symbol_floor_per_leg() {
+        let config = initial_only().with_symbol(
+            SYM_B,
+            SymbolMarginParams {
+                margin_floor: 800,
+                ..SymbolMarginParams::default()
+            },
+        );
+        let engine = MarginEngine::try_new(config).unwrap();
+        let obs = [
+            symbol_obligation(SYM_A, 1, 2, 1_000),
+            symbol_obligation(SYM_B, 1, 2, 1_000),
+        ];
+        let req = engine.compute_portfolio_margin(1, &obs);
+        assert_eq!(req.initial_margin, 50 + 800);
+        // No exposure to SYM_B, no floor.
+        assert_eq!(
+            engine.compute_portfolio_margin(1, &obs[..1]).initial_margin,
+            50
+        );
+    }
+
+    #[test]
+    fn offset_credits_hedged_legs() {
+        let offset = MarginOffset {
+            symbol_a: SYM_A,
+            symbol_b: SYM_B,
+            offset_rate: 0.8,
+        };
+        let hedged = [
+            symbol_obligation(SYM_A, 9, 1, 10_000),
+            symbol_obligation(SYM_B, 1, 9, 10_000),
+        ];
+        let without = MarginEngine::try_new(initial_only())
+            .unwrap()
+            .compute_portfolio_margin(1, &hedged);
+        assert_eq!(without.initial_margin, 1_000);
+
+        let engine = MarginEngine::try_new(initial_only().with_offset(offset)).unwrap();
+        let with = engine.compute_portfolio_margin(1, &hedged);
+        assert_eq!(with.initial_margin, 200);
+
+        // Same-direction exposures get no relief.
+        let unhedged = [
+            symbol_obligation(SYM_A, 9, 1, 10_000),
+            symbol_obligation(SYM_B, 9, 1, 10_000),
+        ];
+        assert_eq!(
+            engine.compute_portfolio_margin(1, &unhedged).initial_margin,
+            1_000
+        );
+    }
+
+    #[test]
+    fn offsets_consume_hedged_exposure() {
+        const SYM_C: u64 = 0xC;
+        let offset = |symbol_b| MarginOffset {
+            symbol_a: SYM_A,
+            symbol_b,
+            offset_rate: 0.8,
+        };
+        let obs = [
+            symbol_obligation(SYM_A, 9, 1, 10_000),
+            symbol_obligation(SYM_B, 1, 9, 10_000),
+            symbol_obligation(SYM_C, 1, 9, 10_000),
+        ];
+        let margin = |config: MarginConfig| {
+            MarginEngine::try_new(config)
+                .unwrap()
+                .compute_portfolio_margin(1, &obs)
+                .initial_margin
+        };
+        assert_eq!(margin(initial_only()), 1_500);
+        let one = initial_only().with_offset(offset(SYM_B));
+        assert_eq!(margin(one.clone()), 700);
+        // SYM_A is fully hedged by SYM_B; the second offset has nothing left.
+        assert_eq!(margin(one.with_offset(offset(SYM_C))), 700);
+
+        // A larger leg can hedge several smaller ones.
+        let obs = [
+            symbol_obligation(SYM_A, 9, 1, 20_000),
+            symbol_obligation(SYM_B, 1, 9, 10_000),
+            symbol_obligation(SYM_C, 1, 9, 10_000),
+        ];
+        let both = initial_only()
+            .with_offset(offset(SYM_B))
+            .with_offset(offset(SYM_C));
+        let req = MarginEngine::try_new(both)
+            .unwrap()
+            .compute_portfolio_margin(1, &obs);
+        // 1000 + 500 + 500, credit 400 per leg for each offset.
+        assert_eq!(req.initial_margin, 400);
+    }
+
+    #[test]
+    fn offset_limited_by_smaller_leg() {
+        let config = MarginConfig {
+            stress_scenarios: vec![0.9],
+            ..initial_only()
+        }
+        .with_offset(MarginOffset {
+            symbol_a: SYM_A,
+            symbol_b: SYM_B,
+            offset_rate: 1.0,
+        });
+        let engine = MarginEngine::try_new(config).unwrap();
+        let obs = [
+            symbol_obligation(SYM_A, 9, 1, 30_000),
+            symbol_obligation(SYM_B, 1, 9, 10_000),
+        ];
+        let req = engine.compute_portfolio_margin(1, &obs);
+        // Initial 1500 + 500, credit 500 per leg; stress 3000 + 1000, credit 1000 per leg.
+        assert_eq!(req.initial_margin, 1_000);
+        assert_eq!(req.stress_margin, 2_000);
+    }
+
+    // ── Fixed Point ────────────────────────────────────────────────
+
+    #[test]
+    fn rounding_modes() {
+        let half = RATE_SCALE / 2;
+        let cases = [
+            (RoundingMode::Down, [2, 2, 3, -2]),
+            (RoundingMode::Up, [3, 3, 4, -3]),
+            (RoundingMode::HalfUp, [2, 3, 4, -3]),
+            (RoundingMode::HalfEven, [2, 2, 4, -2]),
+        ];
+        for (mode, [a, b, c, d]) in cases {
+            // 2.4, 2.5, 3.5, −2.5
+            assert_eq!(mode.apply(24, RATE_SCALE / 10), a, "{mode:?}");
+            assert_eq!(mode.apply(5, half), b, "{mode:?}");
+            assert_eq!(mode.apply(7, half), c, "{mode:?}");
+            assert_eq!(mode.apply(-5, half), d, "{mode:?}");
+        }
+        assert_eq!(RoundingMode::Up.apply(i64::MAX, 2 * RATE_SCALE), i64::MAX);
+        assert_eq!(RoundingMode::Up.apply(i64::MIN, 2 * RATE_SCALE), i64::MIN);
+    }
+
+    #[test]
+    fn rate_conversion() {
+        assert_eq!(rate_to_fixed(0.05), Some(50_000_000));
+        assert_eq!(rate_to_fixed(0.29), Some(290_000_000));
+        assert_eq!(rate_to_fixed(0.0), Some(0));
+        assert_eq!(rate_to_fixed(-0.01), None);
+        assert_eq!(rate_to_fixed(f64::NAN), None);
+        assert_eq!(rate_to_fixed(f64::INFINITY), None);
+        assert_eq!(rate_to_fixed(1e10), None);
+    }
+
+    #[test]
+    fn fixed_point_avoids_float_truncation() {
+        // 100.0 * 0.29 == 28.999999999999996 in f64.
+        let config = MarginConfig {
+            initial_margin_rate: 0.29,
+            rounding: RoundingMode::Down,
+            ..initial_only()
+        };
+        let req = MarginEngine::try_new(config)
+            .unwrap()
+            .compute_obligation_margin(&make_obligation(1, 2, 1, 100));
+        assert_eq!(req.initial_margin, 29);
+    }
+
+    #[test]
+    fn rounding_mode_applies_to_charges() {
+        let ob = make_obligation(1, 2, 1, 7);
+        let engine = |rounding| {
+            MarginEngine::try_new(MarginConfig {
+                stress_scenarios: vec![0.85],
+                rounding,
+                ..initial_only()
+            })
+            .unwrap()
+        };
+        // Initial 7 × 5% = 0.35, stress 7 × 15% = 1.05.
+        let up = engine(RoundingMode::Up).compute_obligation_margin(&ob);
+        assert_eq!((up.initial_margin, up.stress_margin), (1, 2));
+        let down = engine(RoundingMode::Down).compute_obligation_margin(&ob);
+        assert_eq!((down.initial_margin, down.stress_margin), (0, 1));
+        let half = engine(RoundingMode::HalfEven).compute_obligation_margin(&ob);
+        assert_eq!((half.initial_margin, half.stress_margin), (0, 1));
+    }
+
+    #[test]
+    fn validate_rejects_invalid_values() {
+        assert_eq!(MarginConfig::default().validate(), Ok(()));
+        let invalid = |config: MarginConfig| config.validate().unwrap_err();
+        assert_eq!(
+            invalid(MarginConfig {
+                initial_margin_rate: f64::NAN,
+                ..MarginConfig::default()
+            }),
+            MarginConfigError::InvalidInitialMarginRate { symbol_hash: None }
+        );
+        assert_eq!(
+            invalid(MarginConfig {
+                variation_margin_rate: -1.0,
+                ..MarginConfig::default()
+            }),
+            MarginConfigError::InvalidVariationMarginRate
+        );
+        assert_eq!(
+            invalid(MarginConfig {
+                stress_scenarios: vec![0.9, f64::NEG_INFINITY],
+                ..MarginConfig::default()
+            }),
+            MarginConfigError::InvalidStressScenario {
+                symbol_hash: None,
+                index: 1
+            }
+        );
+        assert_eq!(
+            invalid(MarginConfig {
+                margin_floor: -1,
+                ..MarginConfig::default()
+            }),
+            MarginConfigError::NegativeMarginFloor { symbol_hash: None }
+        );
+        let bad_symbol = |params| MarginConfig::default().with_symbol(SYM_B, params);
+        assert_eq!(
+            invalid(bad_symbol(SymbolMarginParams {
+                initial_margin_rate: Some(-0.1),
+                ..SymbolMarginParams::default()
+            })),
+            MarginConfigError::InvalidInitialMarginRate {
+                symbol_hash: Some(SYM_B)
+            }
+        );
+        assert_eq!(
+            invalid(bad_symbol(SymbolMarginParams {
+                margin_floor: -5,
+                ..SymbolMarginParams::default()
+            })),
+            MarginConfigError::NegativeMarginFloor {
+                symbol_hash: Some(SYM_B)
+            }
+        );
+        assert_eq!(
+            invalid(MarginConfig::default().with_offset(MarginOffset {
+                symbol_a: SYM_A,
+                symbol_b: SYM_B,
+                offset_rate: 1.5,
+            })),
+            MarginConfigError::InvalidOffsetRate { index: 0 }
+        );
+    }
+
+    #[test]
+    fn try_new_and_sanitising_new() {
+        let config = MarginConfig {
+            initial_margin_rate: f64::NAN,
+            ..initial_only()
+        };
+        assert!(matches!(
+            MarginEngine::try_new(config.clone()),
+            Err(MarginConfigError::InvalidInitialMarginRate { .. })
+        ));
+        // `new` treats the NaN rate as zero.
+        #[allow(deprecated)]
+        let req =
+            MarginEngine::new(config).compute_obligation_margin(&make_obligation(1, 2, 1, 10_000));
+        assert_eq!(req.initial_margin, 0);
+        assert!(MarginEngine::try_new(MarginConfig::default()).is_ok());
+    }
+
+    // ── Net Positions ──────────────────────────────────────────────
+
+    #[test]
+    fn net_positions_aggregate_per_symbol() {
+        let obs = [
+            symbol_obligation(SYM_B, 1, 2, 500),
+            NetObligation {
+                net_quantity: 10,
+                ..symbol_obligation(SYM_A, 2, 1, 1_000)
+            },
+            NetObligation {
+                net_quantity: 4,
+                ..symbol_obligation(SYM_A, 1, 3, 420)
+            },
+            symbol_obligation(0xC, 2, 3, 999),
+            symbol_obligation(0xD, 1, 2, 0),
+            symbol_obligation(0xD, 2, 1, 0),
+        ];
+        assert_eq!(
+            net_positions(1, &obs),
+            vec![
+                NetPosition {
+                    symbol_hash: SYM_A,
+                    quantity: 6,
+                    contract_value: 580,
+                },
+                NetPosition {
+                    symbol_hash: SYM_B,
+                    quantity: -1,
+                    contract_value: -500,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn offsetting_positions_in_same_symbol_net_out() {
+        let engine = default_engine();
+        let obs = [
+            make_obligation(100, 200, 10, 5_000),
+            make_obligation(300, 100, 10, 5_000),
+        ];
+        let req = engine.compute_portfolio_margin(100, &obs);
+        assert_eq!(req.initial_margin, 0);
+        assert_eq!(req.variation_margin, 0);
+        assert_eq!(req.stress_margin, 0);
+        assert_eq!(req.total_margin, 100);
+    }
+
+    #[test]
+    fn different_symbols_margined_separately() {
+        let engine = MarginEngine::try_new(initial_only()).unwrap();
+        let obs = [
+            symbol_obligation(SYM_A, 9, 1, 10_000),
+            symbol_obligation(SYM_B, 1, 9, 4_000),
+        ];
+        let req = engine.compute_portfolio_margin(1, &obs);
+        // 500 on the long A leg + 200 on the short B leg, no netting.
+        assert_eq!(req.initial_margin, 700);
+    }
+
+    #[test]
+    fn mark_prices_value_positions() {
+        let mut engine = MarginEngine::try_new(MarginConfig {
+            stress_scenarios: vec![0.9],
+            variation_margin_rate: 1.0,
+            ..initial_only()
+        })
+        .unwrap();
+        let obs = [
+            NetObligation {
+                net_quantity: 10,
+                ..symbol_obligation(SYM_A, 9, 1, 1_000)
+            },
+            NetObligation {
+                net_quantity: 5,
+                ..symbol_obligation(SYM_B, 1, 9, 1_000)
+            },
+        ];
+        // Without marks: contract values +1000 and −1000.
+        let req = engine.compute_portfolio_margin(1, &obs);
+        assert_eq!(req.initial_margin, 100);
+        assert_eq!(req.variation_margin, 0);
+
+        engine.set_mark_price(SYM_A, 150);
+        engine.set_mark_price(SYM_B, 100);
+        assert_eq!(engine.mark_price(SYM_A), Some(150));
+        // Marked: +1500 and −500.
+        let req = engine.compute_portfolio_margin(1, &obs);
+        assert_eq!(req.initial_margin, 75 + 25);
+        assert_eq!(req.stress_margin, 150 + 50);
+        assert_eq!(req.variation_margin, 1_000);
+
+        let single = engine.compute_obligation_margin(&obs[1]);
+        assert_eq!(single.initial_margin, 25);
+
+        engine.clear_mark_prices();
+        assert_eq!(engine.mark_price(SYM_A), None);
+    }
+
+    // ── Margin Model ───────────────────────────────────────────────
+
+    /// Fixed-total model standing in for an in-house methodology.
+    struct Flat(i64);
+
+    impl MarginModel for Flat {
+        fn portfolio_margin(
+            &self,
+            account_id: u64,
+            _obligations: &[NetObligation],
+        ) -> Result<MarginRequirement, MarginModelError> {
+            Ok(MarginRequirement {
+                account_id,
+                initial_margin: self.0,
+                variation_margin: 0,
+                stress_margin: 0,
+                total_margin: self.0,
+                content_hash: 0,
+            })
+        }
+    }
+
+    struct Failing;
+
+    impl MarginModel for Failing {
+        fn portfolio_margin(
+            &self,
+            _account_id: u64,
+            _obligations: &[NetObligation],
+        ) -> Result<MarginRequirement, MarginModelError> {
+            Err(MarginModelError::Model("no price data".to_string()))
+        }
+    }
+
+    #[test]
+    fn engine_implements_model() {
+        let engine = default_engine();
+        let ob = make_obligation(100, 200, 10, 5_000);
+        let model: &dyn MarginModel = &engine;
+        let via_trait = model.obligation_margin(&ob).unwrap();
+        assert_eq!(
+            via_trait.total_margin,
+            engine.compute_obligation_margin(&ob).total_margin
+        );
+        let portfolio = model
+            .portfolio_margin(200, std::slice::from_ref(&ob))
+            .unwrap();
+        assert_eq!(
+            portfolio.content_hash,
+            engine.compute_portfolio_margin(200, &[ob]).content_hash
+        );
+    }
+
+    #[test]
+    fn default_obligation_margin_uses_deliverer() {
+        let ob = make_obligation(100, 200, 10, 5_000);
+        let req = Flat(7).obligation_margin(&ob).unwrap();
+        assert_eq!(req.account_id, 100);
+        assert_eq!(req.total_margin, 7);
+    }
+
+    #[test]
+    fn composite_max_and_sum() {
+        let ob = make_obligation(100, 200, 10, 5_000);
+        let max = CompositeMarginModel::new(Aggregation::Max)
+            .with(Box::new(Flat(300)))
+            .with(Box::new(default_engine()))
+            .with(Box::new(Flat(1_000)));
+        let req = max
+            .portfolio_margin(100, std::slice::from_ref(&ob))
+            .unwrap();
+        assert_eq!(req.total_margin, 5_250);
+        assert_eq!(req.initial_margin, 250);
+        assert_eq!(req.content_hash, MarginEngine::hash_requirement(100, 5_250));
+
+        let sum = CompositeMarginModel::new(Aggregation::Sum)
+            .with(Box::new(Flat(300)))
+            .with(Box::new(default_engine()));
+        let req = sum.obligation_margin(&ob).unwrap();
+        assert_eq!(req.account_id, 100);
+        assert_eq!(req.total_margin, 5_550);
+        assert_eq!(req.initial_margin, 550);
+        assert_eq!(req.variation_margin, 5_000);
+        assert_eq!(sum.len(), 2);
+    }
+
+    #[test]
+    fn composite_empty_and_errors() {
+        let empty = CompositeMarginModel::new(Aggregation::Max);
+        assert!(empty.is_empty());
+        assert_eq!(empty.portfolio_margin(1, &[]).unwrap().total_margin, 0);
+
+        let mut failing = CompositeMarginModel::new(Aggregation::Sum);
+        failing.push(Box::new(Flat(1)));
+        failing.push(Box::new(Failing));
+        assert_eq!(
+            failing.portfolio_margin(1, &[]),
+            Err(MarginModelError::Model("no price data".to_string()))
+        );
+    }
+
+    #[test]
+    fn model_error_display() {
+        assert_eq!(
+            MarginModelError::Model("no price data".to_string()).to_string(),
+            "margin model error: no price data"
+        );
+        assert_eq!(
+            MarginModelError::from(VarError::InvalidConfidence).to_string(),
+            "VaR model error: InvalidConfidence"
+        );
+        assert_eq!(
+            MarginModelError::from(SpanError::UnknownInstrument(7)).to_string(),
+            "SPAN model error: UnknownInstrument(7)"
+        );
+    }
+
+    #[test]
+    fn requirements_for_accounts() {
+        let ob = make_obligation(100, 200, 10, 5_000);
+        let reqs = compute_requirements(&default_engine(), &[100, 200, 300], &[ob]).unwrap();
+        let ids: Vec<u64> = reqs.iter().map(|r| r.account_id).collect();
+        assert_eq!(ids, vec![100, 200, 300]);
+        assert_eq!(reqs[2].total_margin, 100);
+    }
 }
@@ -0,0 +1,631 @@
+// ALICE-Settlement — Margin call generation and lifecycle
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Moroya Sakamoto
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::clearing::{ClearingError, ClearingHouse};
+use crate::collateral::{CollateralAccount, HaircutConfig};
+use crate::margin::{compute_requirements, MarginModel, MarginModelError, MarginRequirement};
+use crate::netting::NetObligation;
+
+// ── Types ──────────────────────────────────────────────────────────────
+
+/// Which way collateral must move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CallDirection {
+    /// The member must deliver collateral to cover a shortfall.
+    Call,
+    /// The clearing house must return excess collateral to the member.
+    Return,
+}
+
+/// Lifecycle state of a margin call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MarginCallStatus {
+    /// Issued and not yet acknowledged.
+    Issued,
+    /// Acknowledged by the paying side.
+    Acknowledged,
+    /// Part of the amount has been delivered.
+    PartiallySatisfied,
+    /// The full amount has been delivered.
+    Satisfied,
+    /// The deadline passed with the call unmet; handed to default handling.
+    Escalated,
+    /// Withdrawn before completion.
+    Cancelled,
+}
+
+impl MarginCallStatus {
+    /// True once no further transition is possible.
+    #[inline]
+    #[must_use]
+    pub const fn is_terminal(self) -> bool {
+        matches!(self, Self::Satisfied | Self::Escalated | Self::Cancelled)
+    }
+}
+
+/// A margin call or excess-collateral return.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarginCall {
+    /// Call identifier, unique within a [`MarginCallManager`].
+    pub call_id: u64,
+    /// Member account.
+    pub account_id: u64,
+    /// Whether collateral is due in or out.
+    pub direction: CallDirection,
+    /// Amount called, in post-haircut value.
+    pub amount: i64,
+    /// Amount delivered so far.
+    pub delivered: i64,
+    /// Margin requirement when issued.
+    pub requirement: i64,
+    /// Post-haircut collateral value when issued.
+    pub collateral_value: i64,
+    /// Issue time (nanoseconds).
+    pub issued_ns: u64,
+    /// Time by which the call must be met (nanoseconds).
+    pub deadline_ns: u64,
+    /// Acknowledgement time, if acknowledged.
+    pub acknowledged_ns: Option<u64>,
+    /// Time the call reached a terminal state.
+    pub closed_ns: Option<u64>,
+    /// Current state.
+    pub status: MarginCallStatus,
+}
+
+impl MarginCall {
+    /// Amount still to be delivered.
+    #[inline]
+    #[must_use]
+    pub const fn outstanding(&self) -> i64 {
+        self.amount - self.delivered
+    }
+
+    /// True while the call may still change state.
+    #[inline]
+    #[must_use]
+    pub const fn is_open(&self) -> bool {
+        !self.status.is_terminal()
+    }
+}
+
+/// Margin call thresholds and deadlines.
+#[derive(Debug, Clone)]
+pub struct MarginCallConfig {
+    /// Time allowed to meet a call (nanoseconds).
+    pub call_deadline_ns: u64,
+    /// Time allowed to return excess collateral (nanoseconds).
+    pub return_deadline_ns: u64,
+    /// Smallest shortfall or excess that triggers a call.
+    pub minimum_transfer: i64,
+}
+
+impl Default for MarginCallConfig {
+    fn default() -> Self {
+        Self {
+            call_deadline_ns: 3_600_000_000_000,    // 1 hour
+            return_deadline_ns: 86_400_000_000_000, // 1 day
+            minimum_transfer: 1,
+        }
+    }
+}
+
+/// Error returned by [`MarginCallManager`] operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarginCallError {
+    /// No call with this ID exists.
+    UnknownCall(u64),
+    /// Delivered amounts must be positive.
+    InvalidAmount,
+    /// The call's current state does not allow the operation.
+    InvalidTransition {
+        call_id: u64,
+        status: MarginCallStatus,
+    },
+}
+
+/// An overdue call handed to default handling.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Escalation {
+    /// Escalated call.
+    pub call_id: u64,
+    /// Member account.
+    pub account_id: u64,
+    /// Amount left unmet.
+    pub outstanding: i64,
+    /// Outcome of declaring the member in default.
+    pub default_result: Result<(), ClearingError>,
+}
+
+// ── Manager ────────────────────────────────────────────────────────────
+
+/// Issues margin calls and tracks them through their lifecycle.
+///
+/// Each account has at most one open call at a time; re-evaluating an
+/// account with an open call does nothing until that call is closed.
+#[derive(Debug, Clone, Default)]
+pub struct MarginCallManager {
+    config: MarginCallConfig,
+    calls: BTreeMap<u64, MarginCall>,
+    next_call_id: u64,
+}
+
+impl MarginCallManager {
+    /// Create a manager.
+    #[must_use]
+    pub const fn new(config: MarginCallConfig) -> Self {
+        Self {
+            config,
+            calls: BTreeMap::new(),
+            next_call_id: 1,
+        }
+    }
+
+    /// Active configuration.
+    #[inline]
+    #[must_use]
+    pub const fn config(&self) -> &MarginCallConfig {
+        &self.config
+    }
+
+    /// Compare `requirement.total_margin` with the post-haircut value of
+    /// `collateral` and issue a call or return if the difference reaches
+    /// the minimum transfer.
+    ///
+    /// Returns the ID of the new call, or `None` if no call was issued.
+    pub fn evaluate(
+        &mut self,
+        requirement: &MarginRequirement,
+        collateral: &CollateralAccount,
+        now_ns: u64,
+    ) -> Option<u64> {
+        let account_id = requirement.account_id;
+        if self.open_call(account_id).is_some() {
+            return None;
+        }
+        let collateral_value = collateral.total_adjusted_value();
+        let diff = requirement.total_margin.saturating_sub(collateral_value);
+        let (direction, amount, deadline) = if diff > 0 {
+            (CallDirection::Call, diff, self.config.call_deadline_ns)
+        } else {
+            (
+                CallDirection::Return,
+                diff.saturating_neg(),
+                self.config.return_deadline_ns,
+            )
+        };
+        if amount == 0 || amount < self.config.minimum_transfer {
+            return None;
+        }
+
+        let call_id = self.next_call_id.max(1);
+        self.next_call_id = call_id + 1;
+        self.calls.insert(
+            call_id,
+            MarginCall {
+                call_id,
+                account_id,
+                direction,
+                amount,
+                delivered: 0,
+                requirement: requirement.total_margin,
+                collateral_value,
+                issued_ns: now_ns,
+                deadline_ns: now_ns.saturating_add(deadline),
+                acknowledged_ns: None,
+                closed_ns: None,
+                status: MarginCallStatus::Issued,
+            },
+        );
+        Some(call_id)
+    }
+
+    /// Evaluate every requirement against its collateral account.
+    ///
+    /// Requirements without a matching account are evaluated against no
+    /// collateral.  Returns the IDs of calls issued, in requirement order.
+    pub fn evaluate_all(
+        &mut self,
+        requirements: &[MarginRequirement],
+        collateral: &[CollateralAccount],
+        now_ns: u64,
+    ) -> Vec<u64> {
+        requirements
+            .iter()
+            .filter_map(
+                |req| match collateral.iter().find(|c| c.account_id() == req.account_id) {
+                    Some(acc) => self.evaluate(req, acc, now_ns),
+                    None => {
+                        let empty =
+                            CollateralAccount::new(req.account_id, HaircutConfig::default());
+                        self.evaluate(req, &empty, now_ns)
+                    }
+                },
+            )
+            .collect()
+    }
+
+    /// Compute requirements with `model` for every account holding
+    /// collateral or party to an obligation, then
+    /// [`evaluate_all`](Self::evaluate_all) them.
+    ///
+    /// # Errors
+    ///
+    /// The first [`MarginModelError`] returned by the model; no call is
+    /// issued in that case.
+    pub fn evaluate_model(
+        &mut self,
+        model: &dyn MarginModel,
+        obligations: &[NetObligation],
+        collateral: &[CollateralAccount],
+        now_ns: u64,
+    ) -> Result<Vec<u64>, MarginModelError> {
+        let accounts: Vec<u64> = collateral
+            .iter()
+            .map(CollateralAccount::account_id)
+            .chain(
+                obligations
+                    .iter()
+                    .flat_map(|ob| [ob.deliverer_id, ob.receiver_id]),
+            )
+            .collect::<BTreeSet<u64>>()
+            .into_iter()
+            .collect();
+        let requirements = compute_requirements(model, &accounts, obligations)?;
+        Ok(self.evaluate_all(&requirements, collateral, now_ns))
+    }
+
+    /// Record acknowledgement of an issued call.
+    ///
+    /// # Errors
+    ///
+    /// [`MarginCallError::UnknownCall`] or, unless the call is `Issued`,
+    /// [`MarginCallError::InvalidTransition`].
+    pub fn acknowledge(&mut self, call_id: u64, now_ns: u64) -> Result<(), MarginCallError> {
+        let call = self.call_mut(call_id)?;
+        if call.status != MarginCallStatus::Issued {
+            return Err(MarginCallError::InvalidTransition {
+                call_id,
+                status: call.status,
+            });
+        }
+        call.status = MarginCallStatus::Acknowledged;
+        call.acknowledged_ns = Some(now_ns);
+        Ok(())
+    }
+
+    /// Record delivery of `amount` towards a call.  Deliveries beyond the
+    /// outstanding amount are accepted and satisfy the call.
+    ///
+    /// # Errors
+    ///
+    /// [`MarginCallError::InvalidAmount`] for non-positive amounts,
+    /// [`MarginCallError::UnknownCall`], or
+    /// [`MarginCallError::InvalidTransition`] if the call is closed.
+    pub fn record_delivery(
+        &mut self,
+        call_id: u64,
+        amount: i64,
+        now_ns: u64,
+    ) -> Result<MarginCallStatus, MarginCallError> {
+        if amount <= 0 {
+            return Err(MarginCallError::InvalidAmount);
+        }
+        let call = self.call_mut(call_id)?;
+        if !call.is_open() {
+            return Err(MarginCallError::InvalidTransition {
+                call_id,
+                status: call.status,
+            });
+        }
+        call.delivered = call.delivered.saturating_add(amount);
+        if call.outstanding() <= 0 {
+            call.status = MarginCallStatus::Satisfied;
+            call.closed_ns = Some(now_ns);
+        } else {
+            call.status = MarginCallStatus::PartiallySatisfied;
+        }
+        Ok(call.status)
+    }
+
+    /// Withdraw an open call, e.g. after a favourable revaluation.
+    ///
+    /// # Errors
+    ///
+    /// [`MarginCallError::UnknownCall`] or
+    /// [`MarginCallError::InvalidTransition`] if the call is closed.
+    pub fn cancel(&mut self, call_id: u64, now_ns: u64) -> Result<(), MarginCallError> {
+        let call = self.call_mut(call_id)?;
+        if !call.is_open() {
+            return Err(MarginCallError::InvalidTransition {
+                call_id,
+                status: call.status,
+            });
+        }
+        call.status = MarginCallStatus::Cancelled;
+        call.closed_ns = Some(now_ns);
+        Ok(())
+    }
+
+    /// Open calls (not returns) whose deadline has passed at `now_ns`.
+    #[must_use]
+    pub fn overdue(&self, now_ns: u64) -> Vec<&MarginCall> {
+        self.calls
+            .values()
+            .filter(|c| c.direction == CallDirection::Call && c.is_open() && now_ns > c.deadline_ns)
+            .collect()
+    }
+
+    /// Escalate every overdue call and declare its member in default on
+    /// `house`.
+    ///
+    /// Overdue returns are the clearing house's own obligation and are never
+    /// escalated.
+    pub fn escalate_overdue(&mut self, now_ns: u64, house: &mut ClearingHouse) -> Vec<Escalation> {
+        let ids: Vec<u64> = self.overdue(now_ns).iter().map(|c| c.call_id).collect();
+        ids.into_iter()
+            .filter_map(|id| {
+                let call = self.calls.get_mut(&id)?;
+                call.status = MarginCallStatus::Escalated;
+                call.closed_ns = Some(now_ns);
+                Some(Escalation {
+                    call_id: id,
+                    account_id: call.account_id,
+                    outstanding: call.outstanding(),
+                    default_result: house.declare_default(call.account_id),
+                })
+            })
+            .collect()
+    }
+
+    /// Look up a call.
+    #[must_use]
+    pub fn get(&self, call_id: u64) -> Option<&MarginCall> {
+        self.calls.get(&call_id)
+    }
+
+    /// The open call for `account_id`, if any.
+    #[must_use]
+    pub fn open_call(&self, account_id: u64) -> Option<&MarginCall> {
+        self.calls
+            .values()
+            .find(|c| c.account_id == account_id && c.is_open())
+    }
+
+    /// All calls in issue order.
+    pub fn calls(&self) -> impl Iterator<Item = &MarginCall> {
+        self.calls.values()
+    }
+
+    /// Total outstanding amount of open calls (not returns).
+    #[must_use]
+    pub fn total_outstanding(&self) -> i64 {
+        self.calls
+            .values()
+            .filter(|c| c.direction == CallDirection::Call && c.is_open())
+            .map(MarginCall::outstanding)
+            .sum()
+    }
+
+    fn call_mut(&mut self, call_id: u64) -> Result<&mut MarginCall, MarginCallError> {
+        self.calls
+            .get_mut(&call_id)
+            .ok_or(MarginCallError::UnknownCall(call_id))
+    }
+}
+
+// ── Tests ──────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clearing::AccountStatus;
+    use crate::collateral::CollateralType;
+
+    fn requirement(account_id: u64, total_margin: i64) -> MarginRequirement {
+        MarginRequirement {
+            account_id,
+            initial_margin: total_margin,
+            variation_margin: 0,
+            stress_margin: 0,
+            total_margin,
+            content_hash: 0,
+        }
+    }
+
+    fn cash(account_id: u64, amount: i64) -> CollateralAccount {
+        let mut acc = CollateralAccount::new(account_id, HaircutConfig::default());
+        acc.deposit(CollateralType::Cash, amount);
+        acc
+    }
+
+    fn manager() -> MarginCallManager {
+        MarginCallManager::new(MarginCallConfig {
+            call_deadline_ns: 100,
+            return_deadline_ns: 1_000,
+            minimum_transfer: 10,
+        })
+    }
+
+    #[test]
+    fn shortfall_issues_call() {
+        let mut m = manager();
+        let id = m
+            .evaluate(&requirement(1, 1_000), &cash(1, 700), 50)
+            .unwrap();
+        let call = m.get(id).unwrap();
+        assert_eq!(call.direction, CallDirection::Call);
+        assert_eq!(call.amount, 300);
+        assert_eq!(call.collateral_value, 700);
+        assert_eq!(call.deadline_ns, 150);
+        assert_eq!(call.status, MarginCallStatus::Issued);
+        assert_eq!(m.total_outstanding(), 300);
+    }
+
+    #[test]
+    fn excess_issues_return() {
+        let mut m = manager();
+        let id = m
+            .evaluate(&requirement(1, 1_000), &cash(1, 1_500), 0)
+            .unwrap();
+        let call = m.get(id).unwrap();
+        assert_eq!(call.direction, CallDirection::Return);
+        assert_eq!(call.amount, 500);
+        assert_eq!(call.deadline_ns, 1_000);
+        assert_eq!(m.total_outstanding(), 0);
+    }
+
+    #[test]
+    fn below_minimum_transfer_ignored() {
+        let mut m = manager();
+        assert_eq!(m.evaluate(&requirement(1, 1_000), &cash(1, 995), 0), None);
+        assert_eq!(m.evaluate(&requirement(1, 1_000), &cash(1, 1_000), 0), None);
+        assert_eq!(m.calls().count(), 0);
+    }
+
+    #[test]
+    fn one_open_call_per_account() {
+        let mut m = manager();
+        let first = m.evaluate(&requirement(1, 1_000), &cash(1, 0), 0).unwrap();
+        assert_eq!(m.evaluate(&requirement(1, 2_000), &cash(1, 0), 1), None);
+        m.record_delivery(first, 1_000, 2).unwrap();
+        assert!(m
+            .evaluate(&requirement(1, 2_000), &cash(1, 1_000), 3)
+            .is_some());
+    }
+
+    #[test]
+    fn evaluate_model_covers_all_parties() {
+        use crate::margin::{Aggregation, CompositeMarginModel, MarginConfig, MarginEngine};
+
+        let ob = NetObligation {
+            symbol_hash: 0xAB,
+            deliverer_id: 1,
+            receiver_id: 2,
+            net_quantity: 10,
+            net_payment: 1_000,
+            trade_count: 1,
+        };
+        // Deliverer: 50 initial + 1000 variation; receiver likewise.
+        let model = CompositeMarginModel::new(Aggregation::Max).with(Box::new(
+            MarginEngine::try_new(MarginConfig::default()).unwrap(),
+        ));
+        let mut m = manager();
+        let ids = m
+            .evaluate_model(&model, &[ob], &[cash(1, 1_050), cash(3, 500)], 0)
+            .unwrap();
+        let calls: Vec<(u64, CallDirection, i64)> = ids
+            .iter()
+            .map(|&id| {
+                let c = m.get(id).unwrap();
+                (c.account_id, c.direction, c.amount)
+            })
+            .collect();
+        assert_eq!(
+            calls,
+            vec![
+                (2, CallDirection::Call, 1_050),
+                (3, CallDirection::Return, 400),
+            ]
+        );
+    }
+
+    #[test]
+    fn lifecycle_partial_then_full() {
+        let mut m = manager();
+        let id = m.evaluate(&requirement(1, 1_000), &cash(1, 0), 0).unwrap();
+        m.acknowledge(id, 5).unwrap();
+        assert_eq!(m.get(id).unwrap().acknowledged_ns, Some(5));
+        assert_eq!(
+            m.acknowledge(id, 6),
+            Err(MarginCallError::InvalidTransition {
+                call_id: id,
+                status: MarginCallStatus::Acknowledged,
+            })
+        );
+        assert_eq!(
+            m.record_delivery(id, 400, 10),
+            Ok(MarginCallStatus::PartiallySatisfied)
+        );
+        assert_eq!(m.get(id).unwrap().outstanding(), 600);
+        assert_eq!(
+            m.record_delivery(id, 700, 20),
+            Ok(MarginCallStatus::Satisfied)
+        );
+        let call = m.get(id).unwrap();
+        assert_eq!(call.closed_ns, Some(20));
+        assert!(!call.is_open());
+        assert!(matches!(
+            m.record_delivery(id, 1, 30),
+            Err(MarginCallError::InvalidTransition { .. })
+        ));
+    }
+
+    #[test]
+    fn errors() {
+        let mut m = manager();
+        assert_eq!(m.acknowledge(9, 0), Err(MarginCallError::UnknownCall(9)));
+        assert_eq!(m.cancel(9, 0), Err(MarginCallError::UnknownCall(9)));
+        let id = m.evaluate(&requirement(1, 1_000), &cash(1, 0), 0).unwrap();
+        assert_eq!(
+            m.record_delivery(id, 0, 0),
+            Err(MarginCallError::InvalidAmount)
+        );
+        m.cancel(id, 1).unwrap();
+        assert_eq!(m.get(id).unwrap().status, MarginCallStatus::Cancelled);
+        assert!(m.open_call(1).is_none());
+    }
+
+    #[test]
+    fn overdue_calls_escalate_to_default() {
+        let mut house = ClearingHouse::new();
+        house.register_account(1, 0).unwrap();
+        house.register_account(2, 0).unwrap();
+        let mut m = manager();
+        let late = m.evaluate(&requirement(1, 1_000), &cash(1, 0), 0).unwrap();
+        let met = m.evaluate(&requirement(2, 1_000), &cash(2, 0), 0).unwrap();
+        let ret = m.evaluate(&requirement(3, 0), &cash(3, 500), 0).unwrap();
+        m.record_delivery(met, 1_000, 50).unwrap();
+        m.record_delivery(late, 250, 50).unwrap();
+
+        assert!(m.escalate_overdue(100, &mut house).is_empty());
+        let escalations = m.escalate_overdue(101, &mut house);
+        assert_eq!(
+            escalations,
+            vec![Escalation {
+                call_id: late,
+                account_id: 1,
+                outstanding: 750,
+                default_result: Ok(()),
+            }]
+        );
+        assert_eq!(m.get(late).unwrap().status, MarginCallStatus::Escalated);
+        assert_eq!(m.get(ret).unwrap().status, MarginCallStatus::Issued);
+        assert_eq!(
+            house.get_account(1).unwrap().status,
+            AccountStatus::InDefault
+        );
+        assert_eq!(house.get_account(2).unwrap().status, AccountStatus::Active);
+        assert!(m.escalate_overdue(10_000, &mut house).is_empty());
+    }
+
+    #[test]
+    fn evaluate_all_uses_matching_collateral() {
+        let mut m = manager();
+        let ids = m.evaluate_all(
+            &[
+                requirement(1, 100),
+                requirement(2, 100),
+                requirement(3, 100),
+            ],
+            &[cash(2, 100), cash(1, 40)],
+            0,
+        );
+        assert_eq!(ids.len(), 2);
+        assert_eq!(m.open_call(1).unwrap().amount, 60);
+        assert!(m.open_call(2).is_none());
+        assert_eq!(m.open_call(3).unwrap().amount, 100);
+    }
+}
@@ -162,6 +162,18 @@ impl ReplayVerifier {
             JournalEvent::ClearingAttempted { .. } => 2,
             JournalEvent::SettlementCompleted { .. } => 3,
             JournalEvent::SettlementFailed { .. } => 4,
+            JournalEvent::CashDeposited { .. } => 5,
+            JournalEvent::CashWithdrawn { .. } => 6,
+            JournalEvent::CashTransferred { .. } => 7,
+            JournalEvent::AccountRegistered { .. } => 8,
+            JournalEvent::ObligationCleared { .. } => 9,
+            JournalEvent::ObligationFailed { .. } => 10,
+            JournalEvent::MarginPosted { .. } => 11,
+            JournalEvent::MarginReleased { .. } => 12,
+            JournalEvent::WaterfallApplied { .. } => 13,
+            JournalEvent::FeeCharged { .. } => 14,
+            JournalEvent::AccountStatusChanged { .. } => 15,
+            JournalEvent::OverdraftLimitSet { .. } => 16,
         }
     }
 
@@ -184,9 +196,92 @@ impl ReplayVerifier {
                 let reason_hash = fnv1a(reason.as_bytes());
                 *trade_id ^ reason_hash
             }
+            JournalEvent::CashDeposited {
+                account_id,
+                amount,
+                idempotency_key,
+            }
+            | JournalEvent::CashWithdrawn {
+                account_id,
+                amount,
+                idempotency_key,
+            } => Self::words_hash(&[*account_id, *amount as u64, *idempotency_key]),
+            JournalEvent::CashTransferred {
+                from_id,
+                to_id,
+                amount,
+                idempotency_key,
+            } => Self::words_hash(&[*from_id, *to_id, *amount as u64, *idempotency_key]),
+            JournalEvent::AccountRegistered {
+                account_id,
+                balance: amount,
+            }
+            | JournalEvent::MarginPosted { account_id, amount }
+            | JournalEvent::MarginReleased { account_id, amount }
+            | JournalEvent::OverdraftLimitSet {
+                account_id,
+                limit: amount,
+            } => Self::words_hash(&[*account_id, *amount as u64]),
+            JournalEvent::AccountStatusChanged {
+                account_id,
+                from,
+                to,
+            } => Self::words_hash(&[*account_id, *from as u64, *to as u64]),
+            JournalEvent::ObligationCleared {
+                symbol_hash,
+                deliverer_id,
+                receiver_id,
+                net_quantity,
+                net_payment,
+            } => Self::words_hash(&[
+                *symbol_hash,
+                *deliverer_id,
+                *receiver_id,
+                *net_quantity,
+                *net_payment as u64,
+            ]),
+            JournalEvent::ObligationFailed {
+                symbol_hash,
+                deliverer_id,
+                receiver_id,
+                net_quantity,
+                net_payment,
+                reason,
+            } => Self::words_hash(&[
+                *symbol_hash,
+                *deliverer_id,
+                *receiver_id,
+                *net_quantity,
+                *net_payment as u64,
+                fnv1a(reason.as_bytes()),
+            ]),
+            JournalEvent::FeeCharged {
+                account_id,
+                symbol_hash,
+                amount,
+            } => Self::words_hash(&[*account_id, *symbol_hash, *amount as u64]),
+            JournalEvent::WaterfallApplied {
+                account_id,
+                absorbed,
+            } => {
+                let mut words = [*account_id; 6];
+                for (word, &amount) in words[1..].iter_mut().zip(absorbed) {
+                    *word = amount as u64;
+                }
+                Self::words_hash(&words)
+            }
         }
     }
 
+    /// Hash a sequence of little-endian words into a single payload.
+    fn words_hash(words: &[u64]) -> u64 {
+        let mut data = Vec::with_capacity(words.len() * 8);
+        for w in words {
+            data.extend_from_slice(&w.to_le_bytes());
+        }
+        fnv1a(&data)
+    }
+
     fn step_hash(sequence: u64, timestamp_ns: u64, kind: u8, payload: u64) -> u64 {
         let mut data = [0u8; 25];
         data[0..8].copy_from_slice(&sequence.to_le_bytes());
@@ -209,6 +304,7 @@ impl ReplayVerifier {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clearing::AccountStatus;
 
     fn make_journal(events: &[(u64, JournalEvent)]) -> SettlementJournal {
         let mut journal = SettlementJournal::new();
@@ -469,11 +565,96 @@ mod tests {
                 trade_id: 0,
                 reason: String::new(),
             }),
+            ReplayVerifier::event_kind_byte(&JournalEvent::CashDeposited {
+                account_id: 0,
+                amount: 0,
+                idempotency_key: 0,
+            }),
+            ReplayVerifier::event_kind_byte(&JournalEvent::CashWithdrawn {
+                account_id: 0,
+                amount: 0,
+                idempotency_key: 0,
+            }),
+            ReplayVerifier::event_kind_byte(&JournalEvent::CashTransferred {
+                from_id: 0,
+                to_id: 0,
+                amount: 0,
+                idempotency_key: 0,
+            }),
+            ReplayVerifier::event_kind_byte(&JournalEvent::AccountRegistered {
+                account_id: 0,
+                balance: 0,
+            }),
+            ReplayVerifier::event_kind_byte(&JournalEvent::ObligationCleared {
+                symbol_hash: 0,
+                deliverer_id: 0,
+                receiver_id: 0,
+                net_quantity: 0,
+                net_payment: 0,
+            }),
+            ReplayVerifier::event_kind_byte(&JournalEvent::ObligationFailed {
+                symbol_hash: 0,
+                deliverer_id: 0,
+                receiver_id: 0,
+                net_quantity: 0,
+                net_payment: 0,
+                reason: String::new(),
+            }),
+            ReplayVerifier::event_kind_byte(&JournalEvent::MarginPosted {
+                account_id: 0,
+                amount: 0,
+            }),
+            ReplayVerifier::event_kind_byte(&JournalEvent::MarginReleased {
+                account_id: 0,
+                amount: 0,
+            }),
+            ReplayVerifier::event_kind_byte(&JournalEvent::WaterfallApplied {
+                account_id: 0,
+                absorbed: [0; 5],
+            }),
+            ReplayVerifier::event_kind_byte(&JournalEvent::FeeCharged {
+                account_id: 0,
+                symbol_hash: 0,
+                amount: 0,
+            }),
+            ReplayVerifier::event_kind_byte(&JournalEvent::AccountStatusChanged {
+                account_id: 0,
+                from: AccountStatus::Active,
+                to: AccountStatus::Suspended,
+            }),
+            ReplayVerifier::event_kind_byte(&JournalEvent::OverdraftLimitSet {
+                account_id: 0,
+                limit: 0,
+            }),
         ];
-        // Must be exactly 0..4
+        // Must be exactly 0..16
         let mut sorted = kinds;
         sorted.sort_unstable();
-        assert_eq!(sorted, [0, 1, 2, 3, 4]);
+        let expected: Vec<u8> = (0..17).collect();
+        assert_eq!(sorted.to_vec(), expected);
+    }
+
+    #[test]
+    fn cash_events_distinguished_by_kind() {
+        let j1 = make_journal(&[(
+            100,
+            JournalEvent::CashDeposited {
+                account_id: 1,
+                amount: 500,
+                idempotency_key: 9,
+            },
+        )]);
+        let j2 = make_journal(&[(
+            100,
+            JournalEvent::CashWithdrawn {
+                account_id: 1,
+                amount: 500,
+                idempotency_key: 9,
+            },
+        )]);
+        let log1 = ReplayVerifier::build_replay_log(&j1);
+        let log2 = ReplayVerifier::build_replay_log(&j2);
+        assert!(!ReplayVerifier::verify(&log1, &log2).success);
     }
 
     #[test]
@@ -0,0 +1,431 @@
+// ALICE-Settlement — Pre-clearing credit and exposure limits
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Moroya Sakamoto
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::netting::NetObligation;
+
+// ── Configuration ──────────────────────────────────────────────────────
+
+/// Exposure limits for a single clearing member.
+///
+/// Exposures follow the clearing convention: the deliverer of an obligation
+/// pays `net_payment` and the receiver is credited with it.  Positions are
+/// signed per symbol, positive for the receiver of delivery.
+#[derive(Debug, Clone)]
+pub struct MemberLimits {
+    /// Maximum gross exposure: sum of `|net_payment|` across all obligations.
+    pub max_gross_exposure: i64,
+    /// Maximum net exposure: `|receipts - payments|`.
+    pub max_net_exposure: i64,
+    /// Per-symbol maximum absolute net position in lots.
+    pub position_limits: HashMap<u64, u64>,
+    /// Maximum share of the house's open interest in a single symbol, in
+    /// bps (10000 = no limit).  Open interest is the gross exposure of every
+    /// member in the symbol, so the two sides of a single obligation hold
+    /// 50% each.
+    pub max_concentration_bps: u32,
+}
+
+impl Default for MemberLimits {
+    /// Unlimited: no check can breach.
+    fn default() -> Self {
+        Self {
+            max_gross_exposure: i64::MAX,
+            max_net_exposure: i64::MAX,
+            position_limits: HashMap::new(),
+            max_concentration_bps: 10_000,
+        }
+    }
+}
+
+/// Limit configuration for all clearing members.
+#[derive(Debug, Clone, Default)]
+pub struct RiskLimits {
+    /// Limits applied to members without an explicit entry.
+    pub default_limits: MemberLimits,
+    /// Per-member limits, keyed by account identifier.
+    pub member_limits: HashMap<u64, MemberLimits>,
+    /// When true, [`ClearingHouse::clear_all_checked`](crate::clearing::ClearingHouse::clear_all_checked)
+    /// rejects every obligation that involves a member in breach.
+    pub block_on_breach: bool,
+}
+
+impl RiskLimits {
+    /// Limits applicable to `account_id`.
+    #[inline]
+    #[must_use]
+    pub fn limits_for(&self, account_id: u64) -> &MemberLimits {
+        self.member_limits
+            .get(&account_id)
+            .unwrap_or(&self.default_limits)
+    }
+}
+
+// ── Report ─────────────────────────────────────────────────────────────
+
+/// Which limit was breached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreachKind {
+    /// Gross exposure above `max_gross_exposure`.
+    GrossExposure,
+    /// Net exposure above `max_net_exposure`.
+    NetExposure,
+    /// Absolute net position in a symbol above its position limit.
+    Position { symbol_hash: u64 },
+    /// Share of the house's open interest in a symbol above
+    /// `max_concentration_bps`.
+    Concentration { symbol_hash: u64 },
+}
+
+/// A single limit breach.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RiskBreach {
+    /// Member in breach.
+    pub account_id: u64,
+    /// Which limit was breached.
+    pub kind: BreachKind,
+    /// Observed value (ticks, lots or bps depending on `kind`).
+    pub value: i64,
+    /// Configured limit in the same unit as `value`.
+    pub limit: i64,
+}
+
+/// Aggregated exposure of one member across a set of obligations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberExposure {
+    /// Member identifier.
+    pub account_id: u64,
+    /// Sum of `|net_payment|`.
+    pub gross_exposure: i64,
+    /// Signed net cash: receipts minus payments.
+    pub net_cash: i64,
+    /// Signed net position per symbol (positive = net receiver of delivery).
+    pub positions: BTreeMap<u64, i128>,
+    /// Gross exposure per symbol.
+    pub symbol_gross: BTreeMap<u64, i64>,
+}
+
+/// Result of evaluating obligations against [`RiskLimits`].
+#[derive(Debug, Clone)]
+pub struct RiskReport {
+    /// Per-member exposures, sorted by account identifier.
+    pub exposures: Vec<MemberExposure>,
+    /// All breaches, sorted by account identifier.
+    pub breaches: Vec<RiskBreach>,
+}
+
+impl RiskReport {
+    /// True when no limit is breached.
+    #[inline]
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.breaches.is_empty()
+    }
+
+    /// Members with at least one breach, sorted and deduplicated.
+    #[must_use]
+    pub fn breached_members(&self) -> Vec<u64> {
+        let mut ids: Vec<u64> = self.breaches.iter().map(|b| b.account_id).collect();
+        ids.dedup();
+        ids
+    }
+
+    /// True when `account_id` has at least one breach.
+    #[must_use]
+    pub fn is_breached(&self, account_id: u64) -> bool {
+        self.breaches.iter().any(|b| b.account_id == account_id)
+    }
+}
+
+// ── Risk Checker ───────────────────────────────────────────────────────
+
+/// Pre-clearing risk checker.
+///
+/// Aggregates the obligations produced by
+/// [`NettingEngine`](crate::netting::NettingEngine) per member and evaluates
+/// them against gross, net, position and concentration limits.
+pub struct RiskChecker {
+    limits: RiskLimits,
+}
+
+impl RiskChecker {
+    /// Create a checker with the given limits.
+    #[must_use]
+    pub const fn new(limits: RiskLimits) -> Self {
+        Self { limits }
+    }
+
+    /// Access the configured limits.
+    #[inline]
+    #[must_use]
+    pub const fn limits(&self) -> &RiskLimits {
+        &self.limits
+    }
+
+    /// Aggregate per-member exposures from a set of obligations.
+    #[must_use]
+    pub fn exposures(obligations: &[NetObligation]) -> Vec<MemberExposure> {
+        let mut by_member: BTreeMap<u64, MemberExposure> = BTreeMap::new();
+        for ob in obligations {
+            let notional = ob.net_payment.saturating_abs();
+            let qty = ob.net_quantity as i128;
+            for (id, cash_sign, qty_sign) in [
+                (ob.deliverer_id, -1i64, -1i128),
+                (ob.receiver_id, 1i64, 1i128),
+            ] {
+                let exp = by_member.entry(id).or_insert_with(|| MemberExposure {
+                    account_id: id,
+                    gross_exposure: 0,
+                    net_cash: 0,
+                    positions: BTreeMap::new(),
+                    symbol_gross: BTreeMap::new(),
+                });
+                exp.gross_exposure = exp.gross_exposure.saturating_add(notional);
+                exp.net_cash = exp
+                    .net_cash
+                    .saturating_add(cash_sign.saturating_mul(ob.net_payment));
+                *exp.positions.entry(ob.symbol_hash).or_insert(0) += qty_sign * qty;
+                let sym = exp.symbol_gross.entry(ob.symbol_hash).or_insert(0);
+                *sym = sym.saturating_add(notional);
+            }
+        }
+        by_member.into_values().collect()
+    }
+
+    /// Evaluate obligations against the configured limits.
+    #[must_use]
+    pub fn evaluate(&self, obligations: &[NetObligation]) -> RiskReport {
+        let exposures = Self::exposures(obligations);
+        let mut open_interest: BTreeMap<u64, i64> = BTreeMap::new();
+        for exp in &exposures {
+            for (&symbol_hash, &gross) in &exp.symbol_gross {
+                let oi = open_interest.entry(symbol_hash).or_insert(0);
+                *oi = oi.saturating_add(gross);
+            }
+        }
+        let mut breaches = Vec::new();
+
+        for exp in &exposures {
+            let limits = self.limits.limits_for(exp.account_id);
+            let id = exp.account_id;
+
+            if exp.gross_exposure > limits.max_gross_exposure {
+                breaches.push(RiskBreach {
+                    account_id: id,
+                    kind: BreachKind::GrossExposure,
+                    value: exp.gross_exposure,
+                    limit: limits.max_gross_exposure,
+                });
+            }
+
+            let net = exp.net_cash.saturating_abs();
+            if net > limits.max_net_exposure {
+                breaches.push(RiskBreach {
+                    account_id: id,
+                    kind: BreachKind::NetExposure,
+                    value: net,
+                    limit: limits.max_net_exposure,
+                });
+            }
+
+            for (&symbol_hash, &pos) in &exp.positions {
+                if let Some(&max) = limits.position_limits.get(&symbol_hash) {
+                    let abs = pos.unsigned_abs();
+                    if abs > max as u128 {
+                        breaches.push(RiskBreach {
+                            account_id: id,
+                            kind: BreachKind::Position { symbol_hash },
+                            value: abs.min(i64::MAX as u128) as i64,
+                            limit: max.min(i64::MAX as u64) as i64,
+                        });
+                    }
+                }
+            }
+
+            for (&symbol_hash, &gross) in &exp.symbol_gross {
+                let oi = open_interest[&symbol_hash];
+                if oi == 0 {
+                    continue;
+                }
+                let share_bps = (gross as i128 * 10_000 / oi as i128) as i64;
+                if share_bps > limits.max_concentration_bps as i64 {
+                    breaches.push(RiskBreach {
+                        account_id: id,
+                        kind: BreachKind::Concentration { symbol_hash },
+                        value: share_bps,
+                        limit: limits.max_concentration_bps as i64,
+                    });
+                }
+            }
+        }
+
+        RiskReport {
+            exposures,
+            breaches,
+        }
+    }
+}
+
+// ── Tests ──────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_obligation(
+        symbol_hash: u64,
+        deliverer_id: u64,
+        receiver_id: u64,
+        net_quantity: u64,
+        net_payment: i64,
+    ) -> NetObligation {
+        NetObligation {
+            symbol_hash,
+            deliverer_id,
+            receiver_id,
+            net_quantity,
+            net_payment,
+            trade_count: 1,
+        }
+    }
+
+    #[test]
+    fn default_limits_never_breach() {
+        let checker = RiskChecker::new(RiskLimits::default());
+        let obs = vec![make_obligation(0x1, 1, 2, 1_000, i64::MAX / 2)];
+        let report = checker.evaluate(&obs);
+        assert!(report.is_clean());
+        assert_eq!(report.exposures.len(), 2);
+    }
+
+    #[test]
+    fn exposures_aggregate_both_sides() {
+        let obs = vec![
+            make_obligation(0x1, 1, 2, 10, 1_000),
+            make_obligation(0x2, 2, 1, 5, 400),
+        ];
+        let exps = RiskChecker::exposures(&obs);
+        assert_eq!(exps.len(), 2);
+
+        let a = &exps[0];
+        assert_eq!(a.account_id, 1);
+        assert_eq!(a.gross_exposure, 1_400);
+        assert_eq!(a.net_cash, -600); // pays 1000, receives 400
+        assert_eq!(a.positions[&0x1], -10);
+        assert_eq!(a.positions[&0x2], 5);
+
+        let b = &exps[1];
+        assert_eq!(b.account_id, 2);
+        assert_eq!(b.net_cash, 600);
+    }
+
+    #[test]
+    fn gross_and_net_breaches() {
+        let mut limits = RiskLimits::default();
+        limits.member_limits.insert(
+            1,
+            MemberLimits {
+                max_gross_exposure: 1_000,
+                max_net_exposure: 500,
+                ..MemberLimits::default()
+            },
+        );
+        let checker = RiskChecker::new(limits);
+        let report = checker.evaluate(&[make_obligation(0x1, 1, 2, 10, 1_200)]);
+
+        assert_eq!(report.breached_members(), vec![1]);
+        assert_eq!(report.breaches.len(), 2);
+        assert_eq!(report.breaches[0].kind, BreachKind::GrossExposure);
+        assert_eq!(report.breaches[0].value, 1_200);
+        assert_eq!(report.breaches[0].limit, 1_000);
+        assert_eq!(report.breaches[1].kind, BreachKind::NetExposure);
+        assert!(!report.is_breached(2));
+    }
+
+    #[test]
+    fn offsetting_flows_reduce_net_not_gross() {
+        let mut limits = RiskLimits::default();
+        limits.default_limits.max_net_exposure = 100;
+        let checker = RiskChecker::new(limits);
+        let report = checker.evaluate(&[
+            make_obligation(0x1, 1, 2, 10, 1_000),
+            make_obligation(0x2, 2, 1, 10, 950),
+        ]);
+        assert!(report.is_clean(), "net 50 is within limit: {report:?}");
+    }
+
+    #[test]
+    fn position_limit_breach() {
+        let mut limits = RiskLimits::default();
+        limits.default_limits.position_limits.insert(0xAB, 50);
+        let checker = RiskChecker::new(limits);
+        let report = checker.evaluate(&[
+            make_obligation(0xAB, 1, 2, 40, 4_000),
+            make_obligation(0xAB, 3, 2, 20, 2_000),
+        ]);
+        // Member 2 receives 60 lots > 50; members 1 and 3 are within limit.
+        assert_eq!(report.breached_members(), vec![2]);
+        assert_eq!(
+            report.breaches[0],
+            RiskBreach {
+                account_id: 2,
+                kind: BreachKind::Position { symbol_hash: 0xAB },
+                value: 60,
+                limit: 50,
+            }
+        );
+    }
+
+    #[test]
+    fn concentration_breach() {
+        let mut limits = RiskLimits::default();
+        limits.default_limits.max_concentration_bps = 3_000;
+        let checker = RiskChecker::new(limits);
+        let report = checker.evaluate(&[
+            make_obligation(0x1, 1, 2, 1, 7_000),
+            make_obligation(0x1, 3, 4, 1, 1_000),
+            make_obligation(0x1, 5, 6, 1, 2_000),
+            make_obligation(0x2, 1, 3, 1, 3_000),
+        ]);
+        // Open interest in 0x1 is 20000: members 1 and 2 hold 35% each.
+        // Members 4, 5 and 6 trade only 0x1 but hold at most 10% of it.
+        assert_eq!(report.breached_members(), vec![1, 2, 3]);
+        assert_eq!(
+            report.breaches[0],
+            RiskBreach {
+                account_id: 1,
+                kind: BreachKind::Concentration { symbol_hash: 0x1 },
+                value: 3_500,
+                limit: 3_000,
+            }
+        );
+        // 0x2 has one obligation, so each side holds 50% of it.
+        let m3: Vec<BreachKind> = report
+            .breaches
+            .iter()
+            .filter(|b| b.account_id == 3)
+            .map(|b| b.kind)
+            .collect();
+        assert_eq!(m3, vec![BreachKind::Concentration { symbol_hash: 0x2 }]);
+    }
+
+    #[test]
+    fn member_override_replaces_default() {
+        let mut limits = RiskLimits::default();
+        limits.default_limits.max_gross_exposure = 10;
+        limits.member_limits.insert(1, MemberLimits::default());
+        let checker = RiskChecker::new(limits);
+        let report = checker.evaluate(&[make_obligation(0x1, 1, 2, 1, 100)]);
+        assert_eq!(report.breached_members(), vec![2]);
+    }
+
+    #[test]
+    fn empty_obligations_clean() {
+        let checker = RiskChecker::new(RiskLimits::default());
+        let report = checker.evaluate(&[]);
+        assert!(report.is_clean());
+        assert!(report.exposures.is_empty());
+    }
+}
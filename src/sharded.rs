@@ -0,0 +1,1064 @@
+// ALICE-Settlement — Sharded, thread-safe clearing
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Moroya Sakamoto
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread;
+
+use crate::audit::{AuditReport, BalanceAuditor, ExternalFlows};
+use crate::clearing::{
+    apply_cash, apply_settlement, check_amount, check_cash, check_settlement, AccountStatus,
+    Accounts, Books, CashOperation, CashOutcome, ClearingAccount, ClearingError, ClearingHouse,
+    ClearingResult, FeeResult,
+};
+use crate::fee::FeeObligation;
+use crate::journal::JournalEntry;
+use crate::ledger::LedgerAccount;
+use crate::netting::NetObligation;
+use crate::snapshot::{ClearingSnapshot, SnapshotError};
+use crate::waterfall::{WaterfallLayer, WaterfallResult};
+
+type Shard = HashMap<u64, ClearingAccount>;
+
+/// The locked shards holding one operation's accounts.
+struct Locked<'a> {
+    first: &'a mut Shard,
+    second: Option<&'a mut Shard>,
+}
+
+impl Accounts for Locked<'_> {
+    fn account(&self, id: u64) -> Option<&ClearingAccount> {
+        self.first
+            .get(&id)
+            .or_else(|| self.second.as_deref()?.get(&id))
+    }
+
+    fn account_mut(&mut self, id: u64) -> Option<&mut ClearingAccount> {
+        if self.first.contains_key(&id) {
+            return self.first.get_mut(&id);
+        }
+        self.second.as_deref_mut()?.get_mut(&id)
+    }
+}
+
+// ── Sharded Clearing House ─────────────────────────────────────────────
+
+/// Thread-safe clearing house with accounts spread over mutex-guarded shards.
+///
+/// Every operation takes `&self` and runs the same checks and bookkeeping as
+/// the matching [`ClearingHouse`] operation.  Balances and statuses live in
+/// the shards; the general ledger, settlement journal, idempotency keys and
+/// external flows live in one set of books.  State-changing operations lock
+/// the shards of their accounts in ascending shard index, check the change,
+/// then lock the books only to post and journal it before updating the
+/// accounts.  Operations on different shards therefore run in parallel and
+/// cannot deadlock, and each account's journal entries are in the order its
+/// changes were applied; entries of operations with no account in common
+/// may interleave in any order, which does not affect replay.  Reads lock
+/// only the shards they need.
+///
+/// [`clear_all`](Self::clear_all) splits a batch into waves of obligations
+/// with no account in common and settles each wave in parallel.  An
+/// account's obligations run in batch order, so results and balances are
+/// identical to clearing the batch sequentially with
+/// [`ClearingHouse::clear_all`].
+///
+/// Risk-checked clearing, fee pricing, statements, ledger reconciliation and
+/// journal replay are only offered by [`ClearingHouse`]; restore a
+/// [`snapshot`](Self::snapshot) into it to use them.  Either house can be
+/// restored from the other's snapshots.
+pub struct ShardedClearingHouse {
+    shards: Vec<Mutex<Shard>>,
+    books: Mutex<Books>,
+}
+
+impl ShardedClearingHouse {
+    /// Create an empty clearing house with `shard_count` shards (at least 1).
+    #[must_use]
+    pub fn new(shard_count: usize) -> Self {
+        Self::with_books(shard_count, Books::new())
+    }
+
+    fn with_books(shard_count: usize, books: Books) -> Self {
+        Self {
+            shards: (0..shard_count.max(1))
+                .map(|_| Mutex::new(HashMap::new()))
+                .collect(),
+            books: Mutex::new(books),
+        }
+    }
+
+    /// Rebuild a clearing house from a snapshot, as [`ClearingHouse::restore`]
+    /// does.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SnapshotError::HashMismatch`] if the snapshot's content no
+    /// longer matches its hash.
+    pub fn from_snapshot(
+        snapshot: &ClearingSnapshot,
+        shard_count: usize,
+    ) -> Result<Self, SnapshotError> {
+        snapshot.check()?;
+        let ch = Self::with_books(shard_count, Books::restore(snapshot));
+        for acc in &snapshot.accounts {
+            ch.lock(ch.shard_of(acc.account_id))
+                .insert(acc.account_id, acc.clone());
+        }
+        Ok(ch)
+    }
+
+    /// Number of shards.
+    #[inline(always)]
+    #[must_use]
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Shard holding `account_id`.
+    #[inline(always)]
+    #[must_use]
+    pub fn shard_of(&self, account_id: u64) -> usize {
+        (account_id % self.shards.len() as u64) as usize
+    }
+
+    /// Lock a shard.  A panic while holding a lock cannot leave an account
+    /// half-updated (checks precede mutation), so poisoning is ignored.
+    #[inline(always)]
+    fn lock(&self, index: usize) -> MutexGuard<'_, Shard> {
+        self.shards[index]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Lock every shard in index order.
+    fn lock_all(&self) -> Vec<MutexGuard<'_, Shard>> {
+        (0..self.shards.len()).map(|i| self.lock(i)).collect()
+    }
+
+    /// Lock the books.  Taken after any shard locks and held only to read or
+    /// record.
+    #[inline(always)]
+    fn books(&self) -> MutexGuard<'_, Books> {
+        self.books.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Lock the shards of `a` and `b` in ascending shard index and call `f`
+    /// with them.
+    fn with_pair<R>(&self, a: u64, b: u64, f: impl FnOnce(&mut Locked<'_>) -> R) -> R {
+        let (sa, sb) = (self.shard_of(a), self.shard_of(b));
+        if sa == sb {
+            return f(&mut Locked {
+                first: &mut self.lock(sa),
+                second: None,
+            });
+        }
+        let (mut lo, mut hi) = (self.lock(sa.min(sb)), self.lock(sa.max(sb)));
+        f(&mut Locked {
+            first: &mut lo,
+            second: Some(&mut hi),
+        })
+    }
+
+    /// Set the timestamp recorded on subsequent journal entries.
+    pub fn set_time(&self, timestamp_ns: u64) {
+        self.books().now_ns = timestamp_ns;
+    }
+
+    /// Register a new account with an initial balance.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError::AccountExists`] if `id` is already
+    /// registered, whatever its status.
+    pub fn register_account(&self, id: u64, initial_balance: i64) -> Result<(), ClearingError> {
+        let mut shard = self.lock(self.shard_of(id));
+        if shard.contains_key(&id) {
+            return Err(ClearingError::AccountExists(id));
+        }
+        self.books().record_registration(id, initial_balance);
+        shard.insert(id, ClearingAccount::open(id, initial_balance));
+        Ok(())
+    }
+
+    /// Allow withdrawals and transfers to take `id`'s balance down to
+    /// `-limit`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the account is missing or `limit` is
+    /// negative.
+    pub fn set_overdraft_limit(&self, id: u64, limit: i64) -> Result<(), ClearingError> {
+        if limit < 0 {
+            return Err(ClearingError::InvalidAmount(limit));
+        }
+        let mut shard = self.lock(self.shard_of(id));
+        shard.lookup(id)?;
+        self.books().record_overdraft_limit(id, limit);
+        if let Some(acc) = shard.get_mut(&id) {
+            acc.overdraft_limit = limit;
+        }
+        Ok(())
+    }
+
+    /// Copy of an account.
+    #[must_use]
+    pub fn get_account(&self, id: u64) -> Option<ClearingAccount> {
+        self.lock(self.shard_of(id)).get(&id).cloned()
+    }
+
+    /// Number of registered accounts.
+    #[must_use]
+    pub fn account_count(&self) -> usize {
+        (0..self.shards.len()).map(|i| self.lock(i).len()).sum()
+    }
+
+    /// Sum of balance plus margin held across all accounts.
+    ///
+    /// Holds every shard lock (in index order) so the total is consistent.
+    #[must_use]
+    pub fn total_funds(&self) -> i128 {
+        self.lock_all()
+            .iter()
+            .flat_map(|shard| shard.values())
+            .map(|a| a.balance as i128 + a.margin_held as i128)
+            .sum()
+    }
+
+    // ── Account lifecycle ─────────────────────────────────────────────
+
+    /// Suspend an `Active` account, as [`ClearingHouse::suspend_account`]
+    /// does.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the account is missing or not `Active`.
+    pub fn suspend_account(&self, id: u64) -> Result<(), ClearingError> {
+        self.transition(id, AccountStatus::Suspended)
+    }
+
+    /// Reinstate a `Suspended` account to `Active`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the account is missing or not `Suspended`.
+    pub fn reinstate_account(&self, id: u64) -> Result<(), ClearingError> {
+        self.transition(id, AccountStatus::Active)
+    }
+
+    /// Declare an `Active` or `Suspended` account in default.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the account is missing, already in
+    /// default, or closed.
+    pub fn declare_default(&self, id: u64) -> Result<(), ClearingError> {
+        self.transition(id, AccountStatus::InDefault)
+    }
+
+    /// Close an account.  The account must hold no balance and no margin.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the account is missing, already closed,
+    /// or not empty.
+    pub fn close_account(&self, id: u64) -> Result<(), ClearingError> {
+        self.transition(id, AccountStatus::Closed)
+    }
+
+    /// Apply a guarded status transition.
+    fn transition(&self, id: u64, to: AccountStatus) -> Result<(), ClearingError> {
+        let mut shard = self.lock(self.shard_of(id));
+        let acc = shard.lookup(id)?;
+        if to == AccountStatus::Closed {
+            acc.check_close()?;
+        }
+        let from = acc.status;
+        from.check_transition(id, to)?;
+        self.books().record_status(id, from, to);
+        if let Some(acc) = shard.get_mut(&id) {
+            acc.status = to;
+        }
+        Ok(())
+    }
+
+    // ── Cash ──────────────────────────────────────────────────────────
+
+    /// Credit cash paid in from outside the clearing house, as
+    /// [`ClearingHouse::deposit`] does.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the amount is not positive, the account is
+    /// missing or closed, the balance would overflow, or the key was used for
+    /// a different operation.
+    pub fn deposit(
+        &self,
+        idempotency_key: u64,
+        id: u64,
+        amount: i64,
+    ) -> Result<CashOutcome, ClearingError> {
+        self.cash_operation(
+            idempotency_key,
+            CashOperation::Deposit {
+                account_id: id,
+                amount,
+            },
+        )
+    }
+
+    /// Debit cash paid out of the clearing house, as
+    /// [`ClearingHouse::withdraw`] does.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the amount is not positive, the account is
+    /// missing or not `Active`, the withdrawal exceeds the balance plus
+    /// overdraft limit, or the key was used for a different operation.
+    pub fn withdraw(
+        &self,
+        idempotency_key: u64,
+        id: u64,
+        amount: i64,
+    ) -> Result<CashOutcome, ClearingError> {
+        self.cash_operation(
+            idempotency_key,
+            CashOperation::Withdraw {
+                account_id: id,
+                amount,
+            },
+        )
+    }
+
+    /// Move cash between two `Active` accounts, as
+    /// [`ClearingHouse::transfer`] does.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the amount is not positive, both ids name
+    /// the same account, either account is missing or not `Active`, the
+    /// transfer exceeds the sender's balance plus overdraft limit, the
+    /// receiver's balance would overflow, or the key was used for a different
+    /// operation.
+    pub fn transfer(
+        &self,
+        idempotency_key: u64,
+        from_id: u64,
+        to_id: u64,
+        amount: i64,
+    ) -> Result<CashOutcome, ClearingError> {
+        self.cash_operation(
+            idempotency_key,
+            CashOperation::Transfer {
+                from_id,
+                to_id,
+                amount,
+            },
+        )
+    }
+
+    /// Apply an idempotent cash operation under its accounts' shard locks.
+    ///
+    /// The key is checked again when the operation is recorded, since an
+    /// operation on other shards may have used it in the meantime.
+    fn cash_operation(&self, key: u64, op: CashOperation) -> Result<CashOutcome, ClearingError> {
+        let (a, b) = match op {
+            CashOperation::Deposit { account_id, .. }
+            | CashOperation::Withdraw { account_id, .. } => (account_id, account_id),
+            CashOperation::Transfer { from_id, to_id, .. } => (from_id, to_id),
+        };
+        self.with_pair(a, b, |accounts| {
+            if self.books().check_idempotency(key, op)? == CashOutcome::Duplicate {
+                return Ok(CashOutcome::Duplicate);
+            }
+            check_cash(accounts, op)?;
+            let mut books = self.books();
+            if books.check_idempotency(key, op)? == CashOutcome::Duplicate {
+                return Ok(CashOutcome::Duplicate);
+            }
+            books.record_cash(key, op);
+            drop(books);
+            apply_cash(accounts, op);
+            Ok(CashOutcome::Applied)
+        })
+    }
+
+    /// Debit a fee from `id`'s balance into fee income, as
+    /// [`ClearingHouse::charge_fee`] does.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the amount is not positive, the account is
+    /// missing or closed, or the fee exceeds the balance plus overdraft limit.
+    pub fn charge_fee(&self, id: u64, symbol_hash: u64, amount: i64) -> Result<(), ClearingError> {
+        check_amount(amount)?;
+        let mut shard = self.lock(self.shard_of(id));
+        shard.lookup(id)?.check_fee(amount)?;
+        self.books().record_fee(id, symbol_hash, amount);
+        if let Some(acc) = shard.get_mut(&id) {
+            acc.balance -= amount;
+        }
+        Ok(())
+    }
+
+    /// Charge a batch of fee obligations in order.
+    pub fn charge_fees(&self, fees: &[FeeObligation]) -> Vec<FeeResult> {
+        fees.iter()
+            .map(|fee| {
+                let error = self
+                    .charge_fee(fee.account_id, fee.symbol_hash, fee.amount)
+                    .err();
+                FeeResult {
+                    fee: fee.clone(),
+                    success: error.is_none(),
+                    error,
+                }
+            })
+            .collect()
+    }
+
+    // ── Margin ────────────────────────────────────────────────────────
+
+    /// Move `amount` from an account's balance into margin held, as
+    /// [`ClearingHouse::post_margin`] does.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the amount is not positive, the account is
+    /// missing or closed, or the balance is insufficient.
+    pub fn post_margin(&self, id: u64, amount: i64) -> Result<(), ClearingError> {
+        check_amount(amount)?;
+        let mut shard = self.lock(self.shard_of(id));
+        shard.lookup(id)?.check_margin_post(amount)?;
+        self.books().record_margin_posted(id, amount);
+        if let Some(acc) = shard.get_mut(&id) {
+            acc.balance -= amount;
+            acc.margin_held += amount;
+        }
+        Ok(())
+    }
+
+    /// Return `amount` of margin held to an `Active` account's balance.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the amount is not positive, the account is
+    /// missing or not `Active`, or holds less margin than requested.
+    pub fn release_margin(&self, id: u64, amount: i64) -> Result<(), ClearingError> {
+        check_amount(amount)?;
+        let mut shard = self.lock(self.shard_of(id));
+        shard.lookup(id)?.check_margin_release(amount)?;
+        self.books().record_margin_released(id, amount);
+        if let Some(acc) = shard.get_mut(&id) {
+            acc.margin_held -= amount;
+            acc.balance += amount;
+        }
+        Ok(())
+    }
+
+    /// Apply a waterfall result for a defaulted member, as
+    /// [`ClearingHouse::apply_waterfall`] does.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if the account is missing, is not
+    /// [`AccountStatus::InDefault`], or holds less margin than the
+    /// defaulter-margin layer absorbed.
+    pub fn apply_waterfall(
+        &self,
+        defaulter_id: u64,
+        result: &WaterfallResult,
+    ) -> Result<(), ClearingError> {
+        let absorbed = result.absorbed_by_layer();
+        let margin_draw = absorbed[WaterfallLayer::DefaulterMargin as usize];
+        let mut shard = self.lock(self.shard_of(defaulter_id));
+        shard
+            .lookup(defaulter_id)?
+            .check_waterfall_draw(margin_draw)?;
+        self.books().record_waterfall(defaulter_id, absorbed);
+        if let Some(acc) = shard.get_mut(&defaulter_id) {
+            acc.margin_held -= margin_draw;
+        }
+        Ok(())
+    }
+
+    // ── Clearing ──────────────────────────────────────────────────────
+
+    /// Clear a single net obligation, as [`ClearingHouse::clear_obligation`]
+    /// does.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClearingError`] if an account is missing or not `Active`, or
+    /// the deliverer has insufficient funds.
+    pub fn clear_obligation(&self, obligation: &NetObligation) -> Result<(), ClearingError> {
+        self.with_pair(
+            obligation.deliverer_id,
+            obligation.receiver_id,
+            |accounts| {
+                let outcome = check_settlement(accounts, obligation);
+                self.books().record_obligation(obligation, &outcome);
+                if outcome.is_ok() {
+                    apply_settlement(accounts, obligation);
+                }
+                outcome
+            },
+        )
+    }
+
+    /// Clear a batch in parallel using the available hardware threads.
+    pub fn clear_all(&self, obligations: &[NetObligation]) -> Vec<ClearingResult> {
+        let threads = thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get);
+        self.clear_all_with_threads(obligations, threads)
+    }
+
+    /// Clear a batch in parallel on at most `threads` threads.
+    ///
+    /// Results are returned in batch order and do not depend on `threads`.
+    /// Each obligation is journaled when it settles, so with one thread the
+    /// journal matches [`ClearingHouse::clear_all`] entry for entry; with
+    /// more, obligations with no account in common may be journaled in a
+    /// different order.
+    pub fn clear_all_with_threads(
+        &self,
+        obligations: &[NetObligation],
+        threads: usize,
+    ) -> Vec<ClearingResult> {
+        if threads <= 1 {
+            return obligations
+                .iter()
+                .map(|ob| ClearingHouse::to_result(ob, self.clear_obligation(ob)))
+                .collect();
+        }
+        let mut outcomes: Vec<Result<(), ClearingError>> = vec![Ok(()); obligations.len()];
+
+        for wave in Self::schedule_waves(obligations) {
+            if wave.len() < 2 {
+                for i in wave {
+                    outcomes[i] = self.clear_obligation(&obligations[i]);
+                }
+                continue;
+            }
+            let chunk = wave.len().div_ceil(threads);
+            thread::scope(|scope| {
+                let handles: Vec<_> = wave
+                    .chunks(chunk)
+                    .map(|part| {
+                        scope.spawn(move || {
+                            part.iter()
+                                .map(|&i| (i, self.clear_obligation(&obligations[i])))
+                                .collect::<Vec<_>>()
+                        })
+                    })
+                    .collect();
+                for handle in handles {
+                    let done = handle
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+                    for (i, outcome) in done {
+                        outcomes[i] = outcome;
+                    }
+                }
+            });
+        }
+
+        obligations
+            .iter()
+            .zip(outcomes)
+            .map(|(ob, outcome)| ClearingHouse::to_result(ob, outcome))
+            .collect()
+    }
+
+    /// Partition a batch into waves of obligation indices.
+    ///
+    /// No two obligations in a wave share an account, and each account's
+    /// obligations appear in later waves than its earlier ones, so running
+    /// the waves in order reproduces sequential clearing.
+    #[must_use]
+    pub fn schedule_waves(obligations: &[NetObligation]) -> Vec<Vec<usize>> {
+        let mut next_wave: HashMap<u64, usize> = HashMap::new();
+        let mut waves: Vec<Vec<usize>> = Vec::new();
+        for (i, ob) in obligations.iter().enumerate() {
+            let w = next_wave
+                .get(&ob.deliverer_id)
+                .copied()
+                .unwrap_or(0)
+                .max(next_wave.get(&ob.receiver_id).copied().unwrap_or(0));
+            if waves.len() <= w {
+                waves.push(Vec::new());
+            }
+            waves[w].push(i);
+            next_wave.insert(ob.deliverer_id, w + 1);
+            next_wave.insert(ob.receiver_id, w + 1);
+        }
+        waves
+    }
+
+    // ── Books ─────────────────────────────────────────────────────────
+
+    /// Copy of the settlement journal's entries.
+    #[must_use]
+    pub fn journal_entries(&self) -> Vec<JournalEntry> {
+        self.books().journal.entries().to_vec()
+    }
+
+    /// Balance of a general-ledger account.
+    #[must_use]
+    pub fn ledger_balance(&self, account: LedgerAccount) -> i64 {
+        self.books().ledger.balance(account)
+    }
+
+    /// Cash that has entered or left the members' accounts.
+    #[must_use]
+    pub fn external_flows(&self) -> ExternalFlows {
+        self.books().flows
+    }
+
+    /// Check that no cash was created or destroyed, as
+    /// [`ClearingHouse::audit`] does.
+    #[must_use]
+    pub fn audit(&self) -> AuditReport {
+        let shards = self.lock_all();
+        let books = self.books();
+        BalanceAuditor::audit(
+            shards.iter().flat_map(|shard| shard.values()),
+            &books.ledger,
+            &books.flows,
+        )
+    }
+
+    /// Capture accounts, idempotency keys, house-account ledger balances and
+    /// the journal position, for restoring into either clearing house.
+    #[must_use]
+    pub fn snapshot(&self) -> ClearingSnapshot {
+        let shards = self.lock_all();
+        let accounts = shards
+            .iter()
+            .flat_map(|shard| shard.values().cloned())
+            .collect();
+        self.books().snapshot(accounts)
+    }
+
+    /// Content hash of the current state, equal to the hash of
+    /// [`snapshot`](Self::snapshot).
+    #[must_use]
+    pub fn state_hash(&self) -> u64 {
+        self.snapshot().content_hash
+    }
+}
+
+// ── Tests ──────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn ob(deliverer_id: u64, receiver_id: u64, net_payment: i64) -> NetObligation {
+        NetObligation {
+            symbol_hash: 0x51,
+            deliverer_id,
+            receiver_id,
+            net_quantity: 1,
+            net_payment,
+            trade_count: 1,
+        }
+    }
+
+    /// Deterministic pseudo-random batch over `accounts` members.
+    fn batch(seed: u64, len: usize, accounts: u64) -> Vec<NetObligation> {
+        let mut x = seed | 1;
+        let mut next = move || {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x
+        };
+        (0..len)
+            .map(|_| {
+                let d = next() % accounts;
+                let r = (d + 1 + next() % (accounts - 1)) % accounts;
+                ob(d, r, (next() % 800) as i64)
+            })
+            .collect()
+    }
+
+    fn outcomes(results: &[ClearingResult]) -> Vec<(bool, Option<ClearingError>)> {
+        results
+            .iter()
+            .map(|r| (r.success, r.error.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn register_and_lookup() {
+        let ch = ShardedClearingHouse::new(4);
+        ch.register_account(1, 100).unwrap();
+        ch.register_account(6, 200).unwrap();
+        assert_eq!(ch.get_account(6).unwrap().balance, 200);
+        assert_eq!(ch.account_count(), 2);
+        assert_eq!(
+            ch.register_account(1, 5),
+            Err(ClearingError::AccountExists(1))
+        );
+        assert_eq!(ch.total_funds(), 300);
+        assert!(ch.get_account(2).is_none());
+        assert_eq!(ShardedClearingHouse::new(0).shard_count(), 1);
+    }
+
+    #[test]
+    fn clears_within_and_across_shards() {
+        let ch = ShardedClearingHouse::new(4);
+        for id in [1, 2, 5] {
+            ch.register_account(id, 1_000).unwrap();
+        }
+        assert_eq!(ch.shard_of(1), ch.shard_of(5));
+        assert_ne!(ch.shard_of(1), ch.shard_of(2));
+
+        assert!(ch.clear_obligation(&ob(1, 5, 300)).is_ok());
+        assert!(ch.clear_obligation(&ob(2, 1, 400)).is_ok());
+        assert!(ch.clear_obligation(&ob(5, 2, 100)).is_ok());
+        assert_eq!(ch.get_account(1).unwrap().balance, 1_100);
+        assert_eq!(ch.get_account(2).unwrap().balance, 700);
+        assert_eq!(ch.get_account(5).unwrap().balance, 1_200);
+    }
+
+    #[test]
+    fn errors_match_clearing_house() {
+        let mut seq = ClearingHouse::new();
+        seq.register_account(1, 100).unwrap();
+        seq.register_account(2, 100).unwrap();
+        seq.register_account(3, 100).unwrap();
+        assert!(seq.suspend_account(3).is_ok());
+        let ch = ShardedClearingHouse::from_snapshot(&seq.snapshot(), 3).unwrap();
+
+        for o in [
+            ob(9, 1, 1),
+            ob(1, 9, 1),
+            ob(3, 1, 1),
+            ob(1, 3, 1),
+            ob(1, 2, 500),
+        ] {
+            assert_eq!(ch.clear_obligation(&o), seq.clear_obligation(&o));
+        }
+        assert_eq!(ch.total_funds(), 300);
+    }
+
+    #[test]
+    fn waves_are_conflict_free_and_ordered() {
+        let obligations = batch(7, 200, 10);
+        let waves = ShardedClearingHouse::schedule_waves(&obligations);
+        let mut wave_of = vec![0; obligations.len()];
+        for (w, wave) in waves.iter().enumerate() {
+            let mut seen = std::collections::HashSet::new();
+            for &i in wave {
+                assert!(seen.insert(obligations[i].deliverer_id));
+                assert!(seen.insert(obligations[i].receiver_id));
+                wave_of[i] = w;
+            }
+        }
+        for (i, a) in obligations.iter().enumerate() {
+            for (j, b) in obligations.iter().enumerate().skip(i + 1) {
+                let shared = [a.deliverer_id, a.receiver_id]
+                    .iter()
+                    .any(|id| *id == b.deliverer_id || *id == b.receiver_id);
+                if shared {
+                    assert!(wave_of[i] < wave_of[j]);
+                }
+            }
+        }
+        assert_eq!(waves.iter().map(Vec::len).sum::<usize>(), obligations.len());
+    }
+
+    #[test]
+    fn parallel_matches_sequential() {
+        let obligations = batch(42, 2_000, 64);
+        let mut seq = ClearingHouse::new();
+        for id in 0..64 {
+            seq.register_account(id, 1_000).unwrap();
+        }
+        let expected_state = seq.snapshot();
+        let expected = outcomes(&seq.clear_all(&obligations));
+
+        for threads in [1, 2, 8] {
+            let ch = ShardedClearingHouse::from_snapshot(&expected_state, 8).unwrap();
+            let results = ch.clear_all_with_threads(&obligations, threads);
+            assert_eq!(outcomes(&results), expected);
+            for id in 0..64 {
+                assert_eq!(
+                    ch.get_account(id).unwrap().balance,
+                    seq.get_account(id).unwrap().balance
+                );
+            }
+            assert_eq!(ch.snapshot(), seq.snapshot());
+            if threads == 1 {
+                assert_eq!(
+                    ch.journal_entries(),
+                    &seq.journal().entries()[expected_state.sequence as usize..]
+                );
+            }
+            let replayed =
+                ClearingHouse::restore_with_journal(&expected_state, &ch.journal_entries())
+                    .unwrap();
+            assert_eq!(replayed.state_hash(), seq.state_hash());
+            assert!(ch.audit().is_clean());
+        }
+        assert!(expected.iter().any(|(ok, _)| !ok));
+    }
+
+    #[test]
+    fn books_match_clearing_house() {
+        let seq = &mut ClearingHouse::new();
+        let ch = ShardedClearingHouse::new(3);
+        seq.set_time(5);
+        ch.set_time(5);
+        for id in 1..=4 {
+            assert_eq!(
+                ch.register_account(id, 1_000),
+                seq.register_account(id, 1_000)
+            );
+        }
+        assert_eq!(
+            ch.set_overdraft_limit(2, 300),
+            seq.set_overdraft_limit(2, 300)
+        );
+        seq.set_time(9);
+        ch.set_time(9);
+        assert_eq!(ch.deposit(1, 1, 500), seq.deposit(1, 1, 500));
+        assert_eq!(ch.deposit(1, 1, 500), seq.deposit(1, 1, 500));
+        assert_eq!(ch.deposit(1, 2, 500), seq.deposit(1, 2, 500));
+        assert_eq!(ch.withdraw(2, 2, 1_200), seq.withdraw(2, 2, 1_200));
+        assert_eq!(ch.withdraw(3, 3, 5_000), seq.withdraw(3, 3, 5_000));
+        assert_eq!(ch.transfer(4, 1, 4, 700), seq.transfer(4, 1, 4, 700));
+        assert_eq!(ch.transfer(5, 1, 2, 700), seq.transfer(5, 1, 2, 700));
+        assert_eq!(ch.transfer(6, 3, 3, 1), seq.transfer(6, 3, 3, 1));
+        assert_eq!(ch.charge_fee(3, 0x51, 25), seq.charge_fee(3, 0x51, 25));
+        assert_eq!(ch.charge_fee(9, 0x51, 25), seq.charge_fee(9, 0x51, 25));
+        assert_eq!(ch.charge_fee(4, 0, 10), seq.charge_fee(4, 0, 10));
+        assert_eq!(ch.charge_fee(2, 0, 10_000), seq.charge_fee(2, 0, 10_000));
+        let obligations = batch(3, 40, 4)
+            .into_iter()
+            .map(|mut o| {
+                o.deliverer_id += 1;
+                o.receiver_id += 1;
+                o
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            outcomes(&ch.clear_all_with_threads(&obligations, 4)),
+            outcomes(&seq.clear_all(&obligations))
+        );
+
+        assert_eq!(ch.snapshot(), seq.snapshot());
+        let entries = ch.journal_entries();
+        let replayed =
+            ClearingHouse::restore_with_journal(&ClearingHouse::new().snapshot(), &entries)
+                .unwrap();
+        assert_eq!(replayed.journal().entries(), entries);
+        assert_eq!(replayed.state_hash(), seq.state_hash());
+        assert_eq!(ch.external_flows(), *seq.external_flows());
+        assert_eq!(ch.ledger_balance(LedgerAccount::FeeIncome), 35);
+        assert!(ch.audit().is_clean());
+    }
+
+    #[test]
+    fn lifecycle_and_margin_match_clearing_house() {
+        use crate::waterfall::{DefaultWaterfall, WaterfallConfig};
+
+        let seq = &mut ClearingHouse::new();
+        let ch = ShardedClearingHouse::new(2);
+        for id in 1..=3 {
+            assert_eq!(
+                ch.register_account(id, 1_000),
+                seq.register_account(id, 1_000)
+            );
+        }
+        assert_eq!(ch.post_margin(1, 400), seq.post_margin(1, 400));
+        assert_eq!(ch.post_margin(2, 5_000), seq.post_margin(2, 5_000));
+        assert_eq!(ch.release_margin(1, 100), seq.release_margin(1, 100));
+        assert_eq!(ch.release_margin(1, 900), seq.release_margin(1, 900));
+        assert_eq!(ch.suspend_account(2), seq.suspend_account(2));
+        assert_eq!(ch.release_margin(2, 1), seq.release_margin(2, 1));
+        assert_eq!(ch.reinstate_account(2), seq.reinstate_account(2));
+        assert_eq!(ch.reinstate_account(2), seq.reinstate_account(2));
+        assert_eq!(ch.close_account(3), seq.close_account(3));
+
+        let result = DefaultWaterfall::new(WaterfallConfig {
+            defaulter_margin: 300,
+            ..WaterfallConfig::default()
+        })
+        .absorb_loss(1_000);
+        assert_eq!(
+            ch.apply_waterfall(1, &result),
+            seq.apply_waterfall(1, &result)
+        );
+        assert_eq!(ch.declare_default(1), seq.declare_default(1));
+        assert_eq!(
+            ch.apply_waterfall(1, &result),
+            seq.apply_waterfall(1, &result)
+        );
+        assert_eq!(ch.withdraw(1, 3, 1_000), seq.withdraw(1, 3, 1_000));
+        assert_eq!(ch.close_account(3), seq.close_account(3));
+        assert_eq!(ch.deposit(2, 3, 1), seq.deposit(2, 3, 1));
+
+        assert_eq!(ch.get_account(1).unwrap().margin_held, 0);
+        assert_eq!(ch.snapshot(), seq.snapshot());
+        assert_eq!(ch.journal_entries(), seq.journal().entries());
+        assert_eq!(ch.external_flows(), *seq.external_flows());
+        assert!(ch.audit().is_clean());
+    }
+
+    #[test]
+    fn snapshot_restores_into_clearing_house() {
+        let ch = ShardedClearingHouse::new(3);
+        for id in 0..10 {
+            ch.register_account(id, 500).unwrap();
+        }
+        let _ = ch.clear_all(&batch(3, 100, 10));
+        assert_eq!(ch.deposit(7, 4, 50), Ok(CashOutcome::Applied));
+        let snap = ch.snapshot();
+        assert_eq!(snap.sequence, 111);
+        assert_eq!(snap.cash_requests.len(), 1);
+
+        let mut restored = ClearingHouse::restore(&snap).unwrap();
+        for id in 0..10 {
+            assert_eq!(
+                restored.get_account(id).unwrap().balance,
+                ch.get_account(id).unwrap().balance
+            );
+        }
+        assert_eq!(restored.state_hash(), ch.state_hash());
+        assert!(restored.audit().is_clean());
+        assert_eq!(restored.deposit(7, 4, 50), Ok(CashOutcome::Duplicate));
+
+        let reloaded = ShardedClearingHouse::from_snapshot(&snap, 5).unwrap();
+        assert_eq!(reloaded.state_hash(), ch.state_hash());
+        assert_eq!(reloaded.deposit(7, 4, 50), Ok(CashOutcome::Duplicate));
+        assert!(reloaded.audit().is_clean());
+    }
+
+    #[test]
+    fn high_contention_opposing_transfers() {
+        // Even accounts live in shard 0 and odd ones in shard 1.  Every
+        // transfer goes to the next account, so it crosses shards and holds
+        // both shard locks; threads send in both directions, so inconsistent
+        // lock ordering would deadlock.
+        let ch = Arc::new(ShardedClearingHouse::new(2));
+        for id in 0..4 {
+            ch.register_account(id, 10_000).unwrap();
+        }
+        let start = ch.snapshot();
+        let handles: Vec<_> = (0..8u64)
+            .map(|t| {
+                let ch = Arc::clone(&ch);
+                std::thread::spawn(move || {
+                    let mut applied = 0usize;
+                    for (i, o) in batch(t + 1, 5_000, 4).into_iter().enumerate() {
+                        let ok = if i % 2 == 0 {
+                            ch.clear_obligation(&o).is_ok()
+                        } else {
+                            let to_id = (o.deliverer_id + 1) % 4;
+                            ch.transfer(
+                                t << 32 | i as u64,
+                                o.deliverer_id,
+                                to_id,
+                                o.net_payment + 1,
+                            )
+                            .is_ok()
+                        };
+                        applied += usize::from(ok);
+                    }
+                    applied
+                })
+            })
+            .collect();
+        let applied: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+
+        assert!(applied > 0);
+        assert_eq!(ch.total_funds(), 40_000);
+        for id in 0..4 {
+            assert!(ch.get_account(id).unwrap().balance >= 0);
+        }
+        let replayed = ClearingHouse::restore_with_journal(&start, &ch.journal_entries()).unwrap();
+        assert_eq!(replayed.state_hash(), ch.state_hash());
+        assert!(ch.audit().is_clean());
+    }
+
+    #[test]
+    fn duplicate_key_across_shards_applies_once() {
+        let ch = ShardedClearingHouse::new(4);
+        for id in 0..8 {
+            ch.register_account(id, 1_000).unwrap();
+        }
+        let outcomes: Vec<_> = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..8)
+                .map(|id| {
+                    let ch = &ch;
+                    s.spawn(move || ch.deposit(99, id, 10))
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        let applied = outcomes
+            .iter()
+            .filter(|o| **o == Ok(CashOutcome::Applied))
+            .count();
+        assert_eq!(applied, 1);
+        assert!(outcomes.iter().all(|o| matches!(
+            o,
+            Ok(CashOutcome::Applied) | Err(ClearingError::IdempotencyConflict(99))
+        )));
+        assert_eq!(ch.total_funds(), 8_010);
+    }
+
+    #[test]
+    fn concurrent_batches_conserve_funds() {
+        let ch = ShardedClearingHouse::new(4);
+        for id in 0..16 {
+            ch.register_account(id, 2_000).unwrap();
+        }
+        let start = ch.snapshot();
+        std::thread::scope(|s| {
+            for t in 0..4 {
+                let ch = &ch;
+                s.spawn(move || ch.clear_all_with_threads(&batch(100 + t, 1_000, 16), 4));
+            }
+        });
+        assert_eq!(ch.total_funds(), 32_000);
+        let replayed = ClearingHouse::restore_with_journal(&start, &ch.journal_entries()).unwrap();
+        assert_eq!(replayed.state_hash(), ch.state_hash());
+    }
+
+    // ── Property-based tests ──────────────────────────────────────────
+
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn prop_sharded_equals_sequential(
+            seed in 1u64..u64::MAX,
+            len in 0usize..300,
+            accounts in 2u64..20,
+            shards in 1usize..6,
+            threads in 1usize..5,
+        ) {
+            let obligations = batch(seed, len, accounts);
+            let mut seq = ClearingHouse::new();
+            for id in 0..accounts {
+                seq.register_account(id, 1_000).unwrap();
+            }
+            let ch = ShardedClearingHouse::from_snapshot(&seq.snapshot(), shards).unwrap();
+            let expected = outcomes(&seq.clear_all(&obligations));
+            let actual = outcomes(&ch.clear_all_with_threads(&obligations, threads));
+            prop_assert_eq!(actual, expected);
+            prop_assert_eq!(ch.snapshot(), seq.snapshot());
+            for id in 0..accounts {
+                prop_assert_eq!(
+                    ch.get_account(id).unwrap().balance,
+                    seq.get_account(id).unwrap().balance
+                );
+            }
+        }
+    }
+}
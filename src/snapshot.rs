@@ -0,0 +1,721 @@
+// ALICE-Settlement — Versioned binary snapshots of clearing state
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Moroya Sakamoto
+
+use crate::clearing::{AccountStatus, CashOperation, ClearingAccount, ClearingError};
+use crate::fnv1a;
+use crate::ledger::{GeneralLedger, LedgerAccount, LedgerEntryKind};
+use crate::waterfall::WaterfallLayer;
+
+/// Current snapshot format version.
+pub const SNAPSHOT_VERSION: u16 = 2;
+
+/// Leading bytes of every encoded snapshot.
+const MAGIC: [u8; 4] = *b"ALCS";
+
+/// Encoded size of one account record.
+const ACCOUNT_LEN: usize = 8 + 8 + 8 + 1 + 8;
+
+/// Encoded size of one idempotency record.
+const REQUEST_LEN: usize = 8 + 1 + 8 + 8 + 8;
+
+/// Encoded size of one house-account record.
+const HOUSE_LEN: usize = 1 + 8 + 8;
+
+// ── Types ──────────────────────────────────────────────────────────────
+
+/// Error returned when decoding or restoring a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The input ended before the snapshot was complete.
+    Truncated,
+    /// The input does not start with the snapshot magic bytes.
+    BadMagic,
+    /// The snapshot was written by an unsupported format version.
+    UnsupportedVersion(u16),
+    /// Bytes remain after the snapshot.
+    TrailingBytes(usize),
+    /// The stored content hash does not match the decoded content.
+    HashMismatch { expected: u64, actual: u64 },
+    /// An account record carries an unknown status byte.
+    InvalidStatus(u8),
+    /// An idempotency record carries an unknown operation tag.
+    InvalidCashOperation(u8),
+    /// A house-account record carries an unknown tag or waterfall layer.
+    InvalidHouseAccount { tag: u8, id: u64 },
+    /// Journal entries after the snapshot are not contiguous.
+    SequenceGap { expected: u64, found: u64 },
+    /// Re-applying a journal entry failed.
+    ReplayFailed { sequence: u64, error: ClearingError },
+    /// Re-applying a journal entry produced a different entry.
+    ReplayDiverged { sequence: u64 },
+}
+
+/// Point-in-time copy of a clearing house's state.
+///
+/// Accounts are ordered by identifier, idempotency records by key and house
+/// balances by ledger account, so the encoding and
+/// [`content_hash`](Self::content_hash) are deterministic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClearingSnapshot {
+    /// Format version.
+    pub version: u16,
+    /// Last journal sequence reflected in the snapshot (0 if none).
+    pub sequence: u64,
+    /// Clearing-house clock when the snapshot was taken.
+    pub timestamp_ns: u64,
+    /// Every account, ordered by `account_id`.
+    pub accounts: Vec<ClearingAccount>,
+    /// Applied cash operations by idempotency key, ordered by key.
+    pub cash_requests: Vec<(u64, CashOperation)>,
+    /// Non-zero house-account ledger balances (fee income, waterfall layers,
+    /// default losses), ordered by account.
+    pub house_balances: Vec<(LedgerAccount, i64)>,
+    /// FNV-1a hash of the encoded content.
+    pub content_hash: u64,
+}
+
+// ── Encoding ───────────────────────────────────────────────────────────
+
+impl ClearingSnapshot {
+    /// Build a snapshot, sorting its contents and computing the hash.
+    ///
+    /// Member and external accounts in `house_balances` are dropped, as are
+    /// zero balances; member funds are carried by `accounts`.
+    #[must_use]
+    pub fn new(
+        sequence: u64,
+        timestamp_ns: u64,
+        mut accounts: Vec<ClearingAccount>,
+        mut cash_requests: Vec<(u64, CashOperation)>,
+        mut house_balances: Vec<(LedgerAccount, i64)>,
+    ) -> Self {
+        accounts.sort_unstable_by_key(|a| a.account_id);
+        cash_requests.sort_unstable_by_key(|&(key, _)| key);
+        house_balances.retain(|&(acc, b)| b != 0 && encode_house(acc).is_some());
+        house_balances.sort_unstable_by_key(|&(acc, _)| acc);
+        let mut snapshot = Self {
+            version: SNAPSHOT_VERSION,
+            sequence,
+            timestamp_ns,
+            accounts,
+            cash_requests,
+            house_balances,
+            content_hash: 0,
+        };
+        snapshot.content_hash = snapshot.computed_hash();
+        snapshot
+    }
+
+    /// True when the stored hash matches the content.
+    #[must_use]
+    pub fn verify(&self) -> bool {
+        self.computed_hash() == self.content_hash
+    }
+
+    /// Check the stored hash against the content.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SnapshotError::HashMismatch`] when the content was modified
+    /// after the hash was computed.
+    pub fn check(&self) -> Result<(), SnapshotError> {
+        let actual = self.computed_hash();
+        if actual != self.content_hash {
+            return Err(SnapshotError::HashMismatch {
+                expected: self.content_hash,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    fn computed_hash(&self) -> u64 {
+        fnv1a(&self.encode_content())
+    }
+
+    /// Ledger re-opened with one adjustment per member balance, margin held
+    /// and house-account balance.
+    pub(crate) fn opening_ledger(&self) -> GeneralLedger {
+        let mut ledger = GeneralLedger::new();
+        for acc in &self.accounts {
+            ledger.post(
+                LedgerEntryKind::Adjustment,
+                LedgerAccount::MemberCash(acc.account_id),
+                LedgerAccount::External,
+                acc.balance,
+            );
+            ledger.post(
+                LedgerEntryKind::Adjustment,
+                LedgerAccount::MemberMargin(acc.account_id),
+                LedgerAccount::External,
+                acc.margin_held,
+            );
+        }
+        for &(account, balance) in &self.house_balances {
+            ledger.post(
+                LedgerEntryKind::Adjustment,
+                account,
+                LedgerAccount::External,
+                balance,
+            );
+        }
+        ledger
+    }
+
+    /// Balance plus margin held across every account.
+    pub(crate) fn member_funds(&self) -> i128 {
+        self.accounts
+            .iter()
+            .map(|acc| acc.balance as i128 + acc.margin_held as i128)
+            .sum()
+    }
+
+    /// Encode as `magic | content | content_hash`, little-endian.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let content = self.encode_content();
+        let mut out = Vec::with_capacity(MAGIC.len() + content.len() + 8);
+        out.extend_from_slice(&MAGIC);
+        out.extend_from_slice(&content);
+        out.extend_from_slice(&self.content_hash.to_le_bytes());
+        out
+    }
+
+    /// Decode and verify a snapshot produced by [`to_bytes`](Self::to_bytes).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SnapshotError`] if the input is malformed, of another
+    /// version, or fails its integrity check.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let mut r = Reader { bytes, pos: 0 };
+        if r.take(MAGIC.len())? != MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+        let version = u16::from_le_bytes(r.array()?);
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let sequence = r.u64()?;
+        let timestamp_ns = r.u64()?;
+
+        let account_count = r.u32()? as usize;
+        r.check_remaining(account_count, ACCOUNT_LEN)?;
+        let mut accounts = Vec::with_capacity(account_count);
+        for _ in 0..account_count {
+            let account_id = r.u64()?;
+            let balance = r.i64()?;
+            let margin_held = r.i64()?;
+            let status = decode_status(r.u8()?)?;
+            let overdraft_limit = r.i64()?;
+            accounts.push(ClearingAccount {
+                account_id,
+                balance,
+                margin_held,
+                status,
+                overdraft_limit,
+            });
+        }
+
+        let request_count = r.u32()? as usize;
+        r.check_remaining(request_count, REQUEST_LEN)?;
+        let mut cash_requests = Vec::with_capacity(request_count);
+        for _ in 0..request_count {
+            let key = r.u64()?;
+            let tag = r.u8()?;
+            let a = r.u64()?;
+            let b = r.u64()?;
+            let amount = r.i64()?;
+            let op = match tag {
+                0 => CashOperation::Deposit {
+                    account_id: a,
+                    amount,
+                },
+                1 => CashOperation::Withdraw {
+                    account_id: a,
+                    amount,
+                },
+                2 => CashOperation::Transfer {
+                    from_id: a,
+                    to_id: b,
+                    amount,
+                },
+                other => return Err(SnapshotError::InvalidCashOperation(other)),
+            };
+            cash_requests.push((key, op));
+        }
+
+        let house_count = r.u32()? as usize;
+        r.check_remaining(house_count, HOUSE_LEN)?;
+        let mut house_balances = Vec::with_capacity(house_count);
+        for _ in 0..house_count {
+            let tag = r.u8()?;
+            let id = r.u64()?;
+            let balance = r.i64()?;
+            house_balances.push((decode_house(tag, id)?, balance));
+        }
+
+        let content_end = r.pos;
+        let expected = r.u64()?;
+        if r.pos != bytes.len() {
+            return Err(SnapshotError::TrailingBytes(bytes.len() - r.pos));
+        }
+        let actual = fnv1a(&bytes[MAGIC.len()..content_end]);
+        if actual != expected {
+            return Err(SnapshotError::HashMismatch { expected, actual });
+        }
+
+        Ok(Self {
+            version,
+            sequence,
+            timestamp_ns,
+            accounts,
+            cash_requests,
+            house_balances,
+            content_hash: expected,
+        })
+    }
+
+    fn encode_content(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(
+            2 + 8
+                + 8
+                + 4
+                + self.accounts.len() * ACCOUNT_LEN
+                + 4
+                + self.cash_requests.len() * REQUEST_LEN
+                + 4
+                + self.house_balances.len() * HOUSE_LEN,
+        );
+        out.extend_from_slice(&self.version.to_le_bytes());
+        out.extend_from_slice(&self.sequence.to_le_bytes());
+        out.extend_from_slice(&self.timestamp_ns.to_le_bytes());
+
+        out.extend_from_slice(&(self.accounts.len() as u32).to_le_bytes());
+        for acc in &self.accounts {
+            out.extend_from_slice(&acc.account_id.to_le_bytes());
+            out.extend_from_slice(&acc.balance.to_le_bytes());
+            out.extend_from_slice(&acc.margin_held.to_le_bytes());
+            out.push(acc.status as u8);
+            out.extend_from_slice(&acc.overdraft_limit.to_le_bytes());
+        }
+
+        out.extend_from_slice(&(self.cash_requests.len() as u32).to_le_bytes());
+        for &(key, op) in &self.cash_requests {
+            let (tag, a, b, amount) = match op {
+                CashOperation::Deposit { account_id, amount } => (0u8, account_id, 0, amount),
+                CashOperation::Withdraw { account_id, amount } => (1, account_id, 0, amount),
+                CashOperation::Transfer {
+                    from_id,
+                    to_id,
+                    amount,
+                } => (2, from_id, to_id, amount),
+            };
+            out.extend_from_slice(&key.to_le_bytes());
+            out.push(tag);
+            out.extend_from_slice(&a.to_le_bytes());
+            out.extend_from_slice(&b.to_le_bytes());
+            out.extend_from_slice(&amount.to_le_bytes());
+        }
+
+        out.extend_from_slice(&(self.house_balances.len() as u32).to_le_bytes());
+        for &(acc, balance) in &self.house_balances {
+            let (tag, id) = encode_house(acc).unwrap_or((u8::MAX, 0));
+            out.push(tag);
+            out.extend_from_slice(&id.to_le_bytes());
+            out.extend_from_slice(&balance.to_le_bytes());
+        }
+        out
+    }
+}
+
+/// Tag and identifier of a house account, or `None` for member and
+/// external accounts.
+const fn encode_house(acc: LedgerAccount) -> Option<(u8, u64)> {
+    match acc {
+        LedgerAccount::FeeIncome => Some((0, 0)),
+        LedgerAccount::Waterfall(layer) => Some((1, layer as u64)),
+        LedgerAccount::DefaultLoss(id) => Some((2, id)),
+        LedgerAccount::External | LedgerAccount::MemberCash(_) | LedgerAccount::MemberMargin(_) => {
+            None
+        }
+    }
+}
+
+const fn decode_house(tag: u8, id: u64) -> Result<LedgerAccount, SnapshotError> {
+    let acc = match (tag, id) {
+        (0, 0) => LedgerAccount::FeeIncome,
+        (1, 0) => LedgerAccount::Waterfall(WaterfallLayer::DefaulterMargin),
+        (1, 1) => LedgerAccount::Waterfall(WaterfallLayer::DefaulterFund),
+        (1, 2) => LedgerAccount::Waterfall(WaterfallLayer::CcpFirstLoss),
+        (1, 3) => LedgerAccount::Waterfall(WaterfallLayer::MembersFund),
+        (1, 4) => LedgerAccount::Waterfall(WaterfallLayer::CcpCapital),
+        (2, id) => LedgerAccount::DefaultLoss(id),
+        _ => return Err(SnapshotError::InvalidHouseAccount { tag, id }),
+    };
+    Ok(acc)
+}
+
+const fn decode_status(byte: u8) -> Result<AccountStatus, SnapshotError> {
+    match byte {
+        0 => Ok(AccountStatus::Active),
+        1 => Ok(AccountStatus::Suspended),
+        2 => Ok(AccountStatus::InDefault),
+        3 => Ok(AccountStatus::Closed),
+        other => Err(SnapshotError::InvalidStatus(other)),
+    }
+}
+
+/// Little-endian cursor over a byte slice.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], SnapshotError> {
+        let end = self.pos.checked_add(n).ok_or(SnapshotError::Truncated)?;
+        let slice = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or(SnapshotError::Truncated)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], SnapshotError> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    /// Reject counts that cannot fit in the remaining input before
+    /// allocating for them.
+    fn check_remaining(&self, count: usize, record_len: usize) -> Result<(), SnapshotError> {
+        let needed = count
+            .checked_mul(record_len)
+            .ok_or(SnapshotError::Truncated)?;
+        if needed > self.bytes.len() - self.pos {
+            return Err(SnapshotError::Truncated);
+        }
+        Ok(())
+    }
+
+    fn u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, SnapshotError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn i64(&mut self) -> Result<i64, SnapshotError> {
+        Ok(i64::from_le_bytes(self.array()?))
+    }
+}
+
+// ── Tests ──────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clearing::ClearingHouse;
+    use crate::netting::NetObligation;
+
+    fn ob(deliverer_id: u64, receiver_id: u64, net_payment: i64) -> NetObligation {
+        NetObligation {
+            symbol_hash: 0xAB,
+            deliverer_id,
+            receiver_id,
+            net_quantity: 5,
+            net_payment,
+            trade_count: 1,
+        }
+    }
+
+    fn busy_house() -> ClearingHouse {
+        let mut ch = ClearingHouse::new();
+        ch.set_time(10);
+        ch.register_account(1, 10_000).unwrap();
+        ch.register_account(2, 5_000).unwrap();
+        ch.register_account(3, 0).unwrap();
+        let _ = ch.set_overdraft_limit(2, 500);
+        let _ = ch.deposit(1, 3, 250);
+        let _ = ch.transfer(2, 2, 3, 5_200);
+        let _ = ch.clear_obligation(&ob(1, 2, 1_000));
+        let _ = ch.post_margin(1, 2_000);
+        let _ = ch.suspend_account(3);
+        ch
+    }
+
+    /// Activity after the snapshot in `busy_house`.
+    fn more_activity(ch: &mut ClearingHouse) {
+        ch.set_time(20);
+        let _ = ch.reinstate_account(3);
+        let _ = ch.withdraw(3, 3, 100);
+        let _ = ch.clear_obligation(&ob(2, 1, 10_000)); // fails
+        let _ = ch.release_margin(1, 500);
+        let _ = ch.charge_fee(1, 0xAB, 25);
+        let _ = ch.declare_default(2);
+        let _ = ch.set_overdraft_limit(1, 1_000);
+    }
+
+    #[test]
+    fn round_trip_bytes() {
+        let snap = busy_house().snapshot();
+        assert!(snap.verify());
+        let decoded = ClearingSnapshot::from_bytes(&snap.to_bytes()).unwrap();
+        assert_eq!(decoded, snap);
+    }
+
+    #[test]
+    fn restore_reproduces_state() {
+        let ch = busy_house();
+        let restored = ClearingHouse::restore(&ch.snapshot()).unwrap();
+        assert_eq!(restored.state_hash(), ch.state_hash());
+        assert_eq!(
+            restored.get_account(3).unwrap().status,
+            AccountStatus::Suspended
+        );
+        assert_eq!(restored.get_account(2).unwrap().overdraft_limit, 500);
+        assert_eq!(
+            restored.journal().next_sequence(),
+            ch.journal().next_sequence()
+        );
+        assert!(restored.reconcile_ledger().is_empty());
+    }
+
+    #[test]
+    fn restore_with_journal_catches_up() {
+        let mut ch = busy_house();
+        let snap = ch.snapshot();
+        more_activity(&mut ch);
+
+        let restored = ClearingHouse::restore_with_journal(&snap, ch.journal().entries()).unwrap();
+        assert_eq!(restored.state_hash(), ch.state_hash());
+        assert_eq!(
+            restored.get_account(2).unwrap().status,
+            AccountStatus::InDefault
+        );
+        assert!(restored.reconcile_ledger().is_empty());
+        let replayed = &ch.journal().entries()[snap.sequence as usize..];
+        assert_eq!(restored.journal().entries(), replayed);
+    }
+
+    #[test]
+    fn restored_idempotency_keys_survive() {
+        let mut restored = ClearingHouse::restore(&busy_house().snapshot()).unwrap();
+        assert_eq!(
+            restored.deposit(1, 3, 250),
+            Ok(crate::clearing::CashOutcome::Duplicate)
+        );
+        assert_eq!(
+            restored.deposit(1, 3, 999),
+            Err(ClearingError::IdempotencyConflict(1))
+        );
+    }
+
+    #[test]
+    fn restore_with_journal_rejects_gap() {
+        let mut ch = busy_house();
+        let snap = ch.snapshot();
+        more_activity(&mut ch);
+        let tail = &ch.journal().entries()[snap.sequence as usize + 1..];
+        assert_eq!(
+            ClearingHouse::restore_with_journal(&snap, tail).err(),
+            Some(SnapshotError::SequenceGap {
+                expected: snap.sequence + 1,
+                found: snap.sequence + 2,
+            })
+        );
+    }
+
+    #[test]
+    fn restore_with_journal_detects_divergence() {
+        let mut ch = busy_house();
+        let snap = ch.snapshot();
+        more_activity(&mut ch);
+        let mut tail: Vec<_> = ch.journal().entries()[snap.sequence as usize..].to_vec();
+        // Tamper with the withdrawal: the account cannot afford it.
+        if let crate::journal::JournalEvent::CashWithdrawn { amount, .. } = &mut tail[1].event {
+            *amount = 1_000_000;
+        }
+        assert!(matches!(
+            ClearingHouse::restore_with_journal(&snap, &tail),
+            Err(SnapshotError::ReplayFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn corrupted_bytes_rejected() {
+        let bytes = busy_house().snapshot().to_bytes();
+
+        let mut flipped = bytes.clone();
+        flipped[20] ^= 0xFF;
+        assert!(matches!(
+            ClearingSnapshot::from_bytes(&flipped),
+            Err(SnapshotError::HashMismatch { .. })
+        ));
+
+        assert_eq!(
+            ClearingSnapshot::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(SnapshotError::Truncated)
+        );
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            ClearingSnapshot::from_bytes(&trailing),
+            Err(SnapshotError::TrailingBytes(1))
+        );
+
+        let mut magic = bytes.clone();
+        magic[0] = b'X';
+        assert_eq!(
+            ClearingSnapshot::from_bytes(&magic),
+            Err(SnapshotError::BadMagic)
+        );
+
+        let mut version = bytes;
+        version[4] = 9;
+        assert_eq!(
+            ClearingSnapshot::from_bytes(&version),
+            Err(SnapshotError::UnsupportedVersion(9))
+        );
+    }
+
+    #[test]
+    fn restore_keeps_house_balances() {
+        use crate::ledger::LedgerAccount;
+        use crate::waterfall::{DefaultWaterfall, WaterfallConfig};
+
+        let mut ch = busy_house();
+        more_activity(&mut ch);
+        let result = DefaultWaterfall::new(WaterfallConfig {
+            defaulter_margin: 0,
+            ..WaterfallConfig::default()
+        })
+        .absorb_loss(300);
+        ch.apply_waterfall(2, &result).unwrap();
+        let snap = ch.snapshot();
+        assert!(!snap.house_balances.is_empty());
+        assert_eq!(
+            ClearingSnapshot::from_bytes(&snap.to_bytes()).unwrap(),
+            snap
+        );
+
+        let restored = ClearingHouse::restore(&snap).unwrap();
+        assert_eq!(restored.state_hash(), ch.state_hash());
+        for acc in [
+            LedgerAccount::FeeIncome,
+            LedgerAccount::DefaultLoss(2),
+            LedgerAccount::Waterfall(crate::waterfall::WaterfallLayer::DefaulterFund),
+        ] {
+            assert_eq!(restored.ledger().balance(acc), ch.ledger().balance(acc));
+        }
+        assert_eq!(restored.ledger().balance(LedgerAccount::FeeIncome), 25);
+        assert_eq!(restored.ledger().trial_balance(), 0);
+    }
+
+    #[test]
+    fn restore_with_journal_replays_waterfall() {
+        use crate::ledger::LedgerAccount;
+        use crate::waterfall::{DefaultWaterfall, WaterfallConfig, WaterfallLayer};
+
+        let mut ch = ClearingHouse::new();
+        ch.register_account(1, 10_000).unwrap();
+        ch.post_margin(1, 1_000).unwrap();
+        ch.declare_default(1).unwrap();
+        let snap = ch.snapshot();
+
+        let result = DefaultWaterfall::new(WaterfallConfig {
+            defaulter_margin: 1_000,
+            ..WaterfallConfig::default()
+        })
+        .absorb_loss(3_000);
+        ch.apply_waterfall(1, &result).unwrap();
+        assert_eq!(
+            ch.ledger().house_balances(),
+            vec![
+                (
+                    LedgerAccount::Waterfall(WaterfallLayer::DefaulterFund),
+                    -2_000
+                ),
+                (LedgerAccount::DefaultLoss(1), 3_000),
+            ]
+        );
+
+        let restored = ClearingHouse::restore_with_journal(&snap, ch.journal().entries()).unwrap();
+        assert_eq!(restored.state_hash(), ch.state_hash());
+        assert_eq!(
+            restored.ledger().house_balances(),
+            ch.ledger().house_balances()
+        );
+        assert_eq!(restored.get_account(1).unwrap().margin_held, 0);
+        assert!(restored.reconcile_ledger().is_empty());
+        assert!(restored.audit().is_clean());
+    }
+
+    #[test]
+    fn restore_rejects_tampered_snapshot() {
+        let mut snap = busy_house().snapshot();
+        snap.accounts[0].balance += 1;
+        assert!(!snap.verify());
+        assert!(matches!(
+            ClearingHouse::restore(&snap),
+            Err(SnapshotError::HashMismatch { .. })
+        ));
+        assert!(matches!(
+            ClearingHouse::restore_with_journal(&snap, &[]),
+            Err(SnapshotError::HashMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn invalid_house_account_rejected() {
+        let mut snap = busy_house().snapshot();
+        snap.house_balances.push((LedgerAccount::FeeIncome, 5));
+        let mut bytes = snap.to_bytes();
+        // Point the fee-income record at a non-existent waterfall layer.
+        let record = bytes.len() - 8 - HOUSE_LEN;
+        bytes[record] = 1;
+        bytes[record + 1] = 9;
+        let hash = fnv1a(&bytes[MAGIC.len()..bytes.len() - 8]);
+        let len = bytes.len();
+        bytes[len - 8..].copy_from_slice(&hash.to_le_bytes());
+        assert_eq!(
+            ClearingSnapshot::from_bytes(&bytes),
+            Err(SnapshotError::InvalidHouseAccount { tag: 1, id: 9 })
+        );
+    }
+
+    #[test]
+    fn huge_counts_do_not_allocate() {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&[0u8; 16]);
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            ClearingSnapshot::from_bytes(&bytes),
+            Err(SnapshotError::Truncated)
+        );
+    }
+
+    #[test]
+    fn hash_independent_of_insertion_order() {
+        let mut a = ClearingHouse::new();
+        a.register_account(1, 100).unwrap();
+        a.register_account(2, 200).unwrap();
+        let mut b = ClearingHouse::new();
+        b.register_account(2, 200).unwrap();
+        b.register_account(1, 100).unwrap();
+        assert_eq!(a.state_hash(), b.state_hash());
+        b.register_account(3, 0).unwrap();
+        assert_ne!(a.state_hash(), b.state_hash());
+    }
+}
@@ -0,0 +1,891 @@
+// ALICE-Settlement — SPAN portfolio margin with 16-scenario risk arrays
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Moroya Sakamoto
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::fnv1a;
+use crate::margin::{MarginModel, MarginModelError, MarginRequirement, RoundingMode, RATE_SCALE};
+use crate::netting::NetObligation;
+
+// ── Scenarios ──────────────────────────────────────────────────────────
+
+/// Number of scenarios in a SPAN risk array.
+pub const SCENARIO_COUNT: usize = 16;
+
+/// One SPAN risk scenario.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpanScenario {
+    /// Price move in thirds of the scanning range (`3` = one range).
+    pub price_move_thirds: i32,
+    /// Volatility move as a multiple of the volatility range (-1, 0 or +1).
+    pub vol_move: i32,
+    /// Fraction of the scenario loss that counts towards the risk array, in
+    /// [`RATE_SCALE`] fixed point.
+    pub weight: i64,
+}
+
+const fn scenario(price_move_thirds: i32, vol_move: i32, weight: i64) -> SpanScenario {
+    SpanScenario {
+        price_move_thirds,
+        vol_move,
+        weight,
+    }
+}
+
+/// Weight of the extreme-move scenarios (35%).
+const EXTREME_WEIGHT: i64 = RATE_SCALE / 100 * 35;
+
+/// The standard SPAN scenarios: price unchanged and ±⅓, ±⅔, ±1 scanning
+/// range, each with volatility up and down, then two extreme moves of three
+/// scanning ranges counted at 35%.
+pub const SPAN_SCENARIOS: [SpanScenario; SCENARIO_COUNT] = [
+    scenario(0, 1, RATE_SCALE),
+    scenario(0, -1, RATE_SCALE),
+    scenario(1, 1, RATE_SCALE),
+    scenario(1, -1, RATE_SCALE),
+    scenario(-1, 1, RATE_SCALE),
+    scenario(-1, -1, RATE_SCALE),
+    scenario(2, 1, RATE_SCALE),
+    scenario(2, -1, RATE_SCALE),
+    scenario(-2, 1, RATE_SCALE),
+    scenario(-2, -1, RATE_SCALE),
+    scenario(3, 1, RATE_SCALE),
+    scenario(3, -1, RATE_SCALE),
+    scenario(-3, 1, RATE_SCALE),
+    scenario(-3, -1, RATE_SCALE),
+    scenario(9, 0, EXTREME_WEIGHT),
+    scenario(-9, 0, EXTREME_WEIGHT),
+];
+
+// ── Parameters ─────────────────────────────────────────────────────────
+
+/// Risk parameters for a combined commodity (all instruments on one
+/// underlying).
+#[derive(Debug, Clone)]
+pub struct SpanCommodity {
+    /// Combined commodity identifier.
+    pub commodity_id: u64,
+    /// Underlying price move covered, in price ticks.
+    pub scanning_range: i64,
+    /// Implied volatility move covered, in bps of volatility
+    /// (400 = 4 volatility points).
+    pub volatility_range_bps: u32,
+    /// Contract multiplier (value per price tick per contract).
+    pub multiplier: i64,
+    /// Charge per delta spread between tiers.
+    pub intra_spread_charge: i64,
+    /// Minimum charge per short option contract.
+    pub short_option_minimum: i64,
+}
+
+/// Instrument payoff.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpanProduct {
+    /// Future on the underlying.
+    Future,
+    /// European option on the future, valued with Black-76.
+    Option {
+        /// True for a call, false for a put.
+        is_call: bool,
+        /// Strike, in price ticks.
+        strike: i64,
+        /// Implied volatility, in bps (2000 = 20%).
+        volatility_bps: u32,
+        /// Time to expiry, in years.
+        time_to_expiry: f64,
+    },
+}
+
+/// A margined instrument.
+#[derive(Debug, Clone)]
+pub struct SpanInstrument {
+    /// Instrument identifier.
+    pub instrument_id: u64,
+    /// Combined commodity the instrument belongs to.
+    pub commodity_id: u64,
+    /// Tier (typically the expiry month) used for intra-commodity spreads.
+    pub tier: u32,
+    /// Payoff.
+    pub product: SpanProduct,
+    /// Underlying (futures) price, in price ticks.
+    pub underlying_price: i64,
+}
+
+/// Inter-commodity spread credit between two combined commodities.
+///
+/// Ratios and the credit rate are in [`RATE_SCALE`] fixed point.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterCommoditySpread {
+    /// First leg.
+    pub commodity_a: u64,
+    /// Second leg.
+    pub commodity_b: u64,
+    /// Deltas of `commodity_a` per spread (positive).
+    pub delta_ratio_a: i64,
+    /// Deltas of `commodity_b` per spread (positive).
+    pub delta_ratio_b: i64,
+    /// Fraction of each leg's price risk credited per spread
+    /// (`0..=RATE_SCALE`).
+    pub credit_rate: i64,
+}
+
+/// A position in an instrument (positive = long).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpanPosition {
+    /// Instrument identifier.
+    pub instrument_id: u64,
+    /// Signed number of contracts.
+    pub quantity: i64,
+}
+
+// ── Risk Array ─────────────────────────────────────────────────────────
+
+/// Scenario losses for one long contract (positive = loss) and its delta.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RiskArray {
+    /// Loss per scenario, in the order of [`SPAN_SCENARIOS`].
+    pub losses: [i64; SCENARIO_COUNT],
+    /// Delta per contract in futures equivalents, in [`RATE_SCALE`] fixed
+    /// point (a future is `RATE_SCALE`).
+    pub delta: i64,
+}
+
+impl RiskArray {
+    /// Compute the risk array of `instrument` under `commodity` parameters.
+    ///
+    /// Instruments are priced in floating point; each scenario's value change
+    /// is rounded to whole ticks before the scenario weight is applied, and
+    /// the delta is rounded to fixed point, so everything downstream of the
+    /// risk array is integer arithmetic.
+    #[must_use]
+    pub fn compute(instrument: &SpanInstrument, commodity: &SpanCommodity) -> Self {
+        let f = instrument.underlying_price as f64;
+        let range = commodity.scanning_range as f64;
+        let vol_range = commodity.volatility_range_bps as f64 / 10_000.0;
+        let multiplier = commodity.multiplier as f64;
+
+        let (base, delta) = match instrument.product {
+            SpanProduct::Future => (f, 1.0),
+            SpanProduct::Option {
+                is_call,
+                strike,
+                volatility_bps,
+                time_to_expiry,
+            } => {
+                let vol = volatility_bps as f64 / 10_000.0;
+                (
+                    black76(is_call, f, strike as f64, vol, time_to_expiry),
+                    black76_delta(is_call, f, strike as f64, vol, time_to_expiry),
+                )
+            }
+        };
+
+        let mut losses = [0_i64; SCENARIO_COUNT];
+        for (loss, s) in losses.iter_mut().zip(SPAN_SCENARIOS.iter()) {
+            let shifted = (f + f64::from(s.price_move_thirds) * range / 3.0).max(0.0);
+            let value = match instrument.product {
+                SpanProduct::Future => shifted,
+                SpanProduct::Option {
+                    is_call,
+                    strike,
+                    volatility_bps,
+                    time_to_expiry,
+                } => {
+                    let vol = (volatility_bps as f64 / 10_000.0
+                        + f64::from(s.vol_move) * vol_range)
+                        .max(0.0);
+                    black76(is_call, shifted, strike as f64, vol, time_to_expiry)
+                }
+            };
+            let unweighted = ((base - value) * multiplier).round() as i64;
+            *loss = RoundingMode::HalfUp.apply(unweighted, s.weight);
+        }
+        Self {
+            losses,
+            delta: (delta * RATE_SCALE as f64).round() as i64,
+        }
+    }
+
+    /// Risk array of `quantity` contracts.
+    #[must_use]
+    pub fn scaled(&self, quantity: i64) -> [i64; SCENARIO_COUNT] {
+        self.losses.map(|l| l.saturating_mul(quantity))
+    }
+}
+
+/// Standard normal CDF (Abramowitz–Stegun 7.1.26, error < 1.5e-7).
+fn norm_cdf(x: f64) -> f64 {
+    let z = x.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.327_591_1 * z);
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let erf = 1.0 - poly * (-z * z).exp();
+    if x >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+fn black76_d1(f: f64, k: f64, vol: f64, t: f64) -> Option<f64> {
+    let sd = vol * t.sqrt();
+    (f > 0.0 && k > 0.0 && sd > 0.0).then(|| ((f / k).ln() + 0.5 * sd * sd) / sd)
+}
+
+/// Black-76 option value (zero rates); intrinsic value when undefined.
+fn black76(is_call: bool, f: f64, k: f64, vol: f64, t: f64) -> f64 {
+    match black76_d1(f, k, vol, t) {
+        Some(d1) => {
+            let d2 = d1 - vol * t.sqrt();
+            if is_call {
+                f * norm_cdf(d1) - k * norm_cdf(d2)
+            } else {
+                k * norm_cdf(-d2) - f * norm_cdf(-d1)
+            }
+        }
+        None if is_call => (f - k).max(0.0),
+        None => (k - f).max(0.0),
+    }
+}
+
+fn black76_delta(is_call: bool, f: f64, k: f64, vol: f64, t: f64) -> f64 {
+    let d1 = black76_d1(f, k, vol, t);
+    match (is_call, d1) {
+        (true, Some(d1)) => norm_cdf(d1),
+        (false, Some(d1)) => norm_cdf(d1) - 1.0,
+        (true, None) => f64::from(u8::from(f > k)),
+        (false, None) => -f64::from(u8::from(f < k)),
+    }
+}
+
+// ── Results ────────────────────────────────────────────────────────────
+
+/// Margin components for one combined commodity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommodityRisk {
+    /// Combined commodity identifier.
+    pub commodity_id: u64,
+    /// Portfolio risk array (sum over positions).
+    pub risk_array: [i64; SCENARIO_COUNT],
+    /// Largest scenario loss, floored at zero.
+    pub scan_risk: i64,
+    /// Index of the scenario producing the scan risk.
+    pub worst_scenario: usize,
+    /// Net delta in futures equivalents, in [`RATE_SCALE`] fixed point.
+    pub net_delta: i64,
+    /// Intra-commodity (inter-tier) spread charge.
+    pub intra_spread_charge: i64,
+    /// Inter-commodity spread credit.
+    pub inter_spread_credit: i64,
+    /// Short option minimum.
+    pub short_option_minimum: i64,
+    /// `max(scan + intra − inter, short option minimum)`.
+    pub total: i64,
+}
+
+/// SPAN margin for one account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpanResult {
+    /// Account identifier.
+    pub account_id: u64,
+    /// Per-commodity breakdown, ordered by commodity ID.
+    pub commodities: Vec<CommodityRisk>,
+    /// Sum of commodity totals.
+    pub total_margin: i64,
+}
+
+impl SpanResult {
+    /// Convert to a [`MarginRequirement`] (the SPAN total is reported as
+    /// initial margin).
+    #[must_use]
+    pub fn to_requirement(&self) -> MarginRequirement {
+        let mut data = [0u8; 16];
+        data[0..8].copy_from_slice(&self.account_id.to_le_bytes());
+        data[8..16].copy_from_slice(&self.total_margin.to_le_bytes());
+        MarginRequirement {
+            account_id: self.account_id,
+            initial_margin: self.total_margin,
+            variation_margin: 0,
+            stress_margin: 0,
+            total_margin: self.total_margin,
+            content_hash: fnv1a(&data),
+        }
+    }
+}
+
+/// Error returned by [`SpanEngine::compute`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpanError {
+    /// A position refers to an instrument that was never added.
+    UnknownInstrument(u64),
+    /// An instrument refers to a commodity that was never added.
+    UnknownCommodity(u64),
+    /// An inter-commodity spread has a credit rate outside
+    /// `0..=RATE_SCALE` or a non-positive delta ratio.
+    InvalidInterSpread { commodity_a: u64, commodity_b: u64 },
+}
+
+// ── Engine ─────────────────────────────────────────────────────────────
+
+/// SPAN portfolio margin engine.
+///
+/// Risk arrays are computed once when an instrument is added (or supplied
+/// from a published risk parameter file with
+/// [`set_risk_array`](Self::set_risk_array)) and scaled by position size.
+#[derive(Debug, Clone, Default)]
+pub struct SpanEngine {
+    commodities: HashMap<u64, SpanCommodity>,
+    instruments: HashMap<u64, (SpanInstrument, RiskArray)>,
+    inter_spreads: Vec<InterCommoditySpread>,
+}
+
+#[derive(Default)]
+struct CommodityAccumulator {
+    risk_array: [i64; SCENARIO_COUNT],
+    net_delta: i64,
+    tier_deltas: BTreeMap<u32, i64>,
+    short_options: i64,
+}
+
+impl SpanEngine {
+    /// Create an empty engine.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace a combined commodity.  Risk arrays of its existing
+    /// instruments are recomputed.
+    pub fn add_commodity(&mut self, commodity: SpanCommodity) {
+        for (instrument, array) in self.instruments.values_mut() {
+            if instrument.commodity_id == commodity.commodity_id {
+                *array = RiskArray::compute(instrument, &commodity);
+            }
+        }
+        self.commodities.insert(commodity.commodity_id, commodity);
+    }
+
+    /// Add or replace an instrument and compute its risk array.
+    ///
+    /// # Errors
+    ///
+    /// [`SpanError::UnknownCommodity`] if its commodity has not been added.
+    pub fn add_instrument(&mut self, instrument: SpanInstrument) -> Result<(), SpanError> {
+        let commodity = self
+            .commodities
+            .get(&instrument.commodity_id)
+            .ok_or(SpanError::UnknownCommodity(instrument.commodity_id))?;
+        let array = RiskArray::compute(&instrument, commodity);
+        self.instruments
+            .insert(instrument.instrument_id, (instrument, array));
+        Ok(())
+    }
+
+    /// Override an instrument's risk array with published values.
+    ///
+    /// # Errors
+    ///
+    /// [`SpanError::UnknownInstrument`] if the instrument has not been added.
+    pub fn set_risk_array(
+        &mut self,
+        instrument_id: u64,
+        array: RiskArray,
+    ) -> Result<(), SpanError> {
+        let entry = self
+            .instruments
+            .get_mut(&instrument_id)
+            .ok_or(SpanError::UnknownInstrument(instrument_id))?;
+        entry.1 = array;
+        Ok(())
+    }
+
+    /// Add an inter-commodity spread.  Spreads are applied in the order
+    /// added, each consuming the deltas it credits.
+    ///
+    /// # Errors
+    ///
+    /// [`SpanError::InvalidInterSpread`] if the credit rate is outside
+    /// `0..=RATE_SCALE` or a delta ratio is not positive.
+    pub fn add_inter_spread(&mut self, spread: InterCommoditySpread) -> Result<(), SpanError> {
+        if !(0..=RATE_SCALE).contains(&spread.credit_rate)
+            || spread.delta_ratio_a <= 0
+            || spread.delta_ratio_b <= 0
+        {
+            return Err(SpanError::InvalidInterSpread {
+                commodity_a: spread.commodity_a,
+                commodity_b: spread.commodity_b,
+            });
+        }
+        self.inter_spreads.push(spread);
+        Ok(())
+    }
+
+    /// Risk array of an instrument.
+    #[must_use]
+    pub fn risk_array(&self, instrument_id: u64) -> Option<&RiskArray> {
+        self.instruments.get(&instrument_id).map(|(_, a)| a)
+    }
+
+    /// Compute SPAN margin for `account_id` holding `positions`.
+    ///
+    /// # Errors
+    ///
+    /// [`SpanError::UnknownInstrument`] if a position's instrument is unknown.
+    pub fn compute(
+        &self,
+        account_id: u64,
+        positions: &[SpanPosition],
+    ) -> Result<SpanResult, SpanError> {
+        let mut acc: BTreeMap<u64, CommodityAccumulator> = BTreeMap::new();
+        for p in positions {
+            let (instrument, array) = self
+                .instruments
+                .get(&p.instrument_id)
+                .ok_or(SpanError::UnknownInstrument(p.instrument_id))?;
+            let a = acc.entry(instrument.commodity_id).or_default();
+            for (total, loss) in a.risk_array.iter_mut().zip(array.scaled(p.quantity)) {
+                *total = total.saturating_add(loss);
+            }
+            let delta = array.delta.saturating_mul(p.quantity);
+            a.net_delta = a.net_delta.saturating_add(delta);
+            let tier = a.tier_deltas.entry(instrument.tier).or_insert(0);
+            *tier = tier.saturating_add(delta);
+            if p.quantity < 0 && matches!(instrument.product, SpanProduct::Option { .. }) {
+                a.short_options = a.short_options.saturating_add(-p.quantity);
+            }
+        }
+
+        let mut risks: Vec<CommodityRisk> = acc
+            .into_iter()
+            .map(|(commodity_id, a)| self.commodity_risk(commodity_id, a))
+            .collect();
+        self.apply_inter_spreads(&mut risks);
+
+        for r in &mut risks {
+            r.total = r
+                .scan_risk
+                .saturating_add(r.intra_spread_charge)
+                .saturating_sub(r.inter_spread_credit)
+                .max(r.short_option_minimum)
+                .max(0);
+        }
+        let total_margin = risks.iter().fold(0_i64, |t, r| t.saturating_add(r.total));
+        Ok(SpanResult {
+            account_id,
+            commodities: risks,
+            total_margin,
+        })
+    }
+
+    /// Compute SPAN margin for an account from net obligations.  Each
+    /// obligation's `symbol_hash` is the instrument id; the receiver is long
+    /// and the deliverer short `net_quantity` contracts.
+    ///
+    /// # Errors
+    ///
+    /// See [`compute`](Self::compute).
+    pub fn compute_portfolio_margin(
+        &self,
+        account_id: u64,
+        obligations: &[NetObligation],
+    ) -> Result<MarginRequirement, SpanError> {
+        let mut quantities: BTreeMap<u64, i64> = BTreeMap::new();
+        for ob in obligations {
+            let qty = i64::try_from(ob.net_quantity).unwrap_or(i64::MAX);
+            let signed = if ob.receiver_id == account_id {
+                qty
+            } else if ob.deliverer_id == account_id {
+                -qty
+            } else {
+                continue;
+            };
+            let q = quantities.entry(ob.symbol_hash).or_insert(0);
+            *q = q.saturating_add(signed);
+        }
+        let positions: Vec<SpanPosition> = quantities
+            .into_iter()
+            .filter(|&(_, quantity)| quantity != 0)
+            .map(|(instrument_id, quantity)| SpanPosition {
+                instrument_id,
+                quantity,
+            })
+            .collect();
+        self.compute(account_id, &positions)
+            .map(|r| r.to_requirement())
+    }
+
+    fn commodity_risk(&self, commodity_id: u64, a: CommodityAccumulator) -> CommodityRisk {
+        let (worst_scenario, &worst) = a
+            .risk_array
+            .iter()
+            .enumerate()
+            .max_by_key(|&(i, l)| (*l, std::cmp::Reverse(i)))
+            .unwrap_or((0, &0));
+
+        let (long, short) = a.tier_deltas.values().fold((0_i64, 0_i64), |(l, s), &d| {
+            if d > 0 {
+                (l.saturating_add(d), s)
+            } else {
+                (l, s.saturating_sub(d))
+            }
+        });
+        let spreads = long.min(short);
+        let (intra_rate, som_rate) = self
+            .commodities
+            .get(&commodity_id)
+            .map_or((0, 0), |c| (c.intra_spread_charge, c.short_option_minimum));
+
+        CommodityRisk {
+            commodity_id,
+            risk_array: a.risk_array,
+            scan_risk: worst.max(0),
+            worst_scenario,
+            net_delta: a.net_delta,
+            intra_spread_charge: RoundingMode::HalfUp.apply(intra_rate, spreads),
+            inter_spread_credit: 0,
+            short_option_minimum: a.short_options.saturating_mul(som_rate),
+            total: 0,
+        }
+    }
+
+    fn apply_inter_spreads(&self, risks: &mut [CommodityRisk]) {
+        let index: HashMap<u64, usize> = risks
+            .iter()
+            .enumerate()
+            .map(|(i, r)| (r.commodity_id, i))
+            .collect();
+        let scale = i128::from(RATE_SCALE);
+        let mut remaining: Vec<i64> = risks.iter().map(|r| r.net_delta).collect();
+        let mut credits = vec![0_i64; risks.len()];
+
+        for s in &self.inter_spreads {
+            let (Some(&a), Some(&b)) = (index.get(&s.commodity_a), index.get(&s.commodity_b))
+            else {
+                continue;
+            };
+            if remaining[a].signum() * remaining[b].signum() >= 0 {
+                continue;
+            }
+            // Whole and fractional spreads (fixed point), rounded down so a
+            // leg never gives up more delta than it has left.
+            let spreads = (i128::from(remaining[a].unsigned_abs()) * scale
+                / i128::from(s.delta_ratio_a))
+            .min(i128::from(remaining[b].unsigned_abs()) * scale / i128::from(s.delta_ratio_b));
+            for (leg, ratio) in [(a, s.delta_ratio_a), (b, s.delta_ratio_b)] {
+                // At most the leg's remaining delta, so it fits in i64.
+                let used = i64::try_from(spreads * i128::from(ratio) / scale).unwrap_or(i64::MAX);
+                credits[leg] =
+                    credits[leg].saturating_add(leg_credit(&risks[leg], used, s.credit_rate));
+                remaining[leg] -= used * remaining[leg].signum();
+            }
+        }
+
+        for (r, c) in risks.iter_mut().zip(credits) {
+            r.inter_spread_credit = c;
+        }
+    }
+}
+
+/// Credit for `used` deltas of a leg: `rate` of the leg's scan risk per unit
+/// of its net delta (the weighted futures price risk), rounded down.
+fn leg_credit(risk: &CommodityRisk, used: i64, rate: i64) -> i64 {
+    let net = i128::from(risk.net_delta.unsigned_abs());
+    if net == 0 {
+        return 0;
+    }
+    // `used` never exceeds the net delta, so the fraction is at most 1.
+    let fraction = i64::try_from(i128::from(used) * i128::from(RATE_SCALE) / net)
+        .unwrap_or(RATE_SCALE)
+        .min(RATE_SCALE);
+    RoundingMode::Down.apply(RoundingMode::Down.apply(risk.scan_risk, rate), fraction)
+}
+
+impl MarginModel for SpanEngine {
+    fn portfolio_margin(
+        &self,
+        account_id: u64,
+        obligations: &[NetObligation],
+    ) -> Result<MarginRequirement, MarginModelError> {
+        Ok(self.compute_portfolio_margin(account_id, obligations)?)
+    }
+}
+
+// ── Tests ──────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CRUDE: u64 = 1;
+    const BRENT: u64 = 2;
+
+    fn commodity(commodity_id: u64) -> SpanCommodity {
+        SpanCommodity {
+            commodity_id,
+            scanning_range: 300,
+            volatility_range_bps: 500,
+            multiplier: 10,
+            intra_spread_charge: 200,
+            short_option_minimum: 50,
+        }
+    }
+
+    fn future(instrument_id: u64, commodity_id: u64, tier: u32) -> SpanInstrument {
+        SpanInstrument {
+            instrument_id,
+            commodity_id,
+            tier,
+            product: SpanProduct::Future,
+            underlying_price: 10_000,
+        }
+    }
+
+    fn option(instrument_id: u64, is_call: bool, strike: i64) -> SpanInstrument {
+        SpanInstrument {
+            instrument_id,
+            commodity_id: CRUDE,
+            tier: 1,
+            product: SpanProduct::Option {
+                is_call,
+                strike,
+                volatility_bps: 3_000,
+                time_to_expiry: 0.25,
+            },
+            underlying_price: 10_000,
+        }
+    }
+
+    fn engine() -> SpanEngine {
+        let mut e = SpanEngine::new();
+        e.add_commodity(commodity(CRUDE));
+        e.add_commodity(commodity(BRENT));
+        e.add_instrument(future(10, CRUDE, 1)).unwrap();
+        e.add_instrument(future(11, CRUDE, 2)).unwrap();
+        e.add_instrument(future(20, BRENT, 1)).unwrap();
+        e.add_instrument(option(30, true, 10_000)).unwrap();
+        e.add_instrument(option(31, false, 5_000)).unwrap();
+        e
+    }
+
+    fn pos(instrument_id: u64, quantity: i64) -> SpanPosition {
+        SpanPosition {
+            instrument_id,
+            quantity,
+        }
+    }
+
+    #[test]
+    fn future_risk_array() {
+        let e = engine();
+        let a = e.risk_array(10).unwrap();
+        assert_eq!(a.delta, RATE_SCALE);
+        assert_eq!(SPAN_SCENARIOS[15].weight, 350_000_000);
+        assert_eq!(a.losses[0], 0);
+        assert_eq!(a.losses[2], -1_000); // +⅓ range gains on a long
+        assert_eq!(a.losses[12], 3_000); // −1 range
+        assert_eq!(a.losses[15], 3_150); // −3 ranges × 35%
+    }
+
+    #[test]
+    fn long_future_scan_risk() {
+        let r = engine().compute(7, &[pos(10, 2)]).unwrap();
+        assert_eq!(r.commodities.len(), 1);
+        let c = &r.commodities[0];
+        assert_eq!(c.scan_risk, 6_300);
+        assert_eq!(c.worst_scenario, 15);
+        assert_eq!(c.intra_spread_charge, 0);
+        assert_eq!(r.total_margin, 6_300);
+        assert_eq!(r.to_requirement().total_margin, 6_300);
+    }
+
+    #[test]
+    fn margin_from_obligations() {
+        let ob = |symbol_hash, deliverer_id, receiver_id, net_quantity| NetObligation {
+            symbol_hash,
+            deliverer_id,
+            receiver_id,
+            net_quantity,
+            net_payment: 0,
+            trade_count: 1,
+        };
+        let obligations = [ob(10, 8, 7, 3), ob(10, 7, 9, 1), ob(11, 8, 9, 5)];
+        let model: &dyn MarginModel = &engine();
+        let req = model.portfolio_margin(7, &obligations).unwrap();
+        assert_eq!(req.account_id, 7);
+        assert_eq!(req.total_margin, 6_300);
+        assert_eq!(
+            model.portfolio_margin(1, &[ob(99, 1, 2, 1)]),
+            Err(MarginModelError::Span(SpanError::UnknownInstrument(99)))
+        );
+    }
+
+    #[test]
+    fn same_tier_hedge_nets_to_zero() {
+        let r = engine().compute(7, &[pos(10, 3), pos(10, -3)]).unwrap();
+        assert_eq!(r.total_margin, 0);
+    }
+
+    #[test]
+    fn calendar_spread_charged() {
+        let r = engine().compute(7, &[pos(10, 3), pos(11, -2)]).unwrap();
+        let c = &r.commodities[0];
+        // Net one long: scan 3150; two spreads × 200.
+        assert_eq!(c.scan_risk, 3_150);
+        assert_eq!(c.intra_spread_charge, 400);
+        assert_eq!(c.net_delta, RATE_SCALE);
+        assert_eq!(c.total, 3_550);
+    }
+
+    #[test]
+    fn inter_commodity_credit() {
+        let mut e = engine();
+        let positions = [pos(10, 1), pos(20, -1)];
+        let without = e.compute(7, &positions).unwrap();
+        assert_eq!(without.total_margin, 6_300);
+
+        e.add_inter_spread(spread(RATE_SCALE, RATE_SCALE, 600_000_000))
+            .unwrap();
+        let with = e.compute(7, &positions).unwrap();
+        assert_eq!(with.commodities[0].inter_spread_credit, 1_890);
+        assert_eq!(with.commodities[1].inter_spread_credit, 1_890);
+        assert_eq!(with.total_margin, 2_520);
+
+        // Same-direction deltas get no credit.
+        let same = e.compute(7, &[pos(10, 1), pos(20, 1)]).unwrap();
+        assert_eq!(same.total_margin, 6_300);
+    }
+
+    fn spread(delta_ratio_a: i64, delta_ratio_b: i64, credit_rate: i64) -> InterCommoditySpread {
+        InterCommoditySpread {
+            commodity_a: CRUDE,
+            commodity_b: BRENT,
+            delta_ratio_a,
+            delta_ratio_b,
+            credit_rate,
+        }
+    }
+
+    #[test]
+    fn inter_commodity_credit_uses_ratios() {
+        let mut e = engine();
+        // Two crude deltas per brent delta: 3 long crude hedge 1.5 short brent.
+        e.add_inter_spread(spread(2 * RATE_SCALE, RATE_SCALE, RATE_SCALE / 2))
+            .unwrap();
+        let r = e.compute(7, &[pos(10, 3), pos(20, -2)]).unwrap();
+        // Crude: all 3 deltas used, half of its 9450 scan risk.
+        assert_eq!(r.commodities[0].inter_spread_credit, 4_725);
+        // Brent: 1.5 of 2 deltas used, half of 6300 × ¾.
+        assert_eq!(r.commodities[1].inter_spread_credit, 2_362);
+    }
+
+    #[test]
+    fn invalid_inter_spread_rejected() {
+        let mut e = engine();
+        let invalid = Err(SpanError::InvalidInterSpread {
+            commodity_a: CRUDE,
+            commodity_b: BRENT,
+        });
+        assert_eq!(
+            e.add_inter_spread(spread(RATE_SCALE, RATE_SCALE, RATE_SCALE + 1)),
+            invalid
+        );
+        assert_eq!(
+            e.add_inter_spread(spread(RATE_SCALE, RATE_SCALE, -1)),
+            invalid
+        );
+        assert_eq!(
+            e.add_inter_spread(spread(0, RATE_SCALE, RATE_SCALE)),
+            invalid
+        );
+        assert_eq!(
+            e.add_inter_spread(spread(RATE_SCALE, -5, RATE_SCALE)),
+            invalid
+        );
+        assert!(e
+            .add_inter_spread(spread(RATE_SCALE, RATE_SCALE, 0))
+            .is_ok());
+        assert!(e
+            .add_inter_spread(spread(RATE_SCALE, RATE_SCALE, RATE_SCALE))
+            .is_ok());
+    }
+
+    #[test]
+    fn option_risk_array_and_delta() {
+        let e = engine();
+        let call = e.risk_array(30).unwrap();
+        assert!((call.delta - 529_900_000).abs() < 1_000_000);
+        // A long call loses when the price falls and vol drops.
+        assert!(call.losses[13] > 0);
+        assert!(call.losses[10] < 0);
+        // Vol up helps a long option at an unchanged price.
+        assert!(call.losses[0] < 0 && call.losses[1] > 0);
+        let put = e.risk_array(31).unwrap();
+        assert!(put.delta < 0 && put.delta > -10_000_000);
+    }
+
+    #[test]
+    fn short_option_minimum_applies() {
+        let r = engine().compute(7, &[pos(31, -10)]).unwrap();
+        let c = &r.commodities[0];
+        assert_eq!(c.short_option_minimum, 500);
+        assert!(c.scan_risk < c.short_option_minimum);
+        assert_eq!(c.total, 500);
+    }
+
+    #[test]
+    fn published_risk_array_override() {
+        let mut e = engine();
+        e.set_risk_array(
+            10,
+            RiskArray {
+                losses: [100; SCENARIO_COUNT],
+                delta: RATE_SCALE,
+            },
+        )
+        .unwrap();
+        assert_eq!(e.compute(7, &[pos(10, 4)]).unwrap().total_margin, 400);
+        assert_eq!(
+            e.set_risk_array(
+                99,
+                RiskArray {
+                    losses: [0; SCENARIO_COUNT],
+                    delta: 0
+                }
+            ),
+            Err(SpanError::UnknownInstrument(99))
+        );
+    }
+
+    #[test]
+    fn unknown_references() {
+        let mut e = engine();
+        assert_eq!(
+            e.compute(7, &[pos(99, 1)]),
+            Err(SpanError::UnknownInstrument(99))
+        );
+        assert_eq!(
+            e.add_instrument(future(12, 42, 1)),
+            Err(SpanError::UnknownCommodity(42))
+        );
+    }
+
+    #[test]
+    fn commodity_update_recomputes_arrays() {
+        let mut e = engine();
+        e.add_commodity(SpanCommodity {
+            scanning_range: 600,
+            ..commodity(CRUDE)
+        });
+        assert_eq!(e.risk_array(10).unwrap().losses[12], 6_000);
+    }
+
+    #[test]
+    fn norm_cdf_accuracy() {
+        assert!((norm_cdf(0.0) - 0.5).abs() < 1e-7);
+        assert!((norm_cdf(1.96) - 0.975_002).abs() < 1e-6);
+        assert!((norm_cdf(-1.0) - 0.158_655).abs() < 1e-6);
+    }
+}
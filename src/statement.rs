@@ -0,0 +1,751 @@
+// ALICE-Settlement — End-of-day clearing statements
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Moroya Sakamoto
+
+use std::fmt::Write as _;
+
+use crate::fnv1a;
+use crate::journal::{JournalEvent, SettlementJournal};
+use crate::waterfall::WaterfallLayer;
+
+// ── Types ──────────────────────────────────────────────────────────────
+
+/// Which side of a net obligation the account was on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObligationSide {
+    /// The account delivered and paid `net_payment`.
+    Deliverer,
+    /// The account received delivery and was credited `net_payment`.
+    Receiver,
+}
+
+/// What a statement line records.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatementLineKind {
+    /// Balance set on (re-)registration; margin held is reset to zero.
+    Registration,
+    /// Cash paid in.
+    Deposit,
+    /// Cash paid out.
+    Withdrawal,
+    /// Cash received from another member.
+    TransferIn { from_id: u64 },
+    /// Cash sent to another member.
+    TransferOut { to_id: u64 },
+    /// A net obligation was cleared.
+    ObligationCleared {
+        symbol_hash: u64,
+        counterparty_id: u64,
+        side: ObligationSide,
+        net_quantity: u64,
+    },
+    /// A net obligation failed to clear; no cash moved.
+    ObligationFailed {
+        symbol_hash: u64,
+        counterparty_id: u64,
+        side: ObligationSide,
+        net_quantity: u64,
+        net_payment: i64,
+        reason: String,
+    },
+    /// Cash moved into margin.
+    MarginPosted,
+    /// Margin returned to cash.
+    MarginReleased,
+    /// Margin consumed by the default waterfall.
+    MarginDrawn,
+    /// Fee charged.
+    Fee { symbol_hash: u64 },
+}
+
+impl StatementLineKind {
+    /// Short machine-readable label.
+    #[must_use]
+    pub const fn label(&self) -> &'static str {
+        match self {
+            Self::Registration => "registration",
+            Self::Deposit => "deposit",
+            Self::Withdrawal => "withdrawal",
+            Self::TransferIn { .. } => "transfer_in",
+            Self::TransferOut { .. } => "transfer_out",
+            Self::ObligationCleared { .. } => "obligation_cleared",
+            Self::ObligationFailed { .. } => "obligation_failed",
+            Self::MarginPosted => "margin_posted",
+            Self::MarginReleased => "margin_released",
+            Self::MarginDrawn => "margin_drawn",
+            Self::Fee { .. } => "fee",
+        }
+    }
+}
+
+/// A single statement line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatementLine {
+    /// Journal sequence number of the underlying event.
+    pub sequence: u64,
+    /// Journal timestamp.
+    pub timestamp_ns: u64,
+    /// What happened.
+    pub kind: StatementLineKind,
+    /// Change in available balance.
+    pub cash_delta: i64,
+    /// Change in margin held.
+    pub margin_delta: i64,
+    /// Available balance after this line.
+    pub balance_after: i64,
+    /// Margin held after this line.
+    pub margin_after: i64,
+}
+
+/// Statement of one account's activity over a time window.
+#[derive(Debug, Clone)]
+pub struct Statement {
+    /// Account the statement is for.
+    pub account_id: u64,
+    /// Inclusive start of the window.
+    pub from_ns: u64,
+    /// Exclusive end of the window.
+    pub to_ns: u64,
+    /// Available balance at `from_ns`.
+    pub opening_balance: i64,
+    /// Margin held at `from_ns`.
+    pub opening_margin: i64,
+    /// Activity within the window, in journal order.
+    pub lines: Vec<StatementLine>,
+    /// Available balance at `to_ns`.
+    pub closing_balance: i64,
+    /// Margin held at `to_ns`.
+    pub closing_margin: i64,
+    /// Sum of fees charged within the window.
+    pub total_fees: i64,
+    /// True when replaying the whole journal reproduces the account's actual
+    /// balance and margin held.
+    pub reconciled: bool,
+    /// Deterministic content hash.
+    pub content_hash: u64,
+}
+
+// ── Generation ─────────────────────────────────────────────────────────
+
+impl Statement {
+    /// Build a statement for `account_id` over `[from_ns, to_ns)` by replaying
+    /// `journal`.
+    ///
+    /// `start` is the account's `(balance, margin_held)` before the first
+    /// journal entry: zero for a journal kept since registration, or the
+    /// snapshot values for a journal resumed from a snapshot.  `actual` is the
+    /// account's current `(balance, margin_held)`; the statement is marked
+    /// reconciled when a full replay arrives at it.
+    #[must_use]
+    pub fn from_journal(
+        journal: &SettlementJournal,
+        account_id: u64,
+        from_ns: u64,
+        to_ns: u64,
+        start: (i64, i64),
+        actual: (i64, i64),
+    ) -> Self {
+        let (mut balance, mut margin) = start;
+        let mut opening = start;
+        let mut closing = None;
+        let mut lines = Vec::new();
+        let mut total_fees: i64 = 0;
+
+        for entry in journal.entries() {
+            let ts = entry.timestamp_ns;
+            if ts >= to_ns && closing.is_none() {
+                closing = Some((balance, margin));
+            }
+            let Some((kind, cash_delta, margin_delta)) =
+                line_effect(&entry.event, account_id, balance, margin)
+            else {
+                continue;
+            };
+            balance = balance.saturating_add(cash_delta);
+            margin = margin.saturating_add(margin_delta);
+
+            if ts < from_ns {
+                opening = (balance, margin);
+            } else if ts < to_ns {
+                if let StatementLineKind::Fee { .. } = kind {
+                    total_fees = total_fees.saturating_sub(cash_delta);
+                }
+                lines.push(StatementLine {
+                    sequence: entry.sequence,
+                    timestamp_ns: ts,
+                    kind,
+                    cash_delta,
+                    margin_delta,
+                    balance_after: balance,
+                    margin_after: margin,
+                });
+            }
+        }
+
+        let (closing_balance, closing_margin) = closing.unwrap_or((balance, margin));
+        let reconciled = (balance, margin) == actual;
+        let content_hash = Self::compute_hash(
+            account_id,
+            opening.0,
+            closing_balance,
+            closing_margin,
+            &lines,
+        );
+
+        Self {
+            account_id,
+            from_ns,
+            to_ns,
+            opening_balance: opening.0,
+            opening_margin: opening.1,
+            lines,
+            closing_balance,
+            closing_margin,
+            total_fees,
+            reconciled,
+            content_hash,
+        }
+    }
+
+    /// Export the lines as CSV with a header row.
+    #[must_use]
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from(
+            "sequence,timestamp_ns,kind,symbol_hash,counterparty_id,net_quantity,\
+             cash_delta,margin_delta,balance_after,margin_after,detail\n",
+        );
+        for line in &self.lines {
+            let (symbol, counterparty, qty, detail) = line_fields(&line.kind);
+            let _ = writeln!(
+                out,
+                "{},{},{},{},{},{},{},{},{},{},{}",
+                line.sequence,
+                line.timestamp_ns,
+                line.kind.label(),
+                symbol.map_or(String::new(), |v| v.to_string()),
+                counterparty.map_or(String::new(), |v| v.to_string()),
+                qty.map_or(String::new(), |v| v.to_string()),
+                line.cash_delta,
+                line.margin_delta,
+                line.balance_after,
+                line.margin_after,
+                csv_escape(detail),
+            );
+        }
+        out
+    }
+
+    /// Export the full statement as a JSON object.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            "{{\"account_id\":{},\"from_ns\":{},\"to_ns\":{},\
+             \"opening_balance\":{},\"opening_margin\":{},\
+             \"closing_balance\":{},\"closing_margin\":{},\
+             \"total_fees\":{},\"reconciled\":{},\"content_hash\":{},\"lines\":[",
+            self.account_id,
+            self.from_ns,
+            self.to_ns,
+            self.opening_balance,
+            self.opening_margin,
+            self.closing_balance,
+            self.closing_margin,
+            self.total_fees,
+            self.reconciled,
+            self.content_hash,
+        );
+        for (i, line) in self.lines.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let (symbol, counterparty, qty, detail) = line_fields(&line.kind);
+            let _ = write!(
+                out,
+                "{{\"sequence\":{},\"timestamp_ns\":{},\"kind\":\"{}\"",
+                line.sequence,
+                line.timestamp_ns,
+                line.kind.label(),
+            );
+            if let Some(v) = symbol {
+                let _ = write!(out, ",\"symbol_hash\":{v}");
+            }
+            if let Some(v) = counterparty {
+                let _ = write!(out, ",\"counterparty_id\":{v}");
+            }
+            if let Some(v) = qty {
+                let _ = write!(out, ",\"net_quantity\":{v}");
+            }
+            if !detail.is_empty() {
+                let _ = write!(out, ",\"detail\":\"{}\"", json_escape(detail));
+            }
+            let _ = write!(
+                out,
+                ",\"cash_delta\":{},\"margin_delta\":{},\"balance_after\":{},\"margin_after\":{}}}",
+                line.cash_delta, line.margin_delta, line.balance_after, line.margin_after,
+            );
+        }
+        out.push_str("]}");
+        out
+    }
+
+    fn compute_hash(
+        account_id: u64,
+        opening: i64,
+        closing: i64,
+        closing_margin: i64,
+        lines: &[StatementLine],
+    ) -> u64 {
+        let mut data = Vec::with_capacity(32 + lines.len() * 24);
+        data.extend_from_slice(&account_id.to_le_bytes());
+        data.extend_from_slice(&opening.to_le_bytes());
+        data.extend_from_slice(&closing.to_le_bytes());
+        data.extend_from_slice(&closing_margin.to_le_bytes());
+        for line in lines {
+            data.extend_from_slice(&line.sequence.to_le_bytes());
+            data.extend_from_slice(&line.cash_delta.to_le_bytes());
+            data.extend_from_slice(&line.margin_delta.to_le_bytes());
+        }
+        fnv1a(&data)
+    }
+}
+
+/// Map a journal event to its effect on `account_id`, if any.
+///
+/// Returns the line kind with the cash and margin deltas.  `balance` and
+/// `margin` are the running values before the event, needed for
+/// registrations, which set rather than adjust.
+fn line_effect(
+    event: &JournalEvent,
+    account_id: u64,
+    balance: i64,
+    margin: i64,
+) -> Option<(StatementLineKind, i64, i64)> {
+    let side_of = |deliverer_id: u64, receiver_id: u64| {
+        if deliverer_id == account_id {
+            Some((ObligationSide::Deliverer, receiver_id))
+        } else if receiver_id == account_id {
+            Some((ObligationSide::Receiver, deliverer_id))
+        } else {
+            None
+        }
+    };
+
+    match event {
+        JournalEvent::AccountRegistered {
+            account_id: id,
+            balance: new_balance,
+        } if *id == account_id => Some((
+            StatementLineKind::Registration,
+            new_balance.saturating_sub(balance),
+            margin.saturating_neg(),
+        )),
+        JournalEvent::CashDeposited {
+            account_id: id,
+            amount,
+            ..
+        } if *id == account_id => Some((StatementLineKind::Deposit, *amount, 0)),
+        JournalEvent::CashWithdrawn {
+            account_id: id,
+            amount,
+            ..
+        } if *id == account_id => Some((StatementLineKind::Withdrawal, amount.saturating_neg(), 0)),
+        JournalEvent::CashTransferred {
+            from_id,
+            to_id,
+            amount,
+            ..
+        } => {
+            if *from_id == account_id && *to_id == account_id {
+                Some((StatementLineKind::TransferOut { to_id: *to_id }, 0, 0))
+            } else if *from_id == account_id {
+                Some((
+                    StatementLineKind::TransferOut { to_id: *to_id },
+                    amount.saturating_neg(),
+                    0,
+                ))
+            } else if *to_id == account_id {
+                Some((
+                    StatementLineKind::TransferIn { from_id: *from_id },
+                    *amount,
+                    0,
+                ))
+            } else {
+                None
+            }
+        }
+        JournalEvent::ObligationCleared {
+            symbol_hash,
+            deliverer_id,
+            receiver_id,
+            net_quantity,
+            net_payment,
+        } => side_of(*deliverer_id, *receiver_id).map(|(side, counterparty_id)| {
+            let delta = match side {
+                ObligationSide::Deliverer => net_payment.saturating_neg(),
+                ObligationSide::Receiver => *net_payment,
+            };
+            (
+                StatementLineKind::ObligationCleared {
+                    symbol_hash: *symbol_hash,
+                    counterparty_id,
+                    side,
+                    net_quantity: *net_quantity,
+                },
+                delta,
+                0,
+            )
+        }),
+        JournalEvent::ObligationFailed {
+            symbol_hash,
+            deliverer_id,
+            receiver_id,
+            net_quantity,
+            net_payment,
+            reason,
+        } => side_of(*deliverer_id, *receiver_id).map(|(side, counterparty_id)| {
+            (
+                StatementLineKind::ObligationFailed {
+                    symbol_hash: *symbol_hash,
+                    counterparty_id,
+                    side,
+                    net_quantity: *net_quantity,
+                    net_payment: *net_payment,
+                    reason: reason.clone(),
+                },
+                0,
+                0,
+            )
+        }),
+        JournalEvent::MarginPosted {
+            account_id: id,
+            amount,
+        } if *id == account_id => Some((
+            StatementLineKind::MarginPosted,
+            amount.saturating_neg(),
+            *amount,
+        )),
+        JournalEvent::MarginReleased {
+            account_id: id,
+            amount,
+        } if *id == account_id => Some((
+            StatementLineKind::MarginReleased,
+            *amount,
+            amount.saturating_neg(),
+        )),
+        JournalEvent::WaterfallApplied {
+            account_id: id,
+            absorbed,
+        } if *id == account_id && absorbed[WaterfallLayer::DefaulterMargin as usize] != 0 => {
+            Some((
+                StatementLineKind::MarginDrawn,
+                0,
+                absorbed[WaterfallLayer::DefaulterMargin as usize].saturating_neg(),
+            ))
+        }
+        JournalEvent::FeeCharged {
+            account_id: id,
+            symbol_hash,
+            amount,
+        } if *id == account_id => Some((
+            StatementLineKind::Fee {
+                symbol_hash: *symbol_hash,
+            },
+            amount.saturating_neg(),
+            0,
+        )),
+        _ => None,
+    }
+}
+
+/// Optional columns of a line: symbol, counterparty, quantity, detail text.
+fn line_fields(kind: &StatementLineKind) -> (Option<u64>, Option<u64>, Option<u64>, &str) {
+    match kind {
+        StatementLineKind::TransferIn { from_id } => (None, Some(*from_id), None, ""),
+        StatementLineKind::TransferOut { to_id } => (None, Some(*to_id), None, ""),
+        StatementLineKind::ObligationCleared {
+            symbol_hash,
+            counterparty_id,
+            side,
+            net_quantity,
+        } => (
+            Some(*symbol_hash),
+            Some(*counterparty_id),
+            Some(*net_quantity),
+            side_label(*side),
+        ),
+        StatementLineKind::ObligationFailed {
+            symbol_hash,
+            counterparty_id,
+            net_quantity,
+            reason,
+            ..
+        } => (
+            Some(*symbol_hash),
+            Some(*counterparty_id),
+            Some(*net_quantity),
+            reason.as_str(),
+        ),
+        StatementLineKind::Fee { symbol_hash } => (Some(*symbol_hash), None, None, ""),
+        _ => (None, None, None, ""),
+    }
+}
+
+const fn side_label(side: ObligationSide) -> &'static str {
+    match side {
+        ObligationSide::Deliverer => "deliverer",
+        ObligationSide::Receiver => "receiver",
+    }
+}
+
+fn csv_escape(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn json_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// ── Tests ──────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clearing::ClearingHouse;
+    use crate::netting::NetObligation;
+
+    fn ob(
+        symbol_hash: u64,
+        deliverer_id: u64,
+        receiver_id: u64,
+        net_payment: i64,
+    ) -> NetObligation {
+        NetObligation {
+            symbol_hash,
+            deliverer_id,
+            receiver_id,
+            net_quantity: 10,
+            net_payment,
+            trade_count: 1,
+        }
+    }
+
+    /// Day 1 at t=100, day 2 at t=200..=260.
+    fn two_day_house() -> ClearingHouse {
+        let mut ch = day_one();
+        day_two(&mut ch);
+        ch
+    }
+
+    fn day_one() -> ClearingHouse {
+        let mut ch = ClearingHouse::new();
+        ch.set_time(100);
+        ch.register_account(1, 10_000).unwrap();
+        ch.register_account(2, 5_000).unwrap();
+        let _ = ch.clear_obligation(&ob(0xAA, 1, 2, 1_000));
+        ch
+    }
+
+    fn day_two(ch: &mut ClearingHouse) {
+        ch.set_time(200);
+        let _ = ch.deposit(1, 1, 500);
+        ch.set_time(210);
+        let _ = ch.clear_obligation(&ob(0xBB, 2, 1, 300));
+        ch.set_time(220);
+        let _ = ch.clear_obligation(&ob(0xCC, 1, 2, 1_000_000)); // fails
+        ch.set_time(230);
+        let _ = ch.post_margin(1, 2_000);
+        ch.set_time(240);
+        let _ = ch.charge_fee(1, 0xBB, 25);
+        ch.set_time(250);
+        let _ = ch.transfer(2, 1, 2, 100);
+    }
+
+    #[test]
+    fn opening_and_closing_balances() {
+        let ch = two_day_house();
+        let st = ch.statement(1, 200, 300).unwrap();
+        assert_eq!(st.opening_balance, 9_000);
+        assert_eq!(st.opening_margin, 0);
+        // 9000 + 500 + 300 - 2000 - 25 - 100
+        assert_eq!(st.closing_balance, 7_675);
+        assert_eq!(st.closing_margin, 2_000);
+        assert_eq!(st.total_fees, 25);
+        assert!(st.reconciled);
+        assert_eq!(st.closing_balance, ch.get_account(1).unwrap().balance);
+    }
+
+    #[test]
+    fn lines_cover_every_event_kind() {
+        let ch = two_day_house();
+        let st = ch.statement(1, 200, 300).unwrap();
+        let labels: Vec<&str> = st.lines.iter().map(|l| l.kind.label()).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "deposit",
+                "obligation_cleared",
+                "obligation_failed",
+                "margin_posted",
+                "fee",
+                "transfer_out",
+            ]
+        );
+
+        match &st.lines[1].kind {
+            StatementLineKind::ObligationCleared {
+                symbol_hash,
+                counterparty_id,
+                side,
+                net_quantity,
+            } => {
+                assert_eq!(*symbol_hash, 0xBB);
+                assert_eq!(*counterparty_id, 2);
+                assert_eq!(*side, ObligationSide::Receiver);
+                assert_eq!(*net_quantity, 10);
+            }
+            other => panic!("unexpected line {other:?}"),
+        }
+        assert_eq!(st.lines[2].cash_delta, 0);
+        assert_eq!(st.lines[3].margin_delta, 2_000);
+    }
+
+    #[test]
+    fn lines_running_balance_consistent() {
+        let ch = two_day_house();
+        let st = ch.statement(1, 0, u64::MAX).unwrap();
+        let mut bal = st.opening_balance;
+        let mut margin = st.opening_margin;
+        for line in &st.lines {
+            bal += line.cash_delta;
+            margin += line.margin_delta;
+            assert_eq!(bal, line.balance_after);
+            assert_eq!(margin, line.margin_after);
+        }
+        assert_eq!(bal, st.closing_balance);
+        assert_eq!(margin, st.closing_margin);
+    }
+
+    #[test]
+    fn closing_excludes_later_activity() {
+        let ch = two_day_house();
+        let st = ch.statement(1, 0, 200).unwrap();
+        assert_eq!(st.opening_balance, 0);
+        assert_eq!(st.closing_balance, 9_000);
+        assert_eq!(st.lines.len(), 2); // registration + day-1 obligation
+                                       // Still reconciled: the full journal reaches the current state.
+        assert!(st.reconciled);
+    }
+
+    #[test]
+    fn counterparty_statement_mirrors() {
+        let ch = two_day_house();
+        let st = ch.statement(2, 200, 300).unwrap();
+        assert_eq!(st.opening_balance, 6_000);
+        // -300 paid on 0xBB, +100 transfer in; failed obligation moves nothing.
+        assert_eq!(st.closing_balance, 5_800);
+        assert!(st.reconciled);
+        assert!(st
+            .lines
+            .iter()
+            .any(|l| l.kind == StatementLineKind::TransferIn { from_id: 1 }));
+    }
+
+    #[test]
+    fn unreconciled_when_journal_incomplete() {
+        let ch = two_day_house();
+        let st = Statement::from_journal(ch.journal(), 1, 0, u64::MAX, (0, 0), (1, 0));
+        assert!(!st.reconciled);
+    }
+
+    #[test]
+    fn statement_after_restore() {
+        let mut ch = day_one();
+        let snap = ch.snapshot();
+        day_two(&mut ch);
+
+        // Activity before the snapshot is not itemised, but the snapshot
+        // balances open the statement.
+        let restored = ClearingHouse::restore(&snap).unwrap();
+        let st = restored.statement(1, 0, u64::MAX).unwrap();
+        assert_eq!(st.opening_balance, 9_000);
+        assert_eq!(st.closing_balance, 9_000);
+        assert!(st.lines.is_empty());
+        assert!(st.reconciled);
+
+        let caught_up = ClearingHouse::restore_with_journal(&snap, ch.journal().entries()).unwrap();
+        for id in [1, 2] {
+            let original = ch.statement(id, 200, 300).unwrap();
+            let replayed = caught_up.statement(id, 200, 300).unwrap();
+            assert_eq!(replayed.opening_balance, original.opening_balance);
+            assert_eq!(replayed.closing_balance, original.closing_balance);
+            assert_eq!(replayed.content_hash, original.content_hash);
+            assert!(replayed.reconciled);
+        }
+        let st = caught_up.statement(1, 0, u64::MAX).unwrap();
+        assert_eq!(st.closing_balance, 7_675);
+        assert_eq!(st.closing_margin, 2_000);
+        assert!(st.reconciled);
+    }
+
+    #[test]
+    fn unknown_account_errors() {
+        let ch = two_day_house();
+        assert!(ch.statement(99, 0, u64::MAX).is_err());
+    }
+
+    #[test]
+    fn csv_export() {
+        let ch = two_day_house();
+        let csv = ch.statement(1, 200, 300).unwrap().to_csv();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows.len(), 7);
+        assert!(rows[0].starts_with("sequence,timestamp_ns,kind"));
+        assert!(rows[2].contains("obligation_cleared,187,2,10,300,0,"));
+        assert!(rows[2].ends_with(",receiver"));
+    }
+
+    #[test]
+    fn json_export() {
+        let ch = two_day_house();
+        let json = ch.statement(1, 200, 300).unwrap().to_json();
+        assert!(json.starts_with("{\"account_id\":1,"));
+        assert!(json.contains("\"closing_balance\":7675"));
+        assert!(json.contains("\"reconciled\":true"));
+        assert!(json.contains("\"kind\":\"fee\",\"symbol_hash\":187"));
+        assert!(json.ends_with("]}"));
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(csv_escape("a,b"), "\"a,b\"");
+        assert_eq!(csv_escape("plain"), "plain");
+        assert_eq!(json_escape("say \"hi\"\n"), "say \\\"hi\\\"\\n");
+    }
+
+    #[test]
+    fn content_hash_deterministic() {
+        let a = two_day_house().statement(1, 200, 300).unwrap();
+        let b = two_day_house().statement(1, 200, 300).unwrap();
+        assert_eq!(a.content_hash, b.content_hash);
+        let c = two_day_house().statement(2, 200, 300).unwrap();
+        assert_ne!(a.content_hash, c.content_hash);
+    }
+}
@@ -0,0 +1,610 @@
+// ALICE-Settlement — Historical-simulation VaR and expected-shortfall margin
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Moroya Sakamoto
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::fnv1a;
+use crate::margin::{MarginModel, MarginModelError, MarginRequirement};
+use crate::netting::NetObligation;
+
+// ── Configuration ──────────────────────────────────────────────────────
+
+/// Risk measure used as the margin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RiskMeasure {
+    /// Value-at-risk: the loss quantile at the confidence level.
+    ValueAtRisk,
+    /// Expected shortfall: the mean loss beyond the VaR quantile.
+    ExpectedShortfall,
+}
+
+/// Anti-procyclicality (APC) treatment applied to the model margin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AntiProcyclicality {
+    /// Add a fixed buffer, in bps of the model margin (2500 = 25%).
+    Buffer { bps: u32 },
+    /// Floor the margin at the measure computed over the full history
+    /// rather than the lookback window.
+    LongHistoryFloor,
+}
+
+/// Configuration for [`HistoricalVarModel`].
+#[derive(Debug, Clone)]
+pub struct VarConfig {
+    /// Confidence level (e.g. 0.99).
+    pub confidence: f64,
+    /// Holding period in days; one-day losses are scaled by its square root.
+    pub holding_period_days: u32,
+    /// Number of most recent returns used.
+    pub lookback: usize,
+    /// Measure reported as the margin.
+    pub measure: RiskMeasure,
+    /// EWMA decay for filtered historical simulation, strictly between 0
+    /// and 1.  When set, each return is rescaled by current / historical
+    /// volatility.
+    pub ewma_lambda: Option<f64>,
+    /// Anti-procyclicality treatment.
+    pub anti_procyclicality: Option<AntiProcyclicality>,
+    /// Absolute minimum margin floor.
+    pub margin_floor: i64,
+}
+
+impl Default for VarConfig {
+    fn default() -> Self {
+        Self {
+            confidence: 0.99,
+            holding_period_days: 2,
+            lookback: 250,
+            measure: RiskMeasure::ExpectedShortfall,
+            ewma_lambda: None,
+            anti_procyclicality: None,
+            margin_floor: 100,
+        }
+    }
+}
+
+/// Net exposure to one symbol (positive = long), in notional.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VarPosition {
+    /// Symbol hash.
+    pub symbol_hash: u64,
+    /// Signed notional exposure.
+    pub exposure: i64,
+}
+
+/// Error returned by [`HistoricalVarModel`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VarError {
+    /// No return series is loaded for a position's symbol.
+    MissingSeries(u64),
+    /// Fewer returns are available than the scenario set needs.
+    InsufficientHistory { required: usize, available: usize },
+    /// The confidence level is not strictly between 0 and 1.
+    InvalidConfidence,
+    /// The EWMA decay is not strictly between 0 and 1.
+    InvalidEwmaLambda,
+    /// A position's return series contains a NaN or infinite return.
+    NonFiniteReturn(u64),
+}
+
+/// Result of a historical-simulation run for one account.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarResult {
+    /// Account identifier.
+    pub account_id: u64,
+    /// Value-at-risk over the holding period.
+    pub value_at_risk: i64,
+    /// Expected shortfall over the holding period.
+    pub expected_shortfall: i64,
+    /// Largest scenario loss over the holding period.
+    pub worst_loss: i64,
+    /// Number of historical scenarios used.
+    pub scenarios: usize,
+    /// Selected measure before anti-procyclicality.
+    pub model_margin: i64,
+    /// Margin after anti-procyclicality and the floor.
+    pub margin: i64,
+}
+
+impl VarResult {
+    /// Convert to a [`MarginRequirement`]: the selected measure is reported
+    /// as initial margin and expected shortfall as stress margin.
+    #[must_use]
+    pub fn to_requirement(&self) -> MarginRequirement {
+        let mut data = [0u8; 16];
+        data[0..8].copy_from_slice(&self.account_id.to_le_bytes());
+        data[8..16].copy_from_slice(&self.margin.to_le_bytes());
+        MarginRequirement {
+            account_id: self.account_id,
+            initial_margin: self.model_margin,
+            variation_margin: 0,
+            stress_margin: self.expected_shortfall,
+            total_margin: self.margin,
+            content_hash: fnv1a(&data),
+        }
+    }
+}
+
+// ── Model ──────────────────────────────────────────────────────────────
+
+/// Historical-simulation margin model.
+///
+/// Each scenario replays one historical day of per-symbol returns against
+/// the account's current exposures.  Series are aligned on their most recent
+/// observation.
+#[derive(Debug, Clone, Default)]
+pub struct HistoricalVarModel {
+    config: VarConfig,
+    series: HashMap<u64, Vec<f64>>,
+}
+
+impl HistoricalVarModel {
+    /// Create a model with no return series loaded.
+    #[must_use]
+    pub fn new(config: VarConfig) -> Self {
+        Self {
+            config,
+            series: HashMap::new(),
+        }
+    }
+
+    /// Access the current configuration.
+    #[inline]
+    #[must_use]
+    pub const fn config(&self) -> &VarConfig {
+        &self.config
+    }
+
+    /// Load or replace the daily return series of a symbol, oldest first.
+    pub fn set_returns(&mut self, symbol_hash: u64, returns: Vec<f64>) {
+        self.series.insert(symbol_hash, returns);
+    }
+
+    /// Return series of a symbol.
+    #[must_use]
+    pub fn returns(&self, symbol_hash: u64) -> Option<&[f64]> {
+        self.series.get(&symbol_hash).map(Vec::as_slice)
+    }
+
+    /// Compute VaR and expected shortfall for `positions`.
+    ///
+    /// # Errors
+    ///
+    /// [`VarError`] if a series is missing or contains non-finite returns,
+    /// history is too short for the confidence level, or the confidence level
+    /// or EWMA decay is invalid.
+    pub fn compute(
+        &self,
+        account_id: u64,
+        positions: &[VarPosition],
+    ) -> Result<VarResult, VarError> {
+        let cfg = &self.config;
+        if !(cfg.confidence > 0.0 && cfg.confidence < 1.0) {
+            return Err(VarError::InvalidConfidence);
+        }
+        if cfg.ewma_lambda.is_some_and(|l| !(l > 0.0 && l < 1.0)) {
+            return Err(VarError::InvalidEwmaLambda);
+        }
+
+        let mut exposures: BTreeMap<u64, i64> = BTreeMap::new();
+        for p in positions {
+            let e = exposures.entry(p.symbol_hash).or_insert(0);
+            *e = e.saturating_add(p.exposure);
+        }
+        exposures.retain(|_, e| *e != 0);
+
+        let mut scaled: Vec<(f64, Vec<f64>)> = Vec::with_capacity(exposures.len());
+        for (&symbol, &exposure) in &exposures {
+            let returns = self
+                .series
+                .get(&symbol)
+                .ok_or(VarError::MissingSeries(symbol))?;
+            if !returns.iter().all(|r| r.is_finite()) {
+                return Err(VarError::NonFiniteReturn(symbol));
+            }
+            let returns = match cfg.ewma_lambda {
+                Some(lambda) => filter_returns(returns, lambda),
+                None => returns.clone(),
+            };
+            scaled.push((exposure as f64, returns));
+        }
+
+        let history = scaled.iter().map(|(_, r)| r.len()).min().unwrap_or(0);
+        let required = min_scenarios(cfg.confidence);
+        if !scaled.is_empty() && history < required {
+            return Err(VarError::InsufficientHistory {
+                required,
+                available: history,
+            });
+        }
+
+        // One-day portfolio losses, oldest first.
+        let losses: Vec<f64> = (0..history)
+            .map(|day| {
+                -scaled
+                    .iter()
+                    .map(|(exposure, r)| exposure * r[r.len() - history + day])
+                    .sum::<f64>()
+            })
+            .collect();
+        let window = &losses[history.saturating_sub(cfg.lookback.max(required))..];
+
+        let horizon = f64::from(cfg.holding_period_days.max(1)).sqrt();
+        let (var, es, worst) = tail_measures(window, cfg.confidence);
+        let value_at_risk = to_amount(var * horizon);
+        let expected_shortfall = to_amount(es * horizon);
+        let model_margin = match cfg.measure {
+            RiskMeasure::ValueAtRisk => value_at_risk,
+            RiskMeasure::ExpectedShortfall => expected_shortfall,
+        };
+
+        let margin = match cfg.anti_procyclicality {
+            Some(AntiProcyclicality::Buffer { bps }) => {
+                i64::try_from(model_margin as i128 * (10_000 + bps as i128) / 10_000)
+                    .unwrap_or(i64::MAX)
+            }
+            Some(AntiProcyclicality::LongHistoryFloor) => {
+                let (v, e, _) = tail_measures(&losses, cfg.confidence);
+                let long = match cfg.measure {
+                    RiskMeasure::ValueAtRisk => v,
+                    RiskMeasure::ExpectedShortfall => e,
+                };
+                model_margin.max(to_amount(long * horizon))
+            }
+            None => model_margin,
+        };
+
+        Ok(VarResult {
+            account_id,
+            value_at_risk,
+            expected_shortfall,
+            worst_loss: to_amount(worst * horizon),
+            scenarios: window.len(),
+            model_margin,
+            margin: margin.max(cfg.margin_floor),
+        })
+    }
+
+    /// Compute portfolio margin for an account from net obligations, with
+    /// the same call shape as
+    /// [`MarginEngine::compute_portfolio_margin`](crate::margin::MarginEngine::compute_portfolio_margin).
+    ///
+    /// # Errors
+    ///
+    /// See [`compute`](Self::compute).
+    pub fn compute_portfolio_margin(
+        &self,
+        account_id: u64,
+        obligations: &[NetObligation],
+    ) -> Result<MarginRequirement, VarError> {
+        let positions = positions_from_obligations(account_id, obligations);
+        self.compute(account_id, &positions)
+            .map(|r| r.to_requirement())
+    }
+}
+
+impl MarginModel for HistoricalVarModel {
+    fn portfolio_margin(
+        &self,
+        account_id: u64,
+        obligations: &[NetObligation],
+    ) -> Result<MarginRequirement, MarginModelError> {
+        Ok(self.compute_portfolio_margin(account_id, obligations)?)
+    }
+}
+
+/// Net exposures of `account_id` implied by `obligations`: the receiver is
+/// long and the deliverer short `net_payment` of the symbol.
+#[must_use]
+pub fn positions_from_obligations(
+    account_id: u64,
+    obligations: &[NetObligation],
+) -> Vec<VarPosition> {
+    let mut exposures: BTreeMap<u64, i64> = BTreeMap::new();
+    for ob in obligations {
+        let signed = if ob.receiver_id == account_id {
+            ob.net_payment
+        } else if ob.deliverer_id == account_id {
+            ob.net_payment.saturating_neg()
+        } else {
+            continue;
+        };
+        let e = exposures.entry(ob.symbol_hash).or_insert(0);
+        *e = e.saturating_add(signed);
+    }
+    exposures
+        .into_iter()
+        .map(|(symbol_hash, exposure)| VarPosition {
+            symbol_hash,
+            exposure,
+        })
+        .collect()
+}
+
+/// Smallest scenario count with at least one observation in the tail.
+fn min_scenarios(confidence: f64) -> usize {
+    tail_count(1.0 / (1.0 - confidence))
+}
+
+/// `ceil(x)`, tolerant of floating-point error in `1 - confidence`.
+#[inline]
+fn tail_count(x: f64) -> usize {
+    (x - 1e-9).ceil() as usize
+}
+
+/// `(VaR, ES, worst loss)` of one-day `losses`, floored at zero.
+fn tail_measures(losses: &[f64], confidence: f64) -> (f64, f64, f64) {
+    if losses.is_empty() {
+        return (0.0, 0.0, 0.0);
+    }
+    let mut sorted = losses.to_vec();
+    sorted.sort_by(|a, b| b.total_cmp(a));
+    let tail = tail_count(sorted.len() as f64 * (1.0 - confidence)).clamp(1, sorted.len());
+    let var = sorted[tail - 1];
+    let es = sorted[..tail].iter().sum::<f64>() / tail as f64;
+    (var.max(0.0), es.max(0.0), sorted[0].max(0.0))
+}
+
+/// Filtered historical simulation: rescale each return by the ratio of the
+/// latest EWMA volatility to the volatility prevailing on its day.
+fn filter_returns(returns: &[f64], lambda: f64) -> Vec<f64> {
+    if returns.is_empty() {
+        return Vec::new();
+    }
+    let seed = returns.iter().map(|r| r * r).sum::<f64>() / returns.len() as f64;
+    let mut variance = Vec::with_capacity(returns.len());
+    let mut v = seed;
+    for r in returns {
+        variance.push(v);
+        v = lambda * v + (1.0 - lambda) * r * r;
+    }
+    let current = v.sqrt();
+    returns
+        .iter()
+        .zip(variance)
+        .map(|(r, var)| {
+            if var > 0.0 {
+                r * current / var.sqrt()
+            } else {
+                *r
+            }
+        })
+        .collect()
+}
+
+#[inline]
+fn to_amount(x: f64) -> i64 {
+    x.round() as i64
+}
+
+// ── Tests ──────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYM_A: u64 = 0xA;
+    const SYM_B: u64 = 0xB;
+
+    /// 100 returns: 95 of +0.1%, and −1%..−5% on the last five days.
+    fn returns() -> Vec<f64> {
+        let mut r = vec![0.001; 95];
+        r.extend([-0.01, -0.02, -0.03, -0.04, -0.05]);
+        r
+    }
+
+    fn config() -> VarConfig {
+        VarConfig {
+            confidence: 0.95,
+            holding_period_days: 1,
+            lookback: 100,
+            measure: RiskMeasure::ValueAtRisk,
+            ewma_lambda: None,
+            anti_procyclicality: None,
+            margin_floor: 0,
+        }
+    }
+
+    fn model(config: VarConfig) -> HistoricalVarModel {
+        let mut m = HistoricalVarModel::new(config);
+        m.set_returns(SYM_A, returns());
+        m.set_returns(SYM_B, returns());
+        m
+    }
+
+    fn long(symbol_hash: u64, exposure: i64) -> VarPosition {
+        VarPosition {
+            symbol_hash,
+            exposure,
+        }
+    }
+
+    #[test]
+    fn var_and_es_of_long_position() {
+        let r = model(config()).compute(1, &[long(SYM_A, 100_000)]).unwrap();
+        // Five tail observations: losses 1000..5000.
+        assert_eq!(r.scenarios, 100);
+        assert_eq!(r.value_at_risk, 1_000);
+        assert_eq!(r.expected_shortfall, 3_000);
+        assert_eq!(r.worst_loss, 5_000);
+        assert_eq!(r.margin, 1_000);
+    }
+
+    #[test]
+    fn expected_shortfall_measure_and_holding_period() {
+        let cfg = VarConfig {
+            measure: RiskMeasure::ExpectedShortfall,
+            holding_period_days: 4,
+            ..config()
+        };
+        let r = model(cfg).compute(1, &[long(SYM_A, 100_000)]).unwrap();
+        assert_eq!(r.expected_shortfall, 6_000);
+        assert_eq!(r.margin, 6_000);
+    }
+
+    #[test]
+    fn hedged_positions_offset() {
+        let r = model(config())
+            .compute(1, &[long(SYM_A, 100_000), long(SYM_B, -100_000)])
+            .unwrap();
+        assert_eq!(r.margin, 0);
+        // A short position loses on the up days.
+        let short = model(config())
+            .compute(1, &[long(SYM_A, -100_000)])
+            .unwrap();
+        assert_eq!(short.value_at_risk, 100);
+    }
+
+    #[test]
+    fn lookback_window_limits_history() {
+        let mut m = model(VarConfig {
+            lookback: 20,
+            ..config()
+        });
+        // Older crash outside the window.
+        let mut r = vec![-0.5; 10];
+        r.extend(vec![0.001; 90]);
+        m.set_returns(SYM_A, r);
+        let res = m.compute(1, &[long(SYM_A, 100_000)]).unwrap();
+        assert_eq!(res.scenarios, 20);
+        assert_eq!(res.value_at_risk, 0);
+
+        let floored = HistoricalVarModel {
+            config: VarConfig {
+                anti_procyclicality: Some(AntiProcyclicality::LongHistoryFloor),
+                ..m.config().clone()
+            },
+            ..m
+        };
+        let res = floored.compute(1, &[long(SYM_A, 100_000)]).unwrap();
+        assert_eq!(res.model_margin, 0);
+        assert_eq!(res.margin, 50_000);
+    }
+
+    #[test]
+    fn apc_buffer_and_floor() {
+        let cfg = VarConfig {
+            anti_procyclicality: Some(AntiProcyclicality::Buffer { bps: 2_500 }),
+            margin_floor: 10,
+            ..config()
+        };
+        let mut m = model(cfg);
+        assert_eq!(m.compute(1, &[long(SYM_A, 100_000)]).unwrap().margin, 1_250);
+        assert_eq!(m.compute(1, &[]).unwrap().margin, 10);
+
+        // The buffer saturates instead of wrapping.
+        m.set_returns(SYM_A, vec![-1.0; 100]);
+        let r = m.compute(1, &[long(SYM_A, i64::MAX)]).unwrap();
+        assert_eq!(r.model_margin, i64::MAX);
+        assert_eq!(r.margin, i64::MAX);
+    }
+
+    #[test]
+    fn invalid_lambda_and_returns_rejected() {
+        for lambda in [0.0, 1.0, -0.5, f64::NAN] {
+            let m = model(VarConfig {
+                ewma_lambda: Some(lambda),
+                ..config()
+            });
+            assert_eq!(
+                m.compute(1, &[long(SYM_A, 100)]),
+                Err(VarError::InvalidEwmaLambda)
+            );
+        }
+        for bad in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let mut m = model(config());
+            let mut r = returns();
+            r[40] = bad;
+            m.set_returns(SYM_B, r);
+            assert!(m.compute(1, &[long(SYM_A, 100)]).is_ok());
+            assert_eq!(
+                m.compute(1, &[long(SYM_A, 100), long(SYM_B, 100)]),
+                Err(VarError::NonFiniteReturn(SYM_B))
+            );
+        }
+    }
+
+    #[test]
+    fn volatility_scaling_amplifies_calm_history() {
+        let mut r = vec![0.001; 80];
+        r.extend([-0.004, 0.004, -0.004, 0.004, -0.004].repeat(4));
+        let mut plain = HistoricalVarModel::new(config());
+        plain.set_returns(SYM_A, r.clone());
+        let mut filtered = HistoricalVarModel::new(VarConfig {
+            ewma_lambda: Some(0.94),
+            ..config()
+        });
+        filtered.set_returns(SYM_A, r);
+        let p = plain.compute(1, &[long(SYM_A, 100_000)]).unwrap();
+        let f = filtered.compute(1, &[long(SYM_A, 100_000)]).unwrap();
+        assert_eq!(p.value_at_risk, 400);
+        assert!(f.value_at_risk > 0);
+        assert!(f.expected_shortfall > 0);
+    }
+
+    #[test]
+    fn errors() {
+        let m = model(config());
+        assert_eq!(
+            m.compute(1, &[long(0xC, 1)]),
+            Err(VarError::MissingSeries(0xC))
+        );
+        let mut short = HistoricalVarModel::new(config());
+        short.set_returns(SYM_A, vec![0.01; 10]);
+        assert_eq!(
+            short.compute(1, &[long(SYM_A, 1)]),
+            Err(VarError::InsufficientHistory {
+                required: 20,
+                available: 10,
+            })
+        );
+        let bad = HistoricalVarModel::new(VarConfig {
+            confidence: 1.0,
+            ..config()
+        });
+        assert_eq!(bad.compute(1, &[]), Err(VarError::InvalidConfidence));
+    }
+
+    #[test]
+    fn portfolio_margin_from_obligations() {
+        let obligations = [
+            NetObligation {
+                symbol_hash: SYM_A,
+                deliverer_id: 2,
+                receiver_id: 1,
+                net_quantity: 10,
+                net_payment: 100_000,
+                trade_count: 1,
+            },
+            NetObligation {
+                symbol_hash: SYM_B,
+                deliverer_id: 3,
+                receiver_id: 4,
+                net_quantity: 10,
+                net_payment: 100_000,
+                trade_count: 1,
+            },
+        ];
+        let positions = positions_from_obligations(1, &obligations);
+        assert_eq!(positions, vec![long(SYM_A, 100_000)]);
+        assert_eq!(
+            positions_from_obligations(2, &obligations),
+            vec![long(SYM_A, -100_000)]
+        );
+
+        let req = model(config())
+            .compute_portfolio_margin(1, &obligations)
+            .unwrap();
+        assert_eq!(req.account_id, 1);
+        assert_eq!(req.total_margin, 1_000);
+        assert_eq!(req.stress_margin, 3_000);
+
+        let as_model: &dyn MarginModel = &model(config());
+        assert_eq!(as_model.portfolio_margin(1, &obligations), Ok(req));
+        assert_eq!(
+            HistoricalVarModel::new(config()).portfolio_margin(3, &obligations[1..]),
+            Err(MarginModelError::Var(VarError::MissingSeries(SYM_B)))
+        );
+    }
+}
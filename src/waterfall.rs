@@ -7,7 +7,7 @@ use crate::fnv1a;
 // ── Types ──────────────────────────────────────────────────────────────
 
 /// The five layers of the default waterfall, applied in order.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[repr(u8)]
 pub enum WaterfallLayer {
     /// Layer 1: Defaulter's own margin deposit.
@@ -22,6 +22,17 @@ pub enum WaterfallLayer {
     CcpCapital = 4,
 }
 
+impl WaterfallLayer {
+    /// Every layer, in the order losses are absorbed.
+    pub const ALL: [Self; 5] = [
+        Self::DefaulterMargin,
+        Self::DefaulterFund,
+        Self::CcpFirstLoss,
+        Self::MembersFund,
+        Self::CcpCapital,
+    ];
+}
+
 /// Per-layer absorption result.
 #[derive(Debug, Clone)]
 pub struct LayerAbsorption {
@@ -76,6 +87,18 @@ pub struct WaterfallResult {
     pub content_hash: u64,
 }
 
+impl WaterfallResult {
+    /// Amount absorbed by each layer, indexed by `WaterfallLayer as usize`.
+    #[must_use]
+    pub fn absorbed_by_layer(&self) -> [i64; 5] {
+        let mut absorbed = [0; 5];
+        for layer in &self.layers {
+            absorbed[layer.layer as usize] += layer.absorbed;
+        }
+        absorbed
+    }
+}
+
 // ── Default Waterfall ──────────────────────────────────────────────────
 
 /// Five-layer default waterfall for loss absorption.